@@ -0,0 +1,276 @@
+//! Timestamped backups of files taken before they are overwritten
+//!
+//! Backups live in `<workspace_root>/logs/backups/` and are named
+//! `<stem>.<path hash>.<timestamp>.<ext>`, with the timestamp in
+//! milliseconds since the Unix epoch. The hash of the file's canonical path
+//! keeps files with the same name in different projects (every project's
+//! `main.tex`) apart.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::compiler::build::stable_hash;
+use crate::disk::ensure_disk_space;
+use crate::file_ops::{canonical_path, read_file, write_file};
+use crate::workspace::get_backups_dir;
+
+/// Number of backups kept per file unless configured otherwise
+pub const DEFAULT_MAX_BACKUPS: usize = 20;
+
+/// Back up `path` before it is overwritten, keeping at most `max_backups`
+pub fn create_backup(path: &Path, max_backups: usize) -> Result<Option<PathBuf>, String> {
+    let dir = get_backups_dir().ok_or("Could not determine backups directory")?;
+    create_backup_in(&dir, path, now_millis(), max_backups)
+}
+
+/// List the backup timestamps available for `path`, newest first
+pub fn list_backups(path: &Path) -> Result<Vec<u64>, String> {
+    let dir = get_backups_dir().ok_or("Could not determine backups directory")?;
+    list_backups_in(&dir, path)
+}
+
+/// Restore the backup of `path` taken at `timestamp` and return its content
+///
+/// The current content is backed up first so a restore can be undone.
+pub fn restore_backup(path: &Path, timestamp: u64, max_backups: usize) -> Result<String, String> {
+    let dir = get_backups_dir().ok_or("Could not determine backups directory")?;
+    restore_backup_in(&dir, path, timestamp, now_millis(), max_backups)
+}
+
+/// Content of the backup of `path` taken at `timestamp`
+pub fn read_backup(path: &Path, timestamp: u64) -> Result<String, String> {
+    let dir = get_backups_dir().ok_or("Could not determine backups directory")?;
+    read_backup_in(&dir, path, timestamp)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Split a file name into the prefix and extension used in backup names
+///
+/// The prefix is the stem followed by a hash of the canonical path.
+fn name_parts(path: &Path) -> (String, String) {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Untitled".to_string());
+    let canonical = canonical_path(path).unwrap_or_else(|_| path.to_path_buf());
+    let hash = stable_hash(canonical.to_string_lossy().as_bytes());
+    let stem = format!("{}.{:016x}", stem, hash);
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| "tex".to_string());
+    (stem, ext)
+}
+
+/// Path of the backup of `path` taken at `timestamp`
+fn backup_path(dir: &Path, path: &Path, timestamp: u64) -> PathBuf {
+    let (stem, ext) = name_parts(path);
+    dir.join(format!("{}.{}.{}", stem, timestamp, ext))
+}
+
+/// Parse the timestamp out of a backup file name belonging to `path`
+fn backup_timestamp(file_name: &str, path: &Path) -> Option<u64> {
+    let (stem, ext) = name_parts(path);
+    file_name
+        .strip_prefix(&format!("{}.", stem))?
+        .strip_suffix(&format!(".{}", ext))?
+        .parse()
+        .ok()
+}
+
+/// Back up `path` into `dir` with the given timestamp, then prune old backups
+///
+/// Returns `None` when there is nothing to back up yet.
+pub fn create_backup_in(
+    dir: &Path,
+    path: &Path,
+    timestamp: u64,
+    max_backups: usize,
+) -> Result<Option<PathBuf>, String> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create backups directory: {}", e))?;
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    ensure_disk_space(dir, size)?;
+
+    let backup = backup_path(dir, path, timestamp);
+    fs::copy(path, &backup).map_err(|e| format!("Failed to create backup: {}", e))?;
+    prune_backups_in(dir, path, max_backups)?;
+    Ok(Some(backup))
+}
+
+/// List the backup timestamps for `path` in `dir`, newest first
+pub fn list_backups_in(dir: &Path, path: &Path) -> Result<Vec<u64>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read backups: {}", e))?;
+
+    let mut timestamps: Vec<u64> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| backup_timestamp(&entry.file_name().to_string_lossy(), path))
+        .collect();
+    timestamps.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(timestamps)
+}
+
+/// Delete all but the newest `max_backups` backups of `path`
+pub fn prune_backups_in(dir: &Path, path: &Path, max_backups: usize) -> Result<(), String> {
+    for timestamp in list_backups_in(dir, path)?.into_iter().skip(max_backups) {
+        fs::remove_file(backup_path(dir, path, timestamp))
+            .map_err(|e| format!("Failed to remove old backup: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Restore the backup of `path` taken at `timestamp` from `dir`
+pub fn restore_backup_in(
+    dir: &Path,
+    path: &Path,
+    timestamp: u64,
+    now: u64,
+    max_backups: usize,
+) -> Result<String, String> {
+    let content = read_backup_in(dir, path, timestamp)?;
+
+    // Keep the backup being restored even if it is the oldest one
+    create_backup_in(dir, path, now, max_backups.max(1) + 1)?;
+    write_file(path, &content)?;
+    Ok(content)
+}
+
+pub fn read_backup_in(dir: &Path, path: &Path, timestamp: u64) -> Result<String, String> {
+    let backup = backup_path(dir, path, timestamp);
+    if !backup.is_file() {
+        return Err(format!("No backup found for timestamp {}", timestamp));
+    }
+    read_file(&backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, PathBuf, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        let backups = temp_dir.path().join("backups");
+        (temp_dir, path, backups)
+    }
+
+    #[test]
+    fn test_backup_skips_missing_file() {
+        let (_temp_dir, path, backups) = setup();
+        assert_eq!(create_backup_in(&backups, &path, 1, 20).unwrap(), None);
+        assert!(!backups.exists());
+    }
+
+    #[test]
+    fn test_backup_copies_current_content() {
+        let (_temp_dir, path, backups) = setup();
+        fs::write(&path, "version 1").unwrap();
+
+        let backup = create_backup_in(&backups, &path, 1000, 20)
+            .unwrap()
+            .unwrap();
+
+        let name = backup.file_name().unwrap().to_string_lossy().to_string();
+        let (stem, ext) = name_parts(&path);
+        assert_eq!(name, format!("{}.1000.{}", stem, ext));
+        assert!(name.starts_with("resume.") && name.ends_with(".1000.tex"), "{}", name);
+        assert_eq!(fs::read_to_string(backup).unwrap(), "version 1");
+    }
+
+    #[test]
+    fn test_backups_listed_newest_first() {
+        let (_temp_dir, path, backups) = setup();
+        fs::write(&path, "content").unwrap();
+        for timestamp in [300, 100, 200] {
+            create_backup_in(&backups, &path, timestamp, 20).unwrap();
+        }
+        // Backups of other files are ignored
+        fs::write(backups.join("cover_letter.150.tex"), "other").unwrap();
+
+        assert_eq!(
+            list_backups_in(&backups, &path).unwrap(),
+            vec![300, 200, 100]
+        );
+    }
+
+    #[test]
+    fn test_rotation_prunes_oldest() {
+        let (_temp_dir, path, backups) = setup();
+        fs::write(&path, "content").unwrap();
+        for timestamp in 1..=5 {
+            create_backup_in(&backups, &path, timestamp, 3).unwrap();
+        }
+
+        assert_eq!(list_backups_in(&backups, &path).unwrap(), vec![5, 4, 3]);
+        assert!(!backups.join("resume.1.tex").exists());
+    }
+
+    #[test]
+    fn test_restore_backup() {
+        let (_temp_dir, path, backups) = setup();
+        fs::write(&path, "version 1").unwrap();
+        create_backup_in(&backups, &path, 100, 20).unwrap();
+        fs::write(&path, "version 2").unwrap();
+
+        let content = restore_backup_in(&backups, &path, 100, 200, 20).unwrap();
+
+        assert_eq!(content, "version 1");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "version 1");
+        // The overwritten version was backed up too
+        assert_eq!(list_backups_in(&backups, &path).unwrap(), vec![200, 100]);
+    }
+
+    #[test]
+    fn test_restore_missing_backup_fails() {
+        let (_temp_dir, path, backups) = setup();
+        fs::write(&path, "content").unwrap();
+        assert!(restore_backup_in(&backups, &path, 42, 100, 20).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "content");
+    }
+
+    #[test]
+    fn test_same_name_in_different_folders_kept_apart() {
+        let (temp_dir, path, backups) = setup();
+        let other_dir = temp_dir.path().join("other");
+        fs::create_dir(&other_dir).unwrap();
+        let other = other_dir.join("resume.tex");
+        fs::write(&path, "mine").unwrap();
+        fs::write(&other, "theirs").unwrap();
+
+        create_backup_in(&backups, &path, 100, 1).unwrap();
+        create_backup_in(&backups, &other, 200, 1).unwrap();
+        create_backup_in(&backups, &other, 300, 1).unwrap();
+
+        // Pruning the other file's backups leaves this one's alone
+        assert_eq!(list_backups_in(&backups, &path).unwrap(), vec![100]);
+        assert_eq!(list_backups_in(&backups, &other).unwrap(), vec![300]);
+        assert!(read_backup_in(&backups, &path, 300).is_err());
+        assert_eq!(read_backup_in(&backups, &path, 100).unwrap(), "mine");
+    }
+
+    #[test]
+    fn test_read_backup_leaves_file_alone() {
+        let (_temp_dir, path, backups) = setup();
+        fs::write(&path, "version 1").unwrap();
+        create_backup_in(&backups, &path, 100, 20).unwrap();
+        fs::write(&path, "version 2").unwrap();
+
+        assert_eq!(read_backup_in(&backups, &path, 100).unwrap(), "version 1");
+        assert!(read_backup_in(&backups, &path, 42).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "version 2");
+        assert_eq!(list_backups_in(&backups, &path).unwrap(), vec![100]);
+    }
+}
@@ -1,83 +1,494 @@
 //! Tauri command handlers
 
-use std::path::PathBuf;
-use tauri::State;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::ipc::Response;
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_opener::OpenerExt;
 
-use crate::compiler::{check_requirements, compile_latex_async, RequirementsStatus};
-use crate::file_ops::{get_file_name, read_file, write_file};
+use crate::backups::{create_backup, list_backups, read_backup, restore_backup};
+use crate::compiler::debounce::{DebouncedBuild, StartDecision, DEBOUNCE_DELAY};
+use crate::compiler::history::{self, BuildRecord};
+use crate::compiler::synctex::{find_synctex_for, PdfLocation, SourceLocation, SyncTex};
+use crate::compiler::{
+    build_log_path, check_requirements_async, compile_content_async, compile_latex_async,
+    compile_snippet_async, enrich_diagnostics, ensure_writable_dir, get_build_dir,
+    list_artifacts_in, path_warning, pdflatex, probe_engines, run_selftest, validate_extra_args,
+    ArtifactInfo, BuildProgress, BuildResult, CompileOptions, Diagnostic, EngineResult,
+    InteractionMode, LatexEngine, RequirementsStatus, SelftestReport,
+};
+use crate::diff::{diff_lines, DiffHunk};
+use crate::disk::{disk_status, DiskStatus};
+use crate::file_ops::{
+    canonical_path, check_path_allowed, check_writable, file_encoding, is_tex_file,
+    line_ending_for, normalized_path, read_file_with_encoding, write_file,
+    write_file_with_encoding,
+};
+use crate::latex::outline::{self, OutlineEntry};
+use crate::latex::class::{self, ClassInfo};
+use crate::latex::format::format_whitespace;
+use crate::latex::keywords::{self, MatchReport};
+use crate::latex::links::{self, LinkStatus};
+use crate::latex::{escape, export, includes, lint};
+use crate::line_endings::normalize_line_endings;
 use crate::pdf;
+use crate::projects::{self, Project};
+use crate::reveal;
+use crate::session;
+use crate::settings::{load_settings, save_settings, Settings};
 use crate::state::AppState;
+use crate::templates::{self, NewFileKind, TemplateInfo};
 use crate::types::FileInfo;
-use crate::workspace::init_workspace;
+use crate::watcher::{DirWatcher, FileWatcher};
+use crate::workspace::{
+    get_logs_dir, get_templates_dir, get_workspace_root, init_workspace, repair_workspace,
+    RepairReport,
+};
+
+/// Event emitted when templates are added to, removed from or changed in
+/// the templates folder
+pub const TEMPLATES_CHANGED_EVENT: &str = "templates-changed";
 
 /// Initialize the workspace and return info about it
+///
+/// Also starts watching the templates folder; changes are reported with a
+/// `templates-changed` event.
+#[tauri::command]
+pub fn workspace_init(state: State<AppState>, app: AppHandle) -> Result<String, String> {
+    let path = init_workspace().map_err(|e| format!("Failed to initialize workspace: {}", e))?;
+    watch_templates(&state, &app)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Watch the templates folder unless it is already watched
+fn watch_templates(state: &AppState, app: &AppHandle) -> Result<(), String> {
+    let Some(dir) = get_templates_dir() else {
+        return Ok(());
+    };
+    let mut watcher = state.templates_watcher.lock().map_err(|e| e.to_string())?;
+    if watcher.as_ref().map(|w| w.path()) == Some(dir.as_path()) {
+        return Ok(());
+    }
+    let app = app.clone();
+    *watcher = Some(DirWatcher::watch(&dir, move |_| {
+        let _ = app.emit(TEMPLATES_CHANGED_EVENT, ());
+    }));
+    Ok(())
+}
+
+/// Recreate missing workspace folders and reset unreadable settings
+///
+/// Returns a summary of what was fixed.
 #[tauri::command]
-pub fn workspace_init() -> Result<String, String> {
-    match init_workspace() {
-        Ok(path) => Ok(path.to_string_lossy().to_string()),
-        Err(e) => Err(format!("Failed to initialize workspace: {}", e)),
+pub fn workspace_repair() -> Result<RepairReport, String> {
+    repair_workspace()
+}
+
+/// Free space on the volume holding the workspace
+#[tauri::command]
+pub fn workspace_disk_status() -> Result<DiskStatus, String> {
+    let root = get_workspace_root().ok_or("Could not determine workspace directory")?;
+    disk_status(&root)
+}
+
+/// Event emitted with the file's path when another program changes it
+pub const FILE_CHANGED_EVENT: &str = "file-changed-externally";
+
+/// Watch `path` for external changes instead of the previously watched file
+///
+/// `None` stops watching.
+fn watch_file(path: Option<&Path>, state: &AppState, app: &AppHandle) -> Result<(), String> {
+    let mut watcher = state.watcher.lock().map_err(|e| e.to_string())?;
+    if watcher.as_ref().map(|w| w.path()) == path {
+        return Ok(());
     }
+    *watcher = path.map(|path| {
+        let app = app.clone();
+        FileWatcher::watch(path, move |changed| {
+            let _ = app.emit(FILE_CHANGED_EVENT, changed.to_string_lossy().to_string());
+        })
+    });
+    Ok(())
+}
+
+/// Tell the watcher about content the app is about to write
+fn expect_write(path: &Path, content: &str, state: &AppState) -> Result<(), String> {
+    if let Some(watcher) = state.watcher.lock().map_err(|e| e.to_string())?.as_ref() {
+        watcher.expect_content(path, content);
+    }
+    Ok(())
 }
 
 /// Open a file and return its contents along with file info
+///
+/// The file is watched while it is active; external changes are reported
+/// with a `file-changed-externally` event.
 #[tauri::command]
-pub fn file_open(path: String, state: State<AppState>) -> Result<FileInfo, String> {
-    let path_buf = PathBuf::from(&path);
-    let content = read_file(&path_buf)?;
-    let name = get_file_name(&path_buf);
+pub fn file_open(path: String, state: State<AppState>, app: AppHandle) -> Result<FileInfo, String> {
+    let path_buf = normalized_path(Path::new(&path))?;
+    ensure_path_allowed(&path_buf)?;
+    let (content, encoding) = read_file_with_encoding(&path_buf)?;
+
+    // Open in a tab (or switch to its existing tab, keeping its edits)
+    state.open_loaded_document(&path_buf, &content)?;
+    watch_file(Some(&path_buf), &state, &app)?;
+    // Not being able to reopen it next time shouldn't fail the open
+    let _ = session::record_active_file(&path_buf);
+
+    Ok(FileInfo::new(&path_buf, content, encoding))
+}
 
-    // Update current file state
-    let mut current = state.current_file.lock().map_err(|e| e.to_string())?;
-    *current = Some(path_buf.clone());
+/// With `restrict_file_access` on, fail unless `path` is inside the
+/// workspace or a directory granted with `file_allow_directory`
+fn ensure_path_allowed(path: &Path) -> Result<(), String> {
+    let settings = load_settings()?;
+    if !settings.restrict_file_access {
+        return Ok(());
+    }
+    let mut allowed: Vec<PathBuf> = get_workspace_root().into_iter().collect();
+    allowed.extend(settings.allowed_directories.iter().map(PathBuf::from));
+    check_path_allowed(path, &allowed).map(|_| ())
+}
 
-    Ok(FileInfo {
-        path,
-        name,
-        content,
-    })
+/// A path sent by the frontend, made absolute and checked with `ensure_path_allowed`
+fn allowed_path(path: &str) -> Result<PathBuf, String> {
+    let path = normalized_path(Path::new(path))?;
+    ensure_path_allowed(&path)?;
+    Ok(path)
 }
 
-/// Save content to the current file
+/// Grant file operations access to a directory outside the workspace
+///
+/// Meant to be called with a folder the user picked in a dialog. The
+/// directory is stored canonicalized in the settings.
 #[tauri::command]
-pub fn file_save(content: String, state: State<AppState>) -> Result<(), String> {
-    let current = state.current_file.lock().map_err(|e| e.to_string())?;
-    let path = current.as_ref().ok_or("No file is currently open")?;
+pub fn file_allow_directory(path: String) -> Result<(), String> {
+    let dir = canonical_path(Path::new(&path))?;
+    if !dir.is_dir() {
+        return Err("Path is not a folder".to_string());
+    }
+    let dir = dir.to_string_lossy().to_string();
+    let mut settings = load_settings()?;
+    if !settings.allowed_directories.contains(&dir) {
+        settings.allowed_directories.push(dir);
+        save_settings(&settings)?;
+    }
+    Ok(())
+}
 
-    write_file(path, &content)
+/// Back up a file about to be overwritten, if backups are enabled
+fn backup_before_save(path: &Path) {
+    let settings = load_settings().unwrap_or_default();
+    if settings.keep_backups {
+        // A failed backup shouldn't stop the user's work from being saved
+        let _ = create_backup(path, settings.max_backups);
+    }
 }
 
-/// Save content to a new file path
+/// Apply the saving settings (formatting, line endings) to content about to
+/// be written to `path`
+fn prepare_for_save(path: &Path, content: &str) -> String {
+    let settings = load_settings().unwrap_or_default();
+    let content = if settings.format_on_save {
+        format_whitespace(content)
+    } else {
+        content.to_string()
+    };
+    normalize_line_endings(&content, line_ending_for(path, settings.line_ending))
+}
+
+/// Save content to the active file
+///
+/// Returns the content as saved, which differs from `content` when format
+/// on save or line ending conversion changed it.
 #[tauri::command]
-pub fn file_save_as(path: String, content: String, state: State<AppState>) -> Result<FileInfo, String> {
-    let path_buf = PathBuf::from(&path);
-    write_file(&path_buf, &content)?;
+pub fn file_save(content: String, state: State<AppState>) -> Result<String, String> {
+    let path = state.active_path()?.ok_or("No file is currently open")?;
+    let content = prepare_for_save(&path, &content);
 
-    let name = get_file_name(&path_buf);
+    backup_before_save(&path);
+    expect_write(&path, &content, &state)?;
+    write_file(&path, &content)?;
+    state.mark_active_saved(&content)?;
+    Ok(content)
+}
 
-    // Update current file state
-    let mut current = state.current_file.lock().map_err(|e| e.to_string())?;
-    *current = Some(path_buf);
+/// Record the editor's current content for the active file
+///
+/// Returns the file's revision, which increases with every change.
+#[tauri::command]
+pub fn doc_update(content: String, state: State<AppState>) -> Result<u64, String> {
+    state.update_active(&content)
+}
+
+/// Whether the active file has changes since it was last saved or loaded
+///
+/// Compares the revisions recorded by `doc_update` and `file_save`, so
+/// undoing back to the saved text counts as no change.
+#[tauri::command]
+pub fn file_has_unsaved_changes(state: State<AppState>) -> Result<bool, String> {
+    state.active_has_unsaved_changes()
+}
 
-    Ok(FileInfo {
-        path,
-        name,
-        content,
-    })
+/// Line diff from the active file on disk to the editor's `content`
+///
+/// Empty when nothing changed.
+#[tauri::command]
+pub fn file_diff(content: String, state: State<AppState>) -> Result<Vec<DiffHunk>, String> {
+    let path = state.active_path()?.ok_or("No file is currently open")?;
+    let (saved, _) = read_file_with_encoding(&path)?;
+    Ok(diff_lines(&saved, &content))
+}
+
+/// Save content to a new file path
+#[tauri::command]
+pub fn file_save_as(
+    path: String,
+    content: String,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<FileInfo, String> {
+    let path_buf = normalized_path(Path::new(&path))?;
+    ensure_path_allowed(&path_buf)?;
+    state.check_replace_active(&path_buf)?;
+    let content = prepare_for_save(&path_buf, &content);
+    backup_before_save(&path_buf);
+    // Overwriting an existing file keeps its encoding; new files are UTF-8
+    let encoding = file_encoding(&path_buf);
+    write_file_with_encoding(&path_buf, &content, encoding)?;
+
+    // The active tab now refers to the new path
+    state.replace_active(&path_buf)?;
+    state.mark_active_saved(&content)?;
+    watch_file(Some(&path_buf), &state, &app)?;
+    let _ = session::record_active_file(&path_buf);
+
+    Ok(FileInfo::new(&path_buf, content, encoding))
 }
 
 /// Get info about the currently open file
 #[tauri::command]
 pub fn file_get_current(state: State<AppState>) -> Option<String> {
-    let current = state.current_file.lock().ok()?;
-    current.as_ref().map(|p| p.to_string_lossy().to_string())
+    let current = state.active_path().ok()??;
+    Some(current.to_string_lossy().to_string())
 }
 
-/// Compile the current LaTeX file to PDF
+/// Show a file (e.g. the built PDF) in Explorer, Finder or the Linux file manager
+#[tauri::command]
+pub fn reveal_in_file_manager(path: String) -> Result<(), String> {
+    reveal::reveal_in_file_manager(&allowed_path(&path)?)
+}
+
+/// Reopen the file that was active when the app last closed
+///
+/// Returns `None` if there is nothing to restore, e.g. the file was moved
+/// or deleted since.
+#[tauri::command]
+pub fn session_restore(state: State<AppState>, app: AppHandle) -> Option<FileInfo> {
+    let path = session::restorable_file()?;
+    file_open(path.to_string_lossy().to_string(), state, app).ok()
+}
+
+/// Create a new project in the workspace
+#[tauri::command]
+pub fn project_create(name: String) -> Result<Project, String> {
+    projects::create_project(&name)
+}
+
+/// List the projects in the workspace
+#[tauri::command]
+pub fn projects_list() -> Result<Vec<Project>, String> {
+    projects::list_projects()
+}
+
+/// Open a project's main file in a tab
+#[tauri::command]
+pub fn project_open(
+    name: String,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<FileInfo, String> {
+    let project = projects::get_project(&name)?;
+    file_open(
+        project.main_path().to_string_lossy().to_string(),
+        state,
+        app,
+    )
+}
+
+/// Set the name of the PDF a project's builds deliver, e.g. `Jane_Doe_Resume`;
+/// `None` goes back to naming it after the main file
+#[tauri::command]
+pub fn project_set_output_name(
+    name: String,
+    output_name: Option<String>,
+) -> Result<Project, String> {
+    projects::set_output_name(&name, output_name)
+}
+
+/// Find the main file of the project in `dir`, if it has one
+#[tauri::command]
+pub fn project_detect_main(dir: String) -> Option<String> {
+    let dir = allowed_path(&dir).ok()?;
+    projects::detect_main(&dir).map(|path| path.to_string_lossy().to_string())
+}
+
+/// List the templates available to start a project from
+#[tauri::command]
+pub fn templates_list() -> Result<Vec<TemplateInfo>, String> {
+    templates::list_templates()
+}
+
+/// Create a project named `dest` from a template, filling in its placeholders
+#[tauri::command]
+pub fn template_instantiate(
+    template: String,
+    dest: String,
+    vars: HashMap<String, String>,
+) -> Result<Project, String> {
+    templates::instantiate_template(&template, &dest, &vars)
+}
+
+/// Escape LaTeX's special characters in user-entered text
+#[tauri::command]
+pub fn latex_escape(text: String) -> String {
+    escape::latex_escape(&text)
+}
+
+/// Turn text escaped by `latex_escape` back into what the user typed
+#[tauri::command]
+pub fn latex_unescape(text: String) -> String {
+    escape::latex_unescape(&text)
+}
+
+/// List the placeholders a template declares
 #[tauri::command]
-pub async fn build_compile(state: State<'_, AppState>) -> Result<crate::compiler::BuildResult, String> {
-    let tex_path = {
-        let current = state.current_file.lock().map_err(|e| e.to_string())?;
-        current.as_ref().ok_or("No file is currently open")?.clone()
+pub fn template_placeholders(template: String) -> Result<Vec<String>, String> {
+    templates::template_placeholders(&template)
+}
+
+/// Starting content for a new, unsaved file of the given kind
+#[tauri::command]
+pub fn file_new(kind: NewFileKind) -> String {
+    templates::starter_document(kind).to_string()
+}
+
+/// Check whether a file can be saved before trying to
+///
+/// Fails with a reason such as "File is read-only" or "File is locked by
+/// another program".
+#[tauri::command]
+pub fn file_is_writable(path: String) -> Result<(), String> {
+    check_writable(&allowed_path(&path)?)
+}
+
+/// Re-read the active file from disk, e.g. after an external change
+#[tauri::command]
+pub fn file_reload(state: State<AppState>) -> Result<FileInfo, String> {
+    let path_buf = state.active_path()?.ok_or("No file is currently open")?;
+    let (content, encoding) = read_file_with_encoding(&path_buf)?;
+    expect_write(&path_buf, &content, &state)?;
+    state.mark_active_saved(&content)?;
+
+    Ok(FileInfo::new(&path_buf, content, encoding))
+}
+
+/// Switch to the tab at `index` and return its file
+#[tauri::command]
+pub fn tab_switch(
+    index: usize,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<FileInfo, String> {
+    let path_buf = state.switch_tab(index)?;
+    watch_file(Some(&path_buf), &state, &app)?;
+    let (content, encoding) = read_file_with_encoding(&path_buf)?;
+
+    Ok(FileInfo::new(&path_buf, content, encoding))
+}
+
+/// Close the tab at `index`; returns the path of the tab active afterwards
+#[tauri::command]
+pub fn tab_close(
+    index: usize,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<Option<String>, String> {
+    let active = state.close_tab(index)?;
+    watch_file(active.as_deref(), &state, &app)?;
+    Ok(active.map(|p| p.to_string_lossy().to_string()))
+}
+
+/// List backup timestamps (ms since the Unix epoch) for a file, newest first
+#[tauri::command]
+pub fn backups_list(path: String) -> Result<Vec<u64>, String> {
+    list_backups(&allowed_path(&path)?)
+}
+
+/// Restore a file from the backup taken at `timestamp`
+///
+/// Restoring the active file counts as saving it, not as an external change.
+#[tauri::command]
+pub fn backup_restore(
+    path: String,
+    timestamp: u64,
+    state: State<AppState>,
+) -> Result<FileInfo, String> {
+    let path_buf = normalized_path(Path::new(&path))?;
+    ensure_path_allowed(&path_buf)?;
+    let settings = load_settings().unwrap_or_default();
+
+    expect_write(&path_buf, &read_backup(&path_buf, timestamp)?, &state)?;
+    let content = restore_backup(&path_buf, timestamp, settings.max_backups)?;
+    if state.active_path()?.as_deref() == Some(path_buf.as_path()) {
+        state.mark_active_saved(&content)?;
+    }
+
+    Ok(FileInfo::new(&path_buf, content, file_encoding(&path_buf)))
+}
+
+/// Parse the optional engine name sent by the frontend, defaulting to the
+/// engine in settings
+fn parse_engine(engine: Option<String>) -> Result<LatexEngine, String> {
+    match engine {
+        Some(name) => name.parse::<LatexEngine>(),
+        None => Ok(load_settings()?.engine),
+    }
+}
+
+/// Event emitted for each line of engine output during a build
+pub const BUILD_PROGRESS_EVENT: &str = "build-progress";
+
+/// Callback forwarding build output to the frontend as `build-progress` events
+fn progress_emitter(app: &AppHandle) -> impl Fn(BuildProgress) {
+    let app = app.clone();
+    move |progress: BuildProgress| {
+        let _ = app.emit(BUILD_PROGRESS_EVENT, progress);
+    }
+}
+
+/// Compile the active file, registering the build so it can be cancelled
+/// and streaming its output as `build-progress` events
+///
+/// Fails if another build is already running.
+async fn run_build(
+    engine: LatexEngine,
+    draft: bool,
+    use_main: bool,
+    interaction: InteractionMode,
+    output_name: Option<String>,
+    state: &AppState,
+    app: &AppHandle,
+) -> Result<BuildResult, String> {
+    let tex_path = state.active_path()?.ok_or("No file is currently open")?;
+    // pdflatex gives a cryptic failure for anything that isn't LaTeX source
+    if !is_tex_file(&tex_path) {
+        return Err("Current file is not a LaTeX file (.tex, .ltx or .latex)".to_string());
+    }
+    let tex_path = if use_main {
+        projects::build_target(&tex_path)
+    } else {
+        tex_path
     };
 
     // Use the same directory as the tex file for output
@@ -86,24 +497,493 @@ pub async fn build_compile(state: State<'_, AppState>) -> Result<crate::compiler
         .ok_or("Cannot determine output directory")?
         .to_path_buf();
 
-    Ok(compile_latex_async(&tex_path, &output_dir).await)
+    let build_dir = state.build_dir.lock().map_err(|e| e.to_string())?.clone();
+    let settings = load_settings().unwrap_or_default();
+
+    let options = CompileOptions {
+        engine,
+        build_dir,
+        draft,
+        logs_dir: get_logs_dir(),
+        page_limit: settings.page_limit,
+        allow_shell_escape: settings.allow_shell_escape,
+        extra_args: settings.extra_args,
+        install_retries: settings.install_retries,
+        interaction,
+        reproducible: settings.reproducible_builds,
+        in_place: !settings.keep_project_clean,
+        output_name: output_name
+            .or_else(|| projects::project_for(&tex_path).and_then(|p| p.metadata.output_name)),
+        ..Default::default()
+    };
+
+    let path_warning = path_warning(&tex_path, &options);
+    let build = state.compilation.try_begin()?;
+    let emit_progress = progress_emitter(app);
+    let mut result = compile_latex_async(
+        &tex_path,
+        &output_dir,
+        &options,
+        Some(build.token()),
+        Some(&emit_progress),
+    )
+    .await;
+    result.diagnostics.extend(path_warning);
+
+    Ok(result)
+}
+
+/// Compile the current LaTeX file to PDF
+///
+/// `engine` is one of "pdflatex", "xelatex" or "lualatex"; defaults to the
+/// engine in settings. `draft` builds a quick preview in which images are
+/// drawn as empty boxes; build again without it for the final PDF.
+/// With `use_main`, a file without a preamble builds its project's main
+/// file instead. `interaction` is one of "nonstopmode" (the default),
+/// "batchmode", "scrollmode" or "errorstopmode". `output_name` names the
+/// delivered PDF, defaulting to the project's output name, then the file's.
+#[tauri::command]
+pub async fn build_compile(
+    engine: Option<String>,
+    draft: Option<bool>,
+    use_main: Option<bool>,
+    interaction: Option<String>,
+    output_name: Option<String>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<BuildResult, String> {
+    let engine = parse_engine(engine)?;
+    let interaction = match interaction {
+        Some(mode) => mode.parse::<InteractionMode>()?,
+        None => InteractionMode::default(),
+    };
+    let draft = draft.unwrap_or(false);
+    let use_main = use_main.unwrap_or(false);
+    run_build(engine, draft, use_main, interaction, output_name, &state, &app).await
+}
+
+/// Compile LaTeX content that hasn't been saved, e.g. a new draft
+///
+/// The result's `pdf_path` points at a scratch PDF in the build directory.
+#[tauri::command]
+pub async fn build_compile_content(
+    content: String,
+    engine: Option<String>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<BuildResult, String> {
+    let engine = parse_engine(engine)?;
+    let build_dir = state.build_dir.lock().map_err(|e| e.to_string())?.clone();
+    let settings = load_settings().unwrap_or_default();
+    let options = CompileOptions {
+        engine,
+        build_dir,
+        logs_dir: get_logs_dir(),
+        page_limit: settings.page_limit,
+        allow_shell_escape: settings.allow_shell_escape,
+        extra_args: settings.extra_args,
+        install_retries: settings.install_retries,
+        ..Default::default()
+    };
+
+    let build = state.compilation.try_begin()?;
+    let emit_progress = progress_emitter(&app);
+    Ok(compile_content_async(&content, &options, Some(build.token()), Some(&emit_progress)).await)
+}
+
+/// Compile a fragment of the document, e.g. a table, without the rest of it
+///
+/// `body` is wrapped in `preamble`, or a minimal `article` preamble when
+/// none is given. The result's `pdf_path` points at a scratch PDF in the
+/// build directory.
+#[tauri::command]
+pub async fn build_compile_snippet(
+    preamble: Option<String>,
+    body: String,
+    engine: Option<String>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<BuildResult, String> {
+    let engine = parse_engine(engine)?;
+    let build_dir = state.build_dir.lock().map_err(|e| e.to_string())?.clone();
+    let settings = load_settings().unwrap_or_default();
+    let options = CompileOptions {
+        engine,
+        build_dir,
+        allow_shell_escape: settings.allow_shell_escape,
+        extra_args: settings.extra_args,
+        install_retries: settings.install_retries,
+        ..Default::default()
+    };
+
+    let build = state.compilation.try_begin()?;
+    let emit_progress = progress_emitter(&app);
+    let result = compile_snippet_async(
+        preamble.as_deref(),
+        &body,
+        &options,
+        Some(build.token()),
+        Some(&emit_progress),
+    )
+    .await;
+    Ok(result)
+}
+
+/// Check that this machine can build a document, beyond finding the engine
+///
+/// Compiles a small built-in document in a temporary build folder, which
+/// is removed afterwards.
+#[tauri::command]
+pub async fn build_selftest(engine: Option<String>) -> Result<SelftestReport, String> {
+    let engine = parse_engine(engine)?;
+    let build_dir = get_build_dir().join("selftest");
+    let report = run_selftest(engine, &build_dir).await;
+    let _ = std::fs::remove_dir_all(&build_dir);
+    Ok(report)
+}
+
+/// Build `content` with every installed engine at once to see which ones work
+///
+/// Meant for templates that don't say which engine they need. Each engine
+/// builds in its own folder, removed afterwards.
+#[tauri::command]
+pub async fn build_probe_engines(
+    content: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<EngineResult>, String> {
+    let build_dir = state.build_dir.lock().map_err(|e| e.to_string())?.clone();
+    let probe_dir = build_dir.unwrap_or_else(get_build_dir).join("probe");
+
+    let build = state.compilation.try_begin()?;
+    let results = probe_engines(&content, &probe_dir, Some(build.token())).await;
+    let _ = std::fs::remove_dir_all(&probe_dir);
+    Ok(results)
+}
+
+/// Files in the build directory left by building documents named `tex_stem`,
+/// largest first
+#[tauri::command]
+pub fn build_artifacts(
+    tex_stem: String,
+    state: State<AppState>,
+) -> Result<Vec<ArtifactInfo>, String> {
+    let build_dir = state.build_dir.lock().map_err(|e| e.to_string())?.clone();
+    list_artifacts_in(&build_dir.unwrap_or_else(get_build_dir), &tex_stem)
+}
+
+/// Open the engine's `.log` for the document at `path` in the default app
+#[tauri::command]
+pub fn build_open_log(path: String, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let tex_path = allowed_path(&path)?;
+    let build_dir = state.build_dir.lock().map_err(|e| e.to_string())?.clone();
+    let log_path = build_log_path(&tex_path, build_dir.as_deref());
+    if !log_path.is_file() {
+        return Err(format!("No build log for {} yet; build it first", path));
+    }
+    app.opener()
+        .open_path(log_path.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open build log: {}", e))
+}
+
+/// Most recent builds, newest first, for the build history panel
+///
+/// `limit` defaults to 50.
+#[tauri::command]
+pub fn build_history(limit: Option<usize>) -> Result<Vec<BuildRecord>, String> {
+    history::build_history(limit.unwrap_or(50))
+}
+
+/// Diagnostics of the last build of `path`, if `content` is what was built
+///
+/// Lets the error panel show known issues after reopening a file without
+/// rebuilding it.
+#[tauri::command]
+pub fn diagnostics_last(path: String, content: String) -> Option<Vec<Diagnostic>> {
+    history::last_diagnostics(&allowed_path(&path).ok()?, &content)
+}
+
+/// Compile after a short quiet period, coalescing rapid calls (auto-build on save)
+///
+/// Each call restarts the quiet period. If a build is already running,
+/// exactly one follow-up build is queued to run once it finishes.
+#[tauri::command]
+pub async fn build_compile_debounced(
+    engine: Option<String>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<DebouncedBuild, String> {
+    let engine = parse_engine(engine)?;
+    if !load_settings()?.auto_build {
+        return Ok(DebouncedBuild::Disabled);
+    }
+
+    let Some(ticket) = state.build_debounce.request() else {
+        return Ok(DebouncedBuild::Queued);
+    };
+    tokio::time::sleep(DEBOUNCE_DELAY).await;
+
+    match state.build_debounce.try_start(ticket) {
+        StartDecision::Superseded => return Ok(DebouncedBuild::Debounced),
+        StartDecision::Queued => return Ok(DebouncedBuild::Queued),
+        StartDecision::Start => {}
+    }
+
+    let interaction = InteractionMode::default();
+    let mut result = run_build(engine, false, false, interaction, None, &state, &app).await;
+    while state.build_debounce.finish() {
+        result = run_build(engine, false, false, interaction, None, &state, &app).await;
+    }
+    result.map(|build| DebouncedBuild::Ran(Box::new(build)))
+}
+
+/// Set the directory used for build artifacts
+///
+/// Pass `None` to go back to the default cache location.
+#[tauri::command]
+pub fn build_set_output_dir(path: Option<String>, state: State<AppState>) -> Result<(), String> {
+    let dir = path.as_deref().map(allowed_path).transpose()?;
+    if let Some(dir) = &dir {
+        ensure_writable_dir(dir)?;
+    }
+
+    let mut build_dir = state.build_dir.lock().map_err(|e| e.to_string())?;
+    *build_dir = dir;
+    Ok(())
+}
+
+/// Get the persisted settings
+#[tauri::command]
+pub fn settings_get() -> Result<Settings, String> {
+    load_settings()
+}
+
+/// Replace the persisted settings and return them
+///
+/// Fails without saving anything if a new `pdflatex_path` or tool path
+/// doesn't run or the extra engine arguments aren't allowed.
+#[tauri::command]
+pub fn settings_update(settings: Settings) -> Result<Settings, String> {
+    validate_extra_args(&settings.extra_args)?;
+    let current = load_settings()?;
+    if settings.pdflatex_path != current.pdflatex_path {
+        if let Some(path) = &settings.pdflatex_path {
+            pdflatex::verify_tool_command(path)?;
+        }
+    }
+    for (tool, path) in &settings.tool_paths {
+        if current.tool_paths.get(tool) != Some(path) {
+            pdflatex::verify_tool_command(path)?;
+        }
+    }
+
+    save_settings(&settings)?;
+    pdflatex::set_tool_overrides(settings.tool_overrides());
+    Ok(settings)
+}
+
+/// Turn auto-build on save on or off
+#[tauri::command]
+pub fn build_set_auto_build(enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings()?;
+    settings.auto_build = enabled;
+    save_settings(&settings)
+}
+
+/// Cancel the build in progress; returns whether a build was running
+#[tauri::command]
+pub fn build_cancel(state: State<AppState>) -> bool {
+    state.compilation.cancel()
 }
 
 /// Check system requirements (pdflatex, etc.)
 #[tauri::command]
-pub fn check_system_requirements() -> RequirementsStatus {
-    check_requirements()
+pub async fn check_system_requirements() -> RequirementsStatus {
+    check_requirements_async().await
 }
 
 /// Read a PDF file and return it as base64
+///
+/// Deprecated: use `pdf_get_bytes`, which avoids the base64 overhead.
 #[tauri::command]
 pub fn read_pdf_base64(path: String) -> Result<String, String> {
-    pdf::read_pdf_base64(&path)
+    #[allow(deprecated)]
+    pdf::read_pdf_base64(&allowed_path(&path)?.to_string_lossy())
+}
+
+/// Read a PDF file and return its raw bytes
+///
+/// The bytes are sent as a binary IPC response (an `ArrayBuffer` on the
+/// frontend) rather than being encoded into a JSON string.
+#[tauri::command]
+pub fn pdf_get_bytes(path: String) -> Result<Response, String> {
+    pdf::read_pdf_bytes(&allowed_path(&path)?.to_string_lossy()).map(Response::new)
+}
+
+/// Count the pages of a PDF without rendering it
+#[tauri::command]
+pub fn pdf_page_count(path: String) -> Result<u32, String> {
+    pdf::pdf_page_count(&allowed_path(&path)?.to_string_lossy())
+}
+
+/// Extract the text layer of a compiled PDF
+#[tauri::command]
+pub fn pdf_extract_text(path: String) -> Result<String, String> {
+    pdf::pdf_extract_text(&allowed_path(&path)?.to_string_lossy())
+}
+
+/// Save pages `start..=end` (1-based, inclusive) of a PDF as a new PDF
+#[tauri::command]
+pub fn pdf_extract_pages(src: String, dest: String, start: u32, end: u32) -> Result<(), String> {
+    let src = allowed_path(&src)?;
+    let dest = allowed_path(&dest)?;
+    pdf::pdf_extract_pages(&src.to_string_lossy(), &dest.to_string_lossy(), start, end)
+}
+
+/// Render one page (1-based) of a PDF to PNG bytes, for thumbnails
+///
+/// Returned as raw bytes, like `pdf_get_bytes`.
+#[tauri::command]
+pub fn pdf_render_page_png(path: String, page: u32, dpi: u32) -> Result<Response, String> {
+    let path = allowed_path(&path)?;
+    pdf::render::render_page_png(&path.to_string_lossy(), page, dpi).map(Response::new)
+}
+
+/// Compare two compiled PDFs page by page
+#[tauri::command]
+pub fn pdf_diff(a: String, b: String) -> Result<pdf::DiffResult, String> {
+    let a = allowed_path(&a)?;
+    let b = allowed_path(&b)?;
+    pdf::pdf_diff(&a.to_string_lossy(), &b.to_string_lossy())
+}
+
+/// Count words, characters and pages of a compiled PDF
+#[tauri::command]
+pub fn pdf_word_count(path: String) -> Result<pdf::WordCount, String> {
+    pdf::pdf_word_count(&allowed_path(&path)?.to_string_lossy())
+}
+
+/// Convert LaTeX source to plain text for pasting into job applications
+#[tauri::command]
+pub fn export_plaintext(content: String) -> Result<String, String> {
+    Ok(export::export_plaintext(&content))
+}
+
+/// Check which of a job description's keywords a resume contains
+#[tauri::command]
+pub fn keyword_match(resume_content: String, job_description: String) -> MatchReport {
+    keywords::keyword_match(&resume_content, &job_description)
+}
+
+/// Convert LaTeX source to Markdown
+#[tauri::command]
+pub fn export_markdown(content: String) -> Result<String, String> {
+    Ok(export::export_markdown(&content))
+}
+
+/// Sections and resume entries of LaTeX source, with their lines
+#[tauri::command]
+pub fn document_outline(content: String) -> Vec<OutlineEntry> {
+    outline::document_outline(&content)
+}
+
+/// The class LaTeX source declares with `\documentclass`, e.g. `moderncv`
+#[tauri::command]
+pub fn document_class(content: String) -> Option<ClassInfo> {
+    class::document_class(&content)
+}
+
+/// Check LaTeX source for unbalanced braces and environments without building
+#[tauri::command]
+pub fn lint_structure(content: String) -> Vec<Diagnostic> {
+    let mut diagnostics = lint::lint_structure(&content);
+    enrich_diagnostics(&content, &mut diagnostics);
+    diagnostics
+}
+
+/// The `\href` and `\url` links in `content`, each with its line and
+/// whether the URL is well formed
+///
+/// With `online`, each web link is also requested to see if it loads,
+/// which needs network access and can take a few seconds per link.
+#[tauri::command]
+pub async fn check_links(content: String, online: Option<bool>) -> Result<Vec<LinkStatus>, String> {
+    let online = online.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || links::check_links(&content, online))
+        .await
+        .map_err(|e| format!("Failed to check links: {}", e))
+}
+
+/// Follow the `\input`/`\include` tree of a main file, failing on a cycle
+/// or a missing file; returns the files it reads
+#[tauri::command]
+pub fn check_includes(path: String) -> Result<Vec<String>, String> {
+    let files = includes::check_includes(&allowed_path(&path)?)?;
+    Ok(files.iter().map(|p| p.to_string_lossy().to_string()).collect())
+}
+
+/// Use a specific pdflatex executable; `None` goes back to searching for it
+///
+/// Fails if the path doesn't run `pdflatex --version` successfully.
+#[tauri::command]
+pub fn pdflatex_set_path(path: Option<String>) -> Result<(), String> {
+    if let Some(path) = &path {
+        pdflatex::verify_tool_command(path)?;
+    }
+
+    let mut settings = load_settings()?;
+    settings.pdflatex_path = path.clone();
+    save_settings(&settings)?;
+    pdflatex::set_tool_overrides(settings.tool_overrides());
+    Ok(())
+}
+
+/// SyncTeX data from the last build of the active document or its project
+fn active_synctex(state: &AppState) -> Result<SyncTex, String> {
+    let tex_path = state.active_path()?.ok_or("No file is currently open")?;
+    ensure_path_allowed(&tex_path)?;
+    let main_path = projects::build_target(&tex_path);
+    let build_dir = state.build_dir.lock().map_err(|e| e.to_string())?.clone();
+    SyncTex::load(&find_synctex_for(&tex_path, &main_path, build_dir.as_deref())?)
+}
+
+/// Find the source line for a point clicked in the PDF preview of the
+/// active document
+///
+/// `x` and `y` are PDF points from the top-left corner of `page` (1-based).
+#[tauri::command]
+pub fn synctex_resolve(
+    page: u32,
+    x: f64,
+    y: f64,
+    state: State<AppState>,
+) -> Result<SourceLocation, String> {
+    active_synctex(&state)?
+        .resolve(page, x, y)
+        .ok_or_else(|| format!("No source found at page {}", page))
+}
+
+/// Find where a source line appears in the PDF of the active document
+#[tauri::command]
+pub fn synctex_forward(
+    file: String,
+    line: u32,
+    state: State<AppState>,
+) -> Result<PdfLocation, String> {
+    active_synctex(&state)?
+        .forward(&file, line)
+        .ok_or_else(|| format!("Line {} of {} is not in the PDF", line, file))
+}
+
+/// Forget discovered TeX tool paths so the next build looks them up again
+///
+/// Call this after installing TeX while the app is running.
+#[tauri::command]
+pub fn pdflatex_refresh_cache() {
+    pdflatex::clear_command_cache();
 }
 
 /// Debug command to check pdflatex paths
 #[tauri::command]
 pub fn debug_pdflatex() -> String {
-    crate::compiler::pdflatex::debug_pdflatex()
+    pdflatex::debug_pdflatex()
 }
-
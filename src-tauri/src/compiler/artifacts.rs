@@ -0,0 +1,110 @@
+//! Files a build leaves behind in the build directory
+//!
+//! Each document gets its own folder under the build directory (see
+//! `build_dir_for`), holding `<stem>.aux`, `<stem>.log`, `<stem>.pdf` and
+//! so on. Listing them lets the UI show how much space a document's
+//! builds take up.
+
+use std::fs;
+use std::path::Path;
+
+/// A file produced by a build
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ArtifactInfo {
+    pub name: String,
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Everything after the stem, e.g. `aux` or `synctex.gz`
+    pub extension: String,
+}
+
+/// The artifacts for documents named `stem` in `build_dir` and the
+/// per-document folders inside it, largest first
+pub fn list_artifacts_in(build_dir: &Path, stem: &str) -> Result<Vec<ArtifactInfo>, String> {
+    if !build_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let prefix = format!("{}.", stem);
+    let mut artifacts = Vec::new();
+    collect_artifacts(build_dir, &prefix, &mut artifacts)?;
+
+    let entries =
+        fs::read_dir(build_dir).map_err(|e| format!("Failed to read build directory: {}", e))?;
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            collect_artifacts(&entry.path(), &prefix, &mut artifacts)?;
+        }
+    }
+
+    artifacts.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    Ok(artifacts)
+}
+
+/// Add the files in `dir` whose names start with `prefix`
+fn collect_artifacts(
+    dir: &Path,
+    prefix: &str,
+    artifacts: &mut Vec<ArtifactInfo>,
+) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read build directory: {}", e))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(extension) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() || extension.is_empty() {
+            continue;
+        }
+        artifacts.push(ArtifactInfo {
+            extension: extension.to_string(),
+            path: entry.path().to_string_lossy().to_string(),
+            size: metadata.len(),
+            name,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lists_artifacts_for_stem_largest_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let build_dir = temp_dir.path();
+        let document_dir = build_dir.join("0123456789abcdef");
+        fs::create_dir_all(&document_dir).unwrap();
+        fs::write(document_dir.join("resume.aux"), vec![0; 10]).unwrap();
+        fs::write(document_dir.join("resume.pdf"), vec![0; 300]).unwrap();
+        fs::write(document_dir.join("resume.synctex.gz"), vec![0; 50]).unwrap();
+        // Other documents' files are left out
+        fs::write(document_dir.join("letter.pdf"), vec![0; 500]).unwrap();
+        fs::write(build_dir.join("resume_old.log"), vec![0; 500]).unwrap();
+
+        let artifacts = list_artifacts_in(build_dir, "resume").unwrap();
+        let summary: Vec<(&str, u64)> = artifacts
+            .iter()
+            .map(|a| (a.extension.as_str(), a.size))
+            .collect();
+        assert_eq!(summary, [("pdf", 300), ("synctex.gz", 50), ("aux", 10)]);
+        assert_eq!(artifacts[0].name, "resume.pdf");
+        assert_eq!(
+            Path::new(&artifacts[0].path),
+            document_dir.join("resume.pdf")
+        );
+    }
+
+    #[test]
+    fn test_missing_build_dir_has_no_artifacts() {
+        let temp_dir = TempDir::new().unwrap();
+        let artifacts = list_artifacts_in(&temp_dir.path().join("build"), "resume").unwrap();
+        assert!(artifacts.is_empty());
+    }
+}
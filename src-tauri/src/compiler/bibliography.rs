@@ -0,0 +1,174 @@
+//! Bibliography processing (bibtex/biber) for documents with citations
+
+use std::path::Path;
+use std::process::Command;
+
+use super::pdflatex;
+
+/// Tool used to turn citations into a `.bbl` bibliography
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BibliographyTool {
+    BibTex,
+    Biber,
+}
+
+impl BibliographyTool {
+    /// Name of the tool executable (without extension)
+    pub fn binary_name(&self) -> &'static str {
+        match self {
+            BibliographyTool::BibTex => "bibtex",
+            BibliographyTool::Biber => "biber",
+        }
+    }
+}
+
+/// Whether the log reports citations that haven't been resolved yet
+fn has_undefined_citations(log: &str) -> bool {
+    log.lines()
+        .any(|line| line.contains("Citation `") && line.contains("undefined"))
+        || log.contains("There were undefined citations")
+}
+
+/// Decide whether a bibliography pass is needed after the first engine pass
+///
+/// biblatex asks for its backend explicitly; classic `\bibliography{...}`
+/// documents are detected through the `\bibdata` entry in the aux file.
+pub fn detect_bibliography_tool(log: &str, aux: &str, has_bbl: bool) -> Option<BibliographyTool> {
+    if log.contains("(re)run Biber") {
+        return Some(BibliographyTool::Biber);
+    }
+    if log.contains("(re)run BibTeX") {
+        return Some(BibliographyTool::BibTex);
+    }
+    if aux.contains("\\bibdata") && (!has_bbl || has_undefined_citations(log)) {
+        return Some(BibliographyTool::BibTex);
+    }
+    None
+}
+
+/// Build the command running `tool` on the aux/bcf file in `build_dir`
+///
+/// `.bib` and `.bst` files live next to the source, so the tool is pointed
+/// back at the source directory.
+pub fn bibliography_command(tool: BibliographyTool, build_dir: &Path, tex_path: &Path) -> Command {
+    let tool_cmd = pdflatex::get_tool_command(tool.binary_name());
+    let stem = tex_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "output".to_string());
+    let source_dir = tex_path.parent().filter(|p| !p.as_os_str().is_empty());
+
+    let mut cmd = Command::new(&tool_cmd);
+    match tool {
+        BibliographyTool::BibTex => {
+            // bibtex writes next to the aux file, so run it from the build directory
+            cmd.current_dir(build_dir);
+            if let Some(dir) = source_dir {
+                // A trailing separator keeps the distribution's default search path
+                let search_path = format!("{}{}", dir.to_string_lossy(), search_path_separator());
+                cmd.env("BIBINPUTS", &search_path)
+                    .env("BSTINPUTS", &search_path);
+                if tool_cmd.to_lowercase().contains("miktex") {
+                    cmd.arg(format!("-include-directory={}", dir.to_string_lossy()));
+                }
+            }
+        }
+        BibliographyTool::Biber => {
+            // biber searches its output directory first, then the working directory
+            if let Some(dir) = source_dir {
+                cmd.current_dir(dir);
+            }
+            cmd.arg(format!(
+                "--output-directory={}",
+                build_dir.to_string_lossy()
+            ));
+        }
+    }
+    cmd.arg(stem);
+    cmd
+}
+
+/// Separator between entries of a TeX search path variable
+pub(crate) fn search_path_separator() -> char {
+    if cfg!(windows) {
+        ';'
+    } else {
+        ':'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_biblatex_backends() {
+        let log = "Package biblatex Warning: Please (re)run Biber on the file:\n(biblatex) resume";
+        assert_eq!(
+            detect_bibliography_tool(log, "", true),
+            Some(BibliographyTool::Biber)
+        );
+
+        let log = "Package biblatex Warning: Please (re)run BibTeX on the file(s):";
+        assert_eq!(
+            detect_bibliography_tool(log, "", true),
+            Some(BibliographyTool::BibTex)
+        );
+    }
+
+    #[test]
+    fn test_detects_bibdata_without_bbl() {
+        let aux = "\\relax\n\\citation{knuth84}\n\\bibstyle{plain}\n\\bibdata{publications}\n";
+        assert_eq!(
+            detect_bibliography_tool("", aux, false),
+            Some(BibliographyTool::BibTex)
+        );
+    }
+
+    #[test]
+    fn test_detects_undefined_citation_with_stale_bbl() {
+        let aux = "\\bibdata{publications}\n";
+        let log = "LaTeX Warning: Citation `lamport94' on page 1 undefined on input line 12.";
+        assert_eq!(
+            detect_bibliography_tool(log, aux, true),
+            Some(BibliographyTool::BibTex)
+        );
+        assert_eq!(detect_bibliography_tool("", aux, true), None);
+    }
+
+    #[test]
+    fn test_no_bibliography_without_database() {
+        let log = "LaTeX Warning: Citation `knuth84' on page 1 undefined on input line 5.";
+        assert_eq!(detect_bibliography_tool(log, "\\relax\n", false), None);
+    }
+
+    #[test]
+    fn test_bibtex_command_runs_in_build_dir() {
+        let cmd = bibliography_command(
+            BibliographyTool::BibTex,
+            Path::new("/tmp/build"),
+            Path::new("/home/me/cv/resume.tex"),
+        );
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/tmp/build")));
+        assert_eq!(cmd.get_args().last().unwrap(), "resume");
+        let bibinputs = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "BIBINPUTS")
+            .and_then(|(_, value)| value)
+            .unwrap();
+        assert!(bibinputs.to_string_lossy().starts_with("/home/me/cv"));
+    }
+
+    #[test]
+    fn test_biber_command_uses_output_directory() {
+        let cmd = bibliography_command(
+            BibliographyTool::Biber,
+            Path::new("/tmp/build"),
+            Path::new("/home/me/cv/resume.tex"),
+        );
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/home/me/cv")));
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args, ["--output-directory=/tmp/build", "resume"]);
+    }
+}
@@ -1,55 +1,520 @@
 //! LaTeX compilation logic
 
-use std::path::Path;
-use std::process::Command;
-use std::time::Instant;
+use std::ffi::OsString;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::process::{Command, Stdio};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
 use tokio::process::Command as AsyncCommand;
 
+use super::bibliography::{
+    bibliography_command, detect_bibliography_tool, search_path_separator, BibliographyTool,
+};
+use super::diagnostics::{
+    check_document_structure, dedupe_diagnostics, enrich_diagnostics, parse_diagnostics,
+    Diagnostic, Severity, DEFAULT_MAX_DIAGNOSTICS,
+};
+use super::engine::{InteractionMode, LatexEngine};
+use super::handle::CancelToken;
+use super::history::{record_build, save_diagnostics, BuildRecord};
+use super::packages::{
+    parse_font_substitutions, parse_loaded_packages, FontSubstitution, PackageInfo,
+};
 use super::pdflatex;
+use crate::disk::ensure_disk_space;
+use crate::file_ops::is_locked_error;
+use crate::latex::includes::{include_tree, IncludeError};
+
+/// Why a build failed, so the frontend can suggest the right fix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildErrorKind {
+    /// The engine couldn't be run, e.g. TeX isn't installed
+    ToolMissing,
+    /// The engine ran past its time limit
+    Timeout,
+    /// The engine ran but the document didn't produce a PDF
+    CompileError,
+    /// Build files couldn't be read or written
+    IoError,
+    Cancelled,
+}
 
 /// Result of a compilation attempt
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, Default, serde::Serialize)]
 pub struct BuildResult {
     pub success: bool,
     pub pdf_path: Option<String>,
+    /// The PDF in the build directory, which every build rewrites; `pdf_path`
+    /// may instead be the copy next to the source file
+    pub build_pdf_path: Option<String>,
+    /// Engine output: `stdout` followed by `stderr`, then any bibtex/biber output
     pub log: String,
+    /// What the final engine pass printed to standard output (the TeX transcript)
+    pub stdout: String,
+    /// What the final engine pass printed to standard error
+    pub stderr: String,
     pub duration_ms: u64,
     pub error_message: Option<String>,
+    /// Category of the failure; `None` when the build succeeded
+    pub error_kind: Option<BuildErrorKind>,
+    /// Number of engine passes actually run
+    pub passes: u32,
+    /// Errors and warnings parsed from the log
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether bibtex/biber ran to resolve citations
+    pub bibliography_pass: bool,
+    /// Pages in the PDF, as reported in the log
+    pub page_count: Option<u32>,
+    /// Classes and packages a successful build loaded, from the engine's `.log` file
+    pub loaded_packages: Vec<PackageInfo>,
+    /// Font shapes LaTeX replaced with others during a successful build
+    pub font_substitutions: Vec<FontSubstitution>,
+    /// Times the build was rerun after MiKTeX failed to install a package
+    pub retries: u32,
+}
+
+/// Options controlling how a document is compiled
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    pub engine: LatexEngine,
+    /// Upper bound on engine passes when cross-references need resolving
+    pub max_passes: u32,
+    /// Directory for build artifacts; `None` uses a per-project folder in the cache
+    pub build_dir: Option<PathBuf>,
+    /// Fast preview: images are drawn as boxes instead of being embedded,
+    /// and passes that will be rerun don't write a PDF
+    pub draft: bool,
+    /// Most diagnostics returned; repeats are collapsed before capping
+    pub max_diagnostics: usize,
+    /// Directory to record the build history in; `None` doesn't record it
+    pub logs_dir: Option<PathBuf>,
+    /// Add an info diagnostic when the PDF has more pages than this
+    pub page_limit: Option<u32>,
+    /// Extra directories searched for `.cls`, `.sty` and `\input` files,
+    /// after the source file's own directory
+    pub search_paths: Vec<PathBuf>,
+    /// Let the document run external programs (`-shell-escape`), as
+    /// `minted` and `svg` need; off by default since it can run anything
+    pub allow_shell_escape: bool,
+    /// Flags passed to the engine before the source file, e.g. `-halt-on-error`
+    pub extra_args: Vec<String>,
+    /// What the engine does on an error
+    pub interaction: InteractionMode,
+    /// Rebuilds allowed when MiKTeX fails while installing a package on the fly
+    pub install_retries: u32,
+    /// Make identical sources build byte-identical PDFs; see [`REPRODUCIBLE_EPOCH`]
+    pub reproducible: bool,
+    /// Name for the PDF copied next to the source, e.g. `Jane_Doe_Resume`;
+    /// `None` names it after the source file
+    pub output_name: Option<String>,
+    /// Copy the PDF next to the source; when false it stays in the build
+    /// directory so the project folder is left untouched
+    pub in_place: bool,
+}
+
+/// Timestamp reproducible builds embed (as `SOURCE_DATE_EPOCH`) instead of
+/// the build time, unless the environment already sets one
+///
+/// The engines derive the PDF's `/CreationDate`, `/ModDate` and `/ID` from
+/// it. `\today` still prints the real date, and fonts or packages that
+/// embed their own timestamps (some older MiKTeX font maps, `pdfx`
+/// metadata) can still make the bytes differ.
+pub const REPRODUCIBLE_EPOCH: &str = "0";
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            engine: LatexEngine::default(),
+            max_passes: 2,
+            build_dir: None,
+            draft: false,
+            max_diagnostics: DEFAULT_MAX_DIAGNOSTICS,
+            logs_dir: None,
+            page_limit: None,
+            search_paths: Vec::new(),
+            allow_shell_escape: false,
+            extra_args: Vec::new(),
+            interaction: InteractionMode::default(),
+            install_retries: DEFAULT_INSTALL_RETRIES,
+            reproducible: false,
+            output_name: None,
+            in_place: true,
+        }
+    }
+}
+
+/// A line of engine output, emitted while a build is running
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BuildProgress {
+    /// 1-based engine pass the line belongs to
+    pub pass: u32,
+    pub line: String,
+    /// Highest page number shipped out on this line, if any
+    pub page: Option<u32>,
+}
+
+/// Callback receiving build progress as output arrives
+pub type ProgressFn = dyn Fn(BuildProgress) + Send + Sync;
+
+/// Parse the `[1] [2{...pdftex.map}]` page markers TeX prints as it ships
+/// out pages, returning the highest page number on the line
+pub fn parse_shipped_page(line: &str) -> Option<u32> {
+    let mut highest = None;
+    for (index, _) in line.match_indices('[') {
+        let rest = &line[index + 1..];
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if digits.is_empty() {
+            continue;
+        }
+        // "[11pt]" is an option list, not a page marker
+        let terminated = match rest[digits.len()..].chars().next() {
+            None => true,
+            Some(c) => c == ']' || c == '{' || c == '<' || c.is_whitespace(),
+        };
+        if let (true, Ok(page)) = (terminated, digits.parse::<u32>()) {
+            highest = highest.max(Some(page));
+        }
+    }
+    highest
+}
+
+/// Parse the page count from TeX's closing `Output written on resume.pdf
+/// (2 pages, 12345 bytes).` line
+///
+/// TeX wraps log lines at 79 columns, so the message may continue on the
+/// next line. Every pass prints it; the last one wins.
+pub fn parse_page_count(log: &str) -> Option<u32> {
+    let start = log.rfind("Output written on")?;
+    let message: String = log[start..]
+        .lines()
+        .take(3)
+        .collect::<Vec<_>>()
+        .concat();
+    message.match_indices('(').find_map(|(index, _)| {
+        let rest = &message[index + 1..];
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if rest[digits.len()..].starts_with(" page") {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// Log messages that mean another pass is needed to settle references
+const RERUN_MARKERS: [&str; 3] = [
+    "Rerun to get cross-references right",
+    "Rerun to get outlines right",
+    "Label(s) may have changed",
+];
+
+/// Source constructs that only resolve on a second pass
+const CROSS_REFERENCE_COMMANDS: [&str; 5] = [
+    "\\label",
+    "\\ref",
+    "\\pageref",
+    "\\tableofcontents",
+    "{hyperref}",
+];
+
+/// Check whether the source uses references, a TOC or hyperref bookmarks
+fn source_needs_rerun(source: &str) -> bool {
+    CROSS_REFERENCE_COMMANDS
+        .iter()
+        .any(|command| source.contains(command))
+}
+
+/// Check whether the engine log asks for another run
+fn log_requests_rerun(log: &str) -> bool {
+    RERUN_MARKERS.iter().any(|marker| log.contains(marker))
+}
+
+/// Decide whether to run another pass after `passes_run` passes
+fn needs_another_pass(source: &str, log: &str, passes_run: u32, max_passes: u32) -> bool {
+    if passes_run >= max_passes {
+        return false;
+    }
+    // A failed run won't get any better by repeating it
+    if log.contains("Fatal error occurred") || log.contains("No pages of output") {
+        return false;
+    }
+    log_requests_rerun(log) || (passes_run == 1 && source_needs_rerun(source))
 }
 
 /// Get the temp build directory for compilation artifacts
-fn get_build_dir() -> std::path::PathBuf {
+pub fn get_build_dir() -> PathBuf {
     let base = dirs::cache_dir()
         .or_else(dirs::data_local_dir)
         .unwrap_or_else(std::env::temp_dir);
     base.join("ResumeIDE").join("build")
 }
 
-/// Setup command arguments and environment for pdflatex
-fn setup_pdflatex_command_args(cmd: &mut Command, pdflatex_cmd: &str, output_dir: &Path, tex_path: &Path) {
-    cmd.arg("-interaction=nonstopmode")
-        .arg(format!("-output-directory={}", output_dir.to_string_lossy()))
-        .arg(tex_path);
-    
-    // If using full path, add parent directory to PATH for DLLs
-    if pdflatex_cmd.contains('\\') || pdflatex_cmd.contains('/') {
-        if let Some(parent) = std::path::Path::new(pdflatex_cmd).parent() {
-            let current_path = std::env::var("PATH").unwrap_or_default();
-            let new_path = format!("{};{}", parent.to_string_lossy(), current_path);
-            cmd.env("PATH", new_path);
+/// FNV-1a hash; unlike `DefaultHasher` it is stable across Rust releases,
+/// so a project keeps its build directory after an update
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Get the build directory for one source file
+///
+/// Namespaced by a hash of the absolute source path so that unrelated
+/// documents with the same name don't share aux files.
+pub fn build_dir_for(tex_path: &Path) -> PathBuf {
+    let absolute = to_absolute(tex_path);
+    let hash = stable_hash(absolute.to_string_lossy().as_bytes());
+    get_build_dir().join(format!("{:016x}", hash))
+}
+
+/// Resolve the build directory, preferring an explicitly configured one
+pub(crate) fn resolve_build_dir(explicit: Option<&Path>, tex_path: &Path) -> PathBuf {
+    explicit
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| build_dir_for(tex_path))
+}
+
+/// Make sure a directory exists and can be written to
+pub fn ensure_writable_dir(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| {
+        format!("Failed to create build directory {}: {}", dir.to_string_lossy(), e)
+    })?;
+
+    // Permissions alone don't tell the whole story (sandboxes, read-only
+    // mounts), so probe with a real file
+    let probe = dir.join(".resumeide-write-test");
+    std::fs::write(&probe, b"")
+        .and_then(|_| std::fs::remove_file(&probe))
+        .map_err(|e| {
+            format!("Build directory {} is not writable: {}", dir.to_string_lossy(), e)
+        })
+}
+
+/// Resolve a possibly relative path against the current directory
+fn to_absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    std::env::current_dir()
+        .map(|cwd| cwd.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Split a tex path into the directory to run the engine from and the
+/// file argument to pass it
+///
+/// Running from the source directory lets `\input{sections/...}` resolve
+/// relative to the main file.
+fn split_tex_path(tex_path: &Path) -> (Option<PathBuf>, PathBuf) {
+    match (tex_path.parent(), tex_path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            (Some(to_absolute(parent)), PathBuf::from(name))
         }
+        _ => (None, tex_path.to_path_buf()),
     }
 }
 
-/// Setup command arguments and environment for pdflatex (async version)
-fn setup_pdflatex_command_args_async(cmd: &mut AsyncCommand, pdflatex_cmd: &str, output_dir: &Path, tex_path: &Path) {
-    cmd.arg("-interaction=nonstopmode")
-        .arg(format!("-output-directory={}", output_dir.to_string_lossy()))
-        .arg(tex_path);
-    
+/// `TEXINPUTS` for a build: the source directory, then `extra`, then
+/// whatever the user already has set
+///
+/// The trailing separator keeps the distribution's default search path,
+/// so class and style files next to the source are found before installed
+/// ones without hiding them.
+fn texinputs(source_dir: Option<&Path>, extra: &[PathBuf]) -> OsString {
+    let separator = search_path_separator();
+    let mut value = OsString::new();
+    for dir in source_dir.into_iter().chain(extra.iter().map(PathBuf::as_path)) {
+        value.push(to_absolute(dir));
+        value.push(separator.to_string());
+    }
+    if let Some(existing) = std::env::var_os("TEXINPUTS").filter(|v| !v.is_empty()) {
+        value.push(&existing);
+        if !existing.to_string_lossy().ends_with(separator) {
+            value.push(separator.to_string());
+        }
+    }
+    value
+}
+
+/// Rebuilds after a failed on-the-fly package install, by default
+pub const DEFAULT_INSTALL_RETRIES: u32 = 1;
+
+/// Wait before a rebuild after a failed install, multiplied by the attempt
+const INSTALL_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Messages MiKTeX prints while fetching a missing package
+const PACKAGE_INSTALL_MARKERS: [&str; 2] = ["installing package", "trying to find"];
+
+/// Whether a failed build's log shows MiKTeX installing a package, which
+/// often succeeds only by the next build
+fn is_package_install_failure(log: &str) -> bool {
+    let log = log.to_lowercase();
+    PACKAGE_INSTALL_MARKERS
+        .iter()
+        .any(|marker| log.contains(marker))
+}
+
+/// Whether an engine command points into a MiKTeX installation
+fn is_miktex_command(engine_cmd: &str) -> bool {
+    engine_cmd.to_lowercase().contains("miktex")
+}
+
+/// Whether a failed build is worth repeating because MiKTeX was installing
+/// a package; TeX Live never installs on the fly
+fn should_retry_install(engine_cmd: &str, log: &str) -> bool {
+    is_miktex_command(engine_cmd) && is_package_install_failure(log)
+}
+
+/// Create build-directory subfolders for `\include{dir/file}` targets
+///
+/// `\include` writes a separate `.aux` per file relative to the output
+/// directory, and the engine can't create the subfolder itself.
+fn prepare_include_dirs(source: &str, build_dir: &Path) {
+    for (index, _) in source.match_indices("\\include{") {
+        let target = &source[index + "\\include{".len()..];
+        let Some(end) = target.find('}') else {
+            continue;
+        };
+        if let Some(parent) = Path::new(target[..end].trim()).parent() {
+            if !parent.as_os_str().is_empty() {
+                let _ = std::fs::create_dir_all(build_dir.join(parent));
+            }
+        }
+    }
+}
+
+/// Engine flags the build sets itself, which extra arguments may not override
+///
+/// Moving the output or renaming the job would leave the build looking for
+/// the PDF in the wrong place; shell escape has its own setting.
+const RESERVED_ARGS: [&str; 8] = [
+    "output-directory",
+    "aux-directory",
+    "jobname",
+    "shell-escape",
+    "no-shell-escape",
+    "shell-restricted",
+    "enable-write18",
+    "disable-write18",
+];
+
+/// Check user-supplied engine arguments before they're passed to the engine
+///
+/// Every argument must be a flag, since anything else would be read as the
+/// file to compile, and none may be one of the `RESERVED_ARGS`.
+pub fn validate_extra_args(args: &[String]) -> Result<(), String> {
+    for arg in args {
+        let Some(flag) = arg.strip_prefix('-') else {
+            return Err(format!("Extra argument `{}` is not a flag", arg));
+        };
+        let name = flag.trim_start_matches('-').split('=').next().unwrap_or("");
+        if name.is_empty() {
+            return Err(format!("Extra argument `{}` is not a flag", arg));
+        }
+        if RESERVED_ARGS.contains(&name) {
+            return Err(format!("Extra argument `{}` is set by the build itself", arg));
+        }
+    }
+    Ok(())
+}
+
+/// Draft settings for a single engine run
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct DraftPass {
+    /// Load graphicx with the `draft` option so images show as boxes
+    draft_graphics: bool,
+    /// Engine flag that skips writing the PDF, for passes followed by another
+    no_output_flag: Option<&'static str>,
+}
+
+/// Characters that can't appear in a file name passed to `\input` as TeX code
+const TEX_SPECIAL_CHARS: &[char] = &['\\', '{', '}', '%', '#', '$', '&', '^', '~', '"'];
+
+/// First line for a draft pass: load graphicx in draft mode, then read the file
+///
+/// The name is quoted so spaces survive. `None` if the name has characters
+/// TeX would interpret, in which case the pass runs as a normal build.
+fn draft_input(tex_arg: &Path) -> Option<String> {
+    let name = tex_arg.to_str()?;
+    if name.contains(TEX_SPECIAL_CHARS) {
+        return None;
+    }
+    Some(format!(
+        "\\PassOptionsToPackage{{draft}}{{graphicx}}\\input{{\"{}\"}}",
+        name
+    ))
+}
+
+/// Setup command arguments and environment for the LaTeX engine
+fn setup_pdflatex_command_args(cmd: &mut Command, engine_cmd: &str, output_dir: &Path, tex_path: &Path) {
+    let options = CompileOptions::default();
+    setup_engine_args(cmd, engine_cmd, output_dir, tex_path, DraftPass::default(), &options);
+}
+
+/// Setup command arguments and environment for one engine pass
+fn setup_engine_args(
+    cmd: &mut Command,
+    engine_cmd: &str,
+    output_dir: &Path,
+    tex_path: &Path,
+    draft: DraftPass,
+    options: &CompileOptions,
+) {
+    // The working directory changes, so the output directory must be absolute
+    let output_dir = to_absolute(output_dir);
+    let (work_dir, tex_arg) = split_tex_path(tex_path);
+
+    // Modes that stop on errors read the answer from stdin; with nothing
+    // to read the engine gives up instead of waiting forever
+    cmd.stdin(Stdio::null());
+    cmd.arg(options.interaction.flag())
+        .arg("-synctex=1")
+        .arg(format!("-output-directory={}", output_dir.to_string_lossy()));
+    cmd.arg(if options.allow_shell_escape {
+        "-shell-escape"
+    } else {
+        "-no-shell-escape"
+    });
+    if let Some(flag) = draft.no_output_flag {
+        cmd.arg(flag);
+    }
+    if options.reproducible {
+        cmd.args(options.engine.output_format_flag());
+        if std::env::var_os("SOURCE_DATE_EPOCH").is_none() {
+            cmd.env("SOURCE_DATE_EPOCH", REPRODUCIBLE_EPOCH);
+        }
+    }
+    cmd.args(&options.extra_args);
+    cmd.env("TEXINPUTS", texinputs(work_dir.as_deref(), &options.search_paths));
+    if let Some(dir) = &work_dir {
+        cmd.current_dir(dir);
+        // MiKTeX-only option; TeX Live finds files through the working directory
+        if is_miktex_command(engine_cmd) {
+            cmd.arg(format!("-include-directory={}", dir.to_string_lossy()));
+        }
+    }
+    match draft_input(&tex_arg).filter(|_| draft.draft_graphics) {
+        Some(input) => {
+            // Set the options before the document loads graphicx; the job keeps
+            // the file's name so outputs land where a normal build puts them
+            if let Some(stem) = tex_arg.file_stem() {
+                cmd.arg(format!("-jobname={}", stem.to_string_lossy()));
+            }
+            cmd.arg(input);
+        }
+        None => {
+            cmd.arg(tex_arg);
+        }
+    }
+
     // If using full path, add parent directory to PATH for DLLs
-    if pdflatex_cmd.contains('\\') || pdflatex_cmd.contains('/') {
-        if let Some(parent) = std::path::Path::new(pdflatex_cmd).parent() {
+    if engine_cmd.contains('\\') || engine_cmd.contains('/') {
+        if let Some(parent) = std::path::Path::new(engine_cmd).parent() {
             let current_path = std::env::var("PATH").unwrap_or_default();
             let new_path = format!("{};{}", parent.to_string_lossy(), current_path);
             cmd.env("PATH", new_path);
@@ -57,60 +522,78 @@ fn setup_pdflatex_command_args_async(cmd: &mut AsyncCommand, pdflatex_cmd: &str,
     }
 }
 
+/// Setup command arguments and environment for one engine pass (async version)
+fn setup_engine_args_async(
+    cmd: &mut AsyncCommand,
+    engine_cmd: &str,
+    output_dir: &Path,
+    tex_path: &Path,
+    draft: DraftPass,
+    options: &CompileOptions,
+) {
+    setup_engine_args(cmd.as_std_mut(), engine_cmd, output_dir, tex_path, draft, options);
+}
+
 /// Process compilation output and build the result
 fn process_compilation_result(
     result: Result<std::process::Output, std::io::Error>,
     tex_path: &Path,
     build_dir: &Path,
-    output_dir: &Path,
     duration_ms: u64,
     copy_to_source: bool,
+    options: &CompileOptions,
 ) -> BuildResult {
+    let engine = options.engine;
     match result {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout).to_string();
             let stderr = String::from_utf8_lossy(&output.stderr).to_string();
             let log = format!("{}\n{}", stdout, stderr);
+            let mut diagnostics = parse_diagnostics(&log);
 
-            // Derive PDF path from tex path
-            let pdf_name = tex_path
-                .file_stem()
-                .map(|s| format!("{}.pdf", s.to_string_lossy()))
-                .unwrap_or_else(|| "output.pdf".to_string());
-
-            let built_pdf = build_dir.join(&pdf_name);
+            // The engine names the PDF after the tex file; only the copy is renamed
+            let built_pdf = build_dir.join(pdf_file_name(tex_path, None));
+            let pdf_name = pdf_file_name(tex_path, options.output_name.as_deref());
 
             // pdflatex may return non-zero but still produce a PDF
-            if built_pdf.exists() {
-                let final_pdf = if copy_to_source {
+            let mut build = if built_pdf.exists() {
+                let final_pdf = if copy_to_source && options.in_place {
                     // Copy PDF to same directory as source file
-                    tex_path
+                    let target = tex_path
                         .parent()
                         .map(|p| p.join(&pdf_name))
-                        .unwrap_or_else(|| std::path::PathBuf::from(&pdf_name))
+                        .unwrap_or_else(|| std::path::PathBuf::from(&pdf_name));
+                    let (delivered, warning) = copy_pdf_to_source(&built_pdf, &target);
+                    diagnostics.extend(warning);
+                    delivered
                 } else {
-                    // Use output directory directly
-                    output_dir.join(&pdf_name)
+                    // Use the PDF in the build directory directly
+                    built_pdf.clone()
                 };
 
-                if copy_to_source {
-                    if let Err(e) = std::fs::copy(&built_pdf, &final_pdf) {
-                        return BuildResult {
-                            success: false,
-                            pdf_path: None,
-                            log,
-                            duration_ms,
-                            error_message: Some(format!("Failed to copy PDF: {}", e)),
-                        };
-                    }
-                }
-
                 BuildResult {
                     success: true,
                     pdf_path: Some(final_pdf.to_string_lossy().to_string()),
+                    build_pdf_path: Some(built_pdf.to_string_lossy().to_string()),
                     log,
                     duration_ms,
                     error_message: None,
+                    diagnostics,
+                    ..Default::default()
+                }
+            } else if !is_tex_output(&stdout) {
+                // Something ran, but it wasn't a TeX engine (e.g. a stale or wrong path)
+                BuildResult {
+                    success: false,
+                    pdf_path: None,
+                    log,
+                    duration_ms,
+                    error_message: Some(format!(
+                        "{} did not run: its output doesn't look like a TeX engine",
+                        engine
+                    )),
+                    error_kind: Some(BuildErrorKind::ToolMissing),
+                    ..Default::default()
                 }
             } else {
                 BuildResult {
@@ -119,17 +602,28 @@ fn process_compilation_result(
                     log,
                     duration_ms,
                     error_message: Some("Compilation failed - no PDF generated".to_string()),
+                    error_kind: Some(BuildErrorKind::CompileError),
+                    diagnostics,
+                    ..Default::default()
                 }
-            }
+            };
+            build.stdout = stdout;
+            build.stderr = stderr;
+            build
         }
         Err(e) => {
             let error_msg = if copy_to_source {
                 format!(
-                    "Failed to run pdflatex: {}. Make sure TeX Live or MiKTeX is installed.",
-                    e
+                    "Failed to run {}: {}. Make sure TeX Live or MiKTeX is installed.",
+                    engine, e
                 )
             } else {
-                format!("Failed to run pdflatex: {}", e)
+                format!("Failed to run {}: {}", engine, e)
+            };
+            // Spawning fails with these when the executable is absent or unusable
+            let error_kind = match e.kind() {
+                ErrorKind::NotFound | ErrorKind::PermissionDenied => BuildErrorKind::ToolMissing,
+                _ => BuildErrorKind::IoError,
             };
             BuildResult {
                 success: false,
@@ -137,47 +631,644 @@ fn process_compilation_result(
                 log: String::new(),
                 duration_ms,
                 error_message: Some(error_msg),
+                error_kind: Some(error_kind),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// Characters that can't appear in a file name on some platform
+const RESERVED_FILE_NAME_CHARS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// File name of the PDF delivered next to `tex_path`
+///
+/// `output_name` (with or without `.pdf`) has characters that aren't
+/// allowed in file names replaced by `_`. A missing or unusable name falls
+/// back to the source's stem.
+pub fn pdf_file_name(tex_path: &Path, output_name: Option<&str>) -> String {
+    let custom = output_name.map(|name| {
+        let name = name.trim();
+        let name = match name.len().checked_sub(4) {
+            Some(end) if name.is_char_boundary(end) && name[end..].eq_ignore_ascii_case(".pdf") => {
+                &name[..end]
+            }
+            _ => name,
+        };
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if RESERVED_FILE_NAME_CHARS.contains(&c) || c.is_control() {
+                    '_'
+                } else {
+                    c
+                }
+            })
+            .collect();
+        // Windows drops trailing dots and spaces, and `..` isn't a name
+        name.trim_end_matches(['.', ' ']).to_string()
+    });
+    match custom.filter(|name| name.chars().any(|c| c.is_alphanumeric())) {
+        Some(name) => format!("{}.pdf", name),
+        None => tex_path
+            .file_stem()
+            .map(|s| format!("{}.pdf", s.to_string_lossy()))
+            .unwrap_or_else(|| "output.pdf".to_string()),
+    }
+}
+
+/// Copy the built PDF next to the source file
+///
+/// If the copy fails, typically because a viewer holds the old PDF open,
+/// the build still succeeds: returns the build-directory PDF instead,
+/// with a warning explaining why.
+fn copy_pdf_to_source(built_pdf: &Path, final_pdf: &Path) -> (PathBuf, Option<Diagnostic>) {
+    let Err(e) = std::fs::copy(built_pdf, final_pdf) else {
+        return (final_pdf.to_path_buf(), None);
+    };
+    let name = final_pdf
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let warning = if is_locked_error(&e) {
+        format!(
+            concat!(
+                "Could not update {}: it is open in another program. ",
+                "Showing the PDF from the build folder"
+            ),
+            name
+        )
+    } else {
+        format!(
+            "Could not copy the PDF to {}: {}. Showing the PDF from the build folder",
+            final_pdf.display(),
+            e
+        )
+    };
+    (built_pdf.to_path_buf(), Some(Diagnostic::warning(warning)))
+}
+
+/// The `.log` file the engine writes for `tex_path`
+fn engine_log_path(tex_path: &Path, build_dir: &Path) -> PathBuf {
+    let stem = tex_path.file_stem().unwrap_or_default();
+    build_dir.join(stem).with_extension("log")
+}
+
+/// The engine's `.log` for `tex_path` in the build directory it is built in
+pub fn build_log_path(tex_path: &Path, build_dir: Option<&Path>) -> PathBuf {
+    engine_log_path(tex_path, &resolve_build_dir(build_dir, tex_path))
+}
+
+/// Whether engine output starts the way every TeX engine's does
+///
+/// pdfTeX, XeTeX and LuaTeX all open with a `This is <engine>, Version ...`
+/// banner, even when the source file is missing.
+fn is_tex_output(stdout: &str) -> bool {
+    stdout.contains("This is ")
+}
+
+/// A failed result for a source file that doesn't exist
+fn missing_source(tex_path: &Path, duration_ms: u64) -> BuildResult {
+    BuildResult {
+        success: false,
+        duration_ms,
+        error_message: Some(format!("File not found: {}", tex_path.display())),
+        error_kind: Some(BuildErrorKind::CompileError),
+        ..Default::default()
+    }
+}
+
+/// Message reported when a build is stopped through its cancel token
+pub const BUILD_CANCELLED: &str = "Build cancelled";
+
+/// Read a stream to the end, passing each line to `on_line` as it arrives
+async fn read_lines<R: AsyncRead + Unpin>(stream: R, mut on_line: impl FnMut(&str)) -> Vec<u8> {
+    let mut reader = BufReader::new(stream);
+    let mut collected = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                on_line(String::from_utf8_lossy(&line).trim_end());
+                collected.extend_from_slice(&line);
             }
         }
     }
+    collected
+}
+
+/// Spawn the engine and stream its stdout line by line
+///
+/// stderr is drained concurrently so neither pipe can fill up and stall
+/// the child.
+async fn run_streaming(
+    cmd: &mut AsyncCommand,
+    pass: u32,
+    cancel: Option<&CancelToken>,
+    progress: Option<&ProgressFn>,
+) -> std::io::Result<std::process::Output> {
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = cmd.spawn()?;
+    // Lets the app kill the child on exit, when this future is never dropped
+    let _registration = cancel.zip(child.id()).map(|(c, pid)| c.register_child(pid));
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let mut stdout_task = pin!(async {
+        match stdout {
+            Some(stdout) => {
+                read_lines(stdout, |line| {
+                    if let Some(progress) = progress {
+                        progress(BuildProgress {
+                            pass,
+                            line: line.to_string(),
+                            page: parse_shipped_page(line),
+                        });
+                    }
+                })
+                .await
+            }
+            None => Vec::new(),
+        }
+    });
+    let mut stderr_task = pin!(async {
+        let mut buffer = Vec::new();
+        if let Some(mut stderr) = stderr {
+            let _ = stderr.read_to_end(&mut buffer).await;
+        }
+        buffer
+    });
+
+    let mut stdout_bytes = None;
+    let mut stderr_bytes = None;
+    std::future::poll_fn(|cx| {
+        if stdout_bytes.is_none() {
+            if let Poll::Ready(bytes) = stdout_task.as_mut().poll(cx) {
+                stdout_bytes = Some(bytes);
+            }
+        }
+        if stderr_bytes.is_none() {
+            if let Poll::Ready(bytes) = stderr_task.as_mut().poll(cx) {
+                stderr_bytes = Some(bytes);
+            }
+        }
+        if stdout_bytes.is_some() && stderr_bytes.is_some() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+
+    let status = child.wait().await?;
+    Ok(std::process::Output {
+        status,
+        stdout: stdout_bytes.unwrap_or_default(),
+        stderr: stderr_bytes.unwrap_or_default(),
+    })
+}
+
+/// Run one engine pass to completion, or stop early if `cancel` fires
+///
+/// Returns `None` when cancelled; the child is killed when its output
+/// future is dropped (see `kill_on_drop`).
+async fn run_cancellable(
+    cmd: &mut AsyncCommand,
+    pass: u32,
+    cancel: Option<&CancelToken>,
+    progress: Option<&ProgressFn>,
+) -> Option<std::io::Result<std::process::Output>> {
+    let Some(cancel) = cancel else {
+        return Some(run_streaming(cmd, pass, None, progress).await);
+    };
+
+    let mut output = pin!(run_streaming(cmd, pass, Some(cancel), progress));
+    let mut cancelled = pin!(cancel.cancelled());
+    std::future::poll_fn(|cx| {
+        if cancelled.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        output.as_mut().poll(cx).map(Some)
+    })
+    .await
+}
+
+/// Whether a diagnostic points into `tex_path` rather than a file it includes
+///
+/// Diagnostics without a file come from checks on the main source.
+fn is_in_file(diagnostic: &Diagnostic, tex_path: &Path) -> bool {
+    diagnostic
+        .file
+        .as_ref()
+        .map_or(true, |file| Path::new(file).file_name() == tex_path.file_name())
+}
+
+/// Engine passes run after a bibliography pass: one to read the `.bbl`,
+/// one to settle the citation labels it introduces
+const PASSES_AFTER_BIBLIOGRAPHY: u32 = 2;
+
+/// Check the first pass's aux file and log for citations needing bibtex/biber
+fn bibliography_needed(tex_path: &Path, build_dir: &Path, log: &str) -> Option<BibliographyTool> {
+    let stem = tex_path.file_stem()?.to_string_lossy().to_string();
+    let aux = std::fs::read_to_string(build_dir.join(format!("{}.aux", stem))).unwrap_or_default();
+    let has_bbl = build_dir.join(format!("{}.bbl", stem)).exists();
+    detect_bibliography_tool(log, &aux, has_bbl)
 }
 
-/// Compile a LaTeX file to PDF using pdflatex (async version)
-pub async fn compile_latex_async(tex_path: &Path, _output_dir: &Path) -> BuildResult {
+/// Run bibtex/biber on the build directory's aux file
+///
+/// Returns the tool's output for the build log, or `None` if cancelled.
+/// A failing tool doesn't fail the build; the next engine pass reports
+/// whatever citations are still missing.
+async fn run_bibliography(
+    tool: BibliographyTool,
+    tex_path: &Path,
+    build_dir: &Path,
+    pass: u32,
+    cancel: Option<&CancelToken>,
+    progress: Option<&ProgressFn>,
+) -> Option<String> {
+    let mut cmd = AsyncCommand::from(bibliography_command(tool, build_dir, tex_path));
+    cmd.kill_on_drop(true);
+    let log = match run_cancellable(&mut cmd, pass, cancel, progress).await? {
+        Ok(output) => format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ),
+        Err(e) => format!("Failed to run {}: {}", tool.binary_name(), e),
+    };
+    Some(log)
+}
+
+/// Compile a LaTeX file to PDF (async version)
+///
+/// Runs up to `options.max_passes` passes when the document uses
+/// cross-references or the log asks for a rerun, plus a bibtex/biber pass
+/// and two more engine passes when citations need resolving. If `cancel`
+/// fires, the running engine is killed and the result reports
+/// `BUILD_CANCELLED`.
+/// Output lines are passed to `progress` as the engine prints them.
+pub async fn compile_latex_async(
+    tex_path: &Path,
+    _output_dir: &Path,
+    options: &CompileOptions,
+    cancel: Option<&CancelToken>,
+    progress: Option<&ProgressFn>,
+) -> BuildResult {
     let start = Instant::now();
+    let cancelled = |passes: u32| BuildResult {
+        success: false,
+        pdf_path: None,
+        log: String::new(),
+        duration_ms: start.elapsed().as_millis() as u64,
+        error_message: Some(BUILD_CANCELLED.to_string()),
+        error_kind: Some(BuildErrorKind::Cancelled),
+        passes,
+        ..Default::default()
+    };
+    if cancel.is_some_and(|c| c.is_cancelled()) {
+        return cancelled(0);
+    }
+    if !tex_path.is_file() {
+        return missing_source(tex_path, start.elapsed().as_millis() as u64);
+    }
+    if let Err(e) = validate_extra_args(&options.extra_args) {
+        return BuildResult {
+            success: false,
+            duration_ms: start.elapsed().as_millis() as u64,
+            error_message: Some(e),
+            error_kind: Some(BuildErrorKind::CompileError),
+            ..Default::default()
+        };
+    }
+    // A cycle would have the engine read the same files over and over
+    if let Err(e @ IncludeError::Cycle(_)) = include_tree(tex_path) {
+        return BuildResult {
+            success: false,
+            duration_ms: start.elapsed().as_millis() as u64,
+            error_message: Some(e.to_string()),
+            error_kind: Some(BuildErrorKind::CompileError),
+            ..Default::default()
+        };
+    }
     
-    // Use a temp directory for build artifacts (aux, log, etc)
-    let build_dir = get_build_dir();
+    // Use a separate directory for build artifacts (aux, log, etc)
+    let build_dir = resolve_build_dir(options.build_dir.as_deref(), tex_path);
 
-    // Ensure build directory exists
-    if let Err(e) = std::fs::create_dir_all(&build_dir) {
+    // Ensure build directory exists, is writable and has room for the output
+    let writable = ensure_writable_dir(&build_dir).and_then(|_| ensure_disk_space(&build_dir, 0));
+    if let Err(e) = writable {
         return BuildResult {
             success: false,
             pdf_path: None,
             log: String::new(),
             duration_ms: start.elapsed().as_millis() as u64,
-            error_message: Some(format!("Failed to create build directory: {}", e)),
+            error_message: Some(e),
+            error_kind: Some(BuildErrorKind::IoError),
+            ..Default::default()
         };
     }
 
-    // Run pdflatex asynchronously
-    let pdflatex_cmd = pdflatex::get_pdflatex_command();
-    let mut cmd = AsyncCommand::new(&pdflatex_cmd);
-    setup_pdflatex_command_args_async(&mut cmd, &pdflatex_cmd, &build_dir, tex_path);
-    
-    let result = cmd.output().await;
-    let duration_ms = start.elapsed().as_millis() as u64;
+    // Run the engine asynchronously
+    let engine = options.engine;
+    let engine_cmd = pdflatex::get_engine_command(engine);
 
-    process_compilation_result(
-        result.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-        tex_path,
-        &build_dir,
-        _output_dir,
-        duration_ms,
-        true, // Copy to source directory
+    let source = std::fs::read_to_string(tex_path).unwrap_or_default();
+    prepare_include_dirs(&source, &build_dir);
+
+    // Rerun in the same build directory until references settle
+    let max_passes = options.max_passes.max(1);
+    // MiKTeX can fail while installing a missing package on the fly; the
+    // package is usually there by the next attempt
+    let mut retries = 0;
+    let (mut build, passes, bibliography_log) = loop {
+        let mut passes = 0;
+        let mut bibliography_log = None;
+        let mut forced_passes_until = 0;
+        let result = loop {
+            passes += 1;
+            // Draft builds skip writing the PDF on passes certain to be rerun
+            let rerun_certain = passes < forced_passes_until
+                || (passes == 1 && max_passes > 1 && source_needs_rerun(&source));
+            let draft = DraftPass {
+                draft_graphics: options.draft,
+                no_output_flag: (options.draft && rerun_certain).then(|| engine.no_output_flag()),
+            };
+            let mut cmd = AsyncCommand::new(&engine_cmd);
+            setup_engine_args_async(
+                &mut cmd,
+                &engine_cmd,
+                &build_dir,
+                tex_path,
+                draft,
+                options,
+            );
+            cmd.kill_on_drop(true);
+
+            let Some(result) = run_cancellable(&mut cmd, passes, cancel, progress).await else {
+                return cancelled(passes);
+            };
+            let log = match &result {
+                Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+                Err(_) => break result,
+            };
+
+            if passes == 1 && !log.contains("Fatal error occurred") {
+                if let Some(tool) = bibliography_needed(tex_path, &build_dir, &log) {
+                    let Some(tool_log) =
+                        run_bibliography(tool, tex_path, &build_dir, passes, cancel, progress).await
+                    else {
+                        return cancelled(passes);
+                    };
+                    bibliography_log = Some(tool_log);
+                    forced_passes_until = passes + PASSES_AFTER_BIBLIOGRAPHY;
+                }
+            }
+            if passes < forced_passes_until {
+                continue;
+            }
+            if !needs_another_pass(&source, &log, passes, max_passes) {
+                break result;
+            }
+        };
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        let build = process_compilation_result(
+            result,
+            tex_path,
+            &build_dir,
+            duration_ms,
+            true, // Copy to source directory unless `in_place` is off
+            options,
+        );
+        if !build.success
+            && retries < options.install_retries
+            && should_retry_install(&engine_cmd, &build.log)
+        {
+            retries += 1;
+            tokio::time::sleep(INSTALL_RETRY_BACKOFF * retries).await;
+            continue;
+        }
+        break (build, passes, bibliography_log);
+    };
+    build.passes = passes;
+    build.retries = retries;
+    if options.interaction == InteractionMode::BatchMode {
+        // Batch mode keeps the terminal quiet; errors only reach the log file
+        if let Ok(engine_log) = std::fs::read(engine_log_path(tex_path, &build_dir)) {
+            let engine_log = String::from_utf8_lossy(&engine_log);
+            build.diagnostics.extend(parse_diagnostics(&engine_log));
+            build.log.push('\n');
+            build.log.push_str(&engine_log);
+        }
+    }
+    if let Some(tool_log) = bibliography_log {
+        build.bibliography_pass = true;
+        build.log.push('\n');
+        build.log.push_str(&tool_log);
+    }
+    build.diagnostics.extend(check_document_structure(&source));
+    if build.success {
+        build.page_count = parse_page_count(&build.log);
+        // Package announcements only go to the log file, not the terminal
+        let engine_log = std::fs::read(engine_log_path(tex_path, &build_dir)).unwrap_or_default();
+        let engine_log = String::from_utf8_lossy(&engine_log);
+        build.loaded_packages = parse_loaded_packages(&engine_log);
+        build.font_substitutions = parse_font_substitutions(&engine_log);
+    }
+    if !options.allow_shell_escape && needs_shell_escape(&build.log) {
+        build.diagnostics.push(Diagnostic::error(SHELL_ESCAPE_REQUIRED));
+    }
+    if let Some(warning) = page_limit_diagnostic(build.page_count, options.page_limit) {
+        build.diagnostics.push(warning);
+    }
+    // Counted before repeats are collapsed and the list is capped
+    let summary = summary_diagnostic(&build);
+    let limit = options.max_diagnostics.saturating_sub(1);
+    build.diagnostics = dedupe_diagnostics(build.diagnostics, limit);
+    enrich_diagnostics(
+        &source,
+        build.diagnostics.iter_mut().filter(|d| is_in_file(d, tex_path)),
+    );
+    build.diagnostics.insert(0, summary);
+    if let Some(logs_dir) = &options.logs_dir {
+        // A history that can't be written shouldn't fail the build
+        let record = BuildRecord::new(tex_path, engine, &build);
+        let _ = record_build(logs_dir, &record, &build.log);
+        let _ = save_diagnostics(logs_dir, tex_path, &source, &build.diagnostics);
+    }
+    build
+}
+
+/// Diagnostic for a build that failed because shell escape is off
+pub const SHELL_ESCAPE_REQUIRED: &str =
+    "This document needs shell escape (used by minted and svg). Allow shell escape in the settings and rebuild.";
+
+/// How packages refer to shell escape when complaining it's off
+///
+/// `\write18` isn't one: the engine's own banner mentions it on every run.
+const SHELL_ESCAPE_MARKERS: [&str; 2] = ["shell-escape", "shell escape"];
+
+/// Whether the log says the document needs shell escape, e.g. minted's
+/// "You must invoke LaTeX with the -shell-escape flag"
+fn needs_shell_escape(log: &str) -> bool {
+    log.lines().any(|line| {
+        let line = line.to_lowercase();
+        (line.contains("error") || line.contains("enable"))
+            && SHELL_ESCAPE_MARKERS.iter().any(|marker| line.contains(marker))
+    })
+}
+
+/// `1 page`, `2 pages`
+fn count_of(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+/// Info diagnostic summing up a build, e.g.
+/// "Build succeeded in 1.2s, 2 pages, 0 errors, 3 warnings"
+///
+/// Repeats count once per occurrence.
+fn summary_diagnostic(build: &BuildResult) -> Diagnostic {
+    let count = |severity| {
+        build
+            .diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .map(|d| d.occurrences as usize)
+            .sum()
+    };
+    let mut parts = vec![format!(
+        "Build {} in {:.1}s",
+        if build.success { "succeeded" } else { "failed" },
+        build.duration_ms as f64 / 1000.0
+    )];
+    parts.extend(build.page_count.map(|pages| count_of(pages as usize, "page")));
+    parts.push(count_of(count(Severity::Error), "error"));
+    parts.push(count_of(count(Severity::Warning), "warning"));
+    Diagnostic::info(parts.join(", "))
+}
+
+/// Info diagnostic for a document longer than `limit` pages
+fn page_limit_diagnostic(pages: Option<u32>, limit: Option<u32>) -> Option<Diagnostic> {
+    match (pages, limit) {
+        (Some(pages), Some(limit)) if pages > limit => Some(Diagnostic::info(format!(
+            "Document is {} pages long, over the limit of {}",
+            pages, limit
+        ))),
+        _ => None,
+    }
+}
+
+/// Characters besides non-ASCII ones that break kpathsea on some setups
+/// (MiKTeX and older TeX Live on Windows)
+const RISKY_PATH_CHARS: [char; 3] = [' ', '#', '%'];
+
+/// Warning for a source or build folder path the engine may not be able to open
+///
+/// Builds still run; this only explains the "no PDF generated" they end
+/// in on the setups that can't cope.
+pub fn path_warning(tex_path: &Path, options: &CompileOptions) -> Option<Diagnostic> {
+    let build_dir = resolve_build_dir(options.build_dir.as_deref(), tex_path);
+    let risky = [tex_path, build_dir.as_path()].into_iter().find(|path| {
+        path.to_string_lossy()
+            .chars()
+            .any(|c| !c.is_ascii() || RISKY_PATH_CHARS.contains(&c))
+    })?;
+    Some(Diagnostic::warning(format!(
+        "The path {} contains spaces, accents or other special characters, which some TeX \
+         installations can't handle. If the build produces no PDF, move the file (or the \
+         build folder) to a path with only plain letters and digits.",
+        risky.display()
+    )))
+}
+
+/// Folder in the build directory that unsaved content is compiled in
+const SCRATCH_DIR: &str = "scratch";
+
+/// Compile LaTeX source that hasn't been saved to a file
+///
+/// The content is written to a scratch `.tex` in the build directory and
+/// removed after the build; the PDF stays next to it for previewing.
+pub async fn compile_content_async(
+    content: &str,
+    options: &CompileOptions,
+    cancel: Option<&CancelToken>,
+    progress: Option<&ProgressFn>,
+) -> BuildResult {
+    compile_scratch_async("scratch", content, options, cancel, progress).await
+}
+
+/// Preamble a snippet is compiled with when none is given
+pub const DEFAULT_SNIPPET_PREAMBLE: &str = "\\documentclass{article}\n\\pagestyle{empty}\n";
+
+/// Wrap a document fragment in `preamble` (or a minimal default) so it
+/// compiles on its own
+pub fn snippet_document(preamble: Option<&str>, body: &str) -> String {
+    let preamble = preamble
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or(DEFAULT_SNIPPET_PREAMBLE);
+    format!(
+        "{}\n\\begin{{document}}\n{}\n\\end{{document}}\n",
+        preamble.trim_end(),
+        body.trim_end()
     )
 }
 
+/// Compile a fragment of a document, e.g. one table, for a quick preview
+///
+/// Works like [`compile_content_async`] with the fragment wrapped by
+/// [`snippet_document`]; its PDF is kept apart from the content preview.
+pub async fn compile_snippet_async(
+    preamble: Option<&str>,
+    body: &str,
+    options: &CompileOptions,
+    cancel: Option<&CancelToken>,
+    progress: Option<&ProgressFn>,
+) -> BuildResult {
+    let content = snippet_document(preamble, body);
+    compile_scratch_async("snippet", &content, options, cancel, progress).await
+}
+
+/// Compile `content` as `<name>.tex` in the scratch folder, removing it afterwards
+async fn compile_scratch_async(
+    name: &str,
+    content: &str,
+    options: &CompileOptions,
+    cancel: Option<&CancelToken>,
+    progress: Option<&ProgressFn>,
+) -> BuildResult {
+    let scratch_dir = options
+        .build_dir
+        .clone()
+        .unwrap_or_else(get_build_dir)
+        .join(SCRATCH_DIR);
+    let tex_path = scratch_dir.join(format!("{}.tex", name));
+    let written = ensure_writable_dir(&scratch_dir).and_then(|_| {
+        std::fs::write(&tex_path, content)
+            .map_err(|e| format!("Failed to write scratch file: {}", e))
+    });
+    if let Err(e) = written {
+        return BuildResult {
+            success: false,
+            error_message: Some(e),
+            error_kind: Some(BuildErrorKind::IoError),
+            ..Default::default()
+        };
+    }
+
+    // Artifacts go in a subfolder so the copied PDF doesn't overwrite the built one
+    let options = CompileOptions {
+        build_dir: Some(scratch_dir.join("build")),
+        ..options.clone()
+    };
+    let result = compile_latex_async(&tex_path, &scratch_dir, &options, cancel, progress).await;
+    let _ = std::fs::remove_file(&tex_path);
+    result
+}
+
 /// Compile a LaTeX file to PDF using pdflatex (sync version for tests)
 #[allow(dead_code)]
 pub fn compile_latex(tex_path: &Path, output_dir: &Path) -> BuildResult {
@@ -190,8 +1281,16 @@ pub fn compile_latex(tex_path: &Path, output_dir: &Path) -> BuildResult {
             log: String::new(),
             duration_ms: start.elapsed().as_millis() as u64,
             error_message: Some(format!("Failed to create output directory: {}", e)),
+            error_kind: Some(BuildErrorKind::IoError),
+            ..Default::default()
         };
     }
+    if !tex_path.is_file() {
+        return missing_source(tex_path, start.elapsed().as_millis() as u64);
+    }
+
+    let source = std::fs::read_to_string(tex_path).unwrap_or_default();
+    prepare_include_dirs(&source, output_dir);
 
     let pdflatex_cmd = pdflatex::get_pdflatex_command();
     let mut cmd = Command::new(&pdflatex_cmd);
@@ -200,14 +1299,16 @@ pub fn compile_latex(tex_path: &Path, output_dir: &Path) -> BuildResult {
     let result = cmd.output();
     let duration_ms = start.elapsed().as_millis() as u64;
 
-    process_compilation_result(
+    let mut build = process_compilation_result(
         result,
         tex_path,
         output_dir, // For sync version, build_dir == output_dir
-        output_dir,
         duration_ms,
         false, // Don't copy, use output_dir directly
-    )
+        &CompileOptions::default(),
+    );
+    build.passes = 1;
+    build
 }
 
 #[cfg(test)]
@@ -228,6 +1329,17 @@ mod tests {
             log: "Build log".to_string(),
             duration_ms: 1500,
             error_message: None,
+            error_kind: None,
+            passes: 1,
+            diagnostics: vec![],
+            bibliography_pass: false,
+            page_count: None,
+            build_pdf_path: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            loaded_packages: vec![],
+            font_substitutions: vec![],
+            retries: 0,
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -245,12 +1357,25 @@ mod tests {
             log: "Error occurred".to_string(),
             duration_ms: 50,
             error_message: Some("Compilation failed".to_string()),
+            error_kind: Some(BuildErrorKind::CompileError),
+            passes: 1,
+            diagnostics: vec![Diagnostic::error("Undefined control sequence.").with_line(4)],
+            bibliography_pass: false,
+            page_count: None,
+            build_pdf_path: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            loaded_packages: vec![],
+            font_substitutions: vec![],
+            retries: 0,
         };
 
         let json = serde_json::to_string(&result).unwrap();
         assert!(json.contains("\"success\":false"));
         assert!(json.contains("\"pdf_path\":null"));
         assert!(json.contains("\"error_message\":\"Compilation failed\""));
+        assert!(json.contains("\"error_kind\":\"compile_error\""));
+        assert!(json.contains("\"diagnostics\":[{\"severity\":\"error\""));
     }
 
     #[test]
@@ -261,6 +1386,17 @@ mod tests {
             log: "Log".to_string(),
             duration_ms: 100,
             error_message: None,
+            error_kind: None,
+            passes: 2,
+            diagnostics: vec![],
+            bibliography_pass: false,
+            page_count: None,
+            build_pdf_path: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            loaded_packages: vec![],
+            font_substitutions: vec![],
+            retries: 0,
         };
 
         let cloned = result.clone();
@@ -268,6 +1404,7 @@ mod tests {
         assert_eq!(result.pdf_path, cloned.pdf_path);
         assert_eq!(result.log, cloned.log);
         assert_eq!(result.duration_ms, cloned.duration_ms);
+        assert_eq!(result.passes, cloned.passes);
     }
 
     #[test]
@@ -278,6 +1415,17 @@ mod tests {
             log: String::new(),
             duration_ms: 0,
             error_message: None,
+            error_kind: None,
+            passes: 0,
+            diagnostics: vec![],
+            bibliography_pass: false,
+            page_count: None,
+            build_pdf_path: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            loaded_packages: vec![],
+            font_substitutions: vec![],
+            retries: 0,
         };
 
         let debug_str = format!("{:?}", result);
@@ -285,17 +1433,468 @@ mod tests {
         assert!(debug_str.contains("success: true"));
     }
 
-    // ============ compile_latex tests ============
+    // ============ multi-pass tests ============
 
     #[test]
-    fn test_compile_latex_creates_output_directory() {
-        let temp_dir = TempDir::new().unwrap();
-        let tex_path = temp_dir.path().join("test.tex");
-        let output_dir = temp_dir.path().join("build");
+    fn test_compile_options_default_two_passes() {
+        let options = CompileOptions::default();
+        assert_eq!(options.max_passes, 2);
+        assert_eq!(options.engine, LatexEngine::PdfLatex);
+    }
 
-        // Create a minimal tex file
-        let mut file = fs::File::create(&tex_path).unwrap();
-        writeln!(file, "\\documentclass{{article}}").unwrap();
+    #[test]
+    fn test_source_needs_rerun_detects_references() {
+        assert!(source_needs_rerun("See section~\\ref{sec:work}"));
+        assert!(source_needs_rerun("\\tableofcontents"));
+        assert!(source_needs_rerun("\\usepackage[hidelinks]{hyperref}"));
+        assert!(!source_needs_rerun("\\section{Experience}"));
+    }
+
+    #[test]
+    fn test_log_requests_rerun() {
+        let log = "LaTeX Warning: Label(s) may have changed. Rerun to get cross-references right.";
+        assert!(log_requests_rerun(log));
+        assert!(!log_requests_rerun("Output written on resume.pdf (1 page)."));
+    }
+
+    #[test]
+    fn test_needs_another_pass_respects_max_passes() {
+        let log = "Rerun to get cross-references right.";
+        assert!(needs_another_pass("", log, 1, 2));
+        assert!(!needs_another_pass("", log, 2, 2));
+        assert!(!needs_another_pass("\\label{x}", "", 1, 1));
+    }
+
+    #[test]
+    fn test_needs_another_pass_source_only_after_first_pass() {
+        let source = "\\label{sec:edu}";
+        assert!(needs_another_pass(source, "", 1, 3));
+        assert!(!needs_another_pass(source, "", 2, 3));
+    }
+
+    #[test]
+    fn test_needs_another_pass_skips_fatal_errors() {
+        let log = "! Emergency stop.\n!  ==> Fatal error occurred, no output PDF file produced!";
+        assert!(!needs_another_pass("\\ref{x}", log, 1, 2));
+    }
+
+    // ============ build directory tests ============
+
+    #[test]
+    fn test_resolve_build_dir_defaults_to_cache() {
+        let tex_path = Path::new("/home/me/resume.tex");
+        assert_eq!(resolve_build_dir(None, tex_path), build_dir_for(tex_path));
+        assert!(build_dir_for(tex_path).starts_with(get_build_dir()));
+        assert!(get_build_dir().ends_with("build"));
+    }
+
+    #[test]
+    fn test_resolve_build_dir_prefers_explicit() {
+        let explicit = Path::new("/tmp/my-project/build");
+        let tex_path = Path::new("/home/me/resume.tex");
+        assert_eq!(resolve_build_dir(Some(explicit), tex_path), explicit);
+    }
+
+    #[test]
+    fn test_build_dir_for_is_per_project() {
+        let first = build_dir_for(Path::new("/home/me/jobs/resume.tex"));
+        let second = build_dir_for(Path::new("/home/me/academic/resume.tex"));
+        assert_ne!(first, second);
+        // Stable across calls
+        assert_eq!(first, build_dir_for(Path::new("/home/me/jobs/resume.tex")));
+        assert_eq!(first.parent(), Some(get_build_dir().as_path()));
+    }
+
+    #[test]
+    fn test_ensure_writable_dir_creates_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("nested").join("build");
+        assert!(ensure_writable_dir(&dir).is_ok());
+        assert!(dir.exists());
+        // The probe file is cleaned up
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_ensure_writable_dir_rejects_file_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("not-a-dir");
+        fs::write(&file, "x").unwrap();
+        let err = ensure_writable_dir(&file).unwrap_err();
+        assert!(err.contains("Failed to create build directory"));
+    }
+
+    #[test]
+    fn test_compile_latex_async_reports_unwritable_build_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("test.tex");
+        fs::write(&tex_path, "\\documentclass{article}").unwrap();
+        let blocker = temp_dir.path().join("blocker");
+        fs::write(&blocker, "x").unwrap();
+
+        let options = CompileOptions {
+            build_dir: Some(blocker.join("build")),
+            ..Default::default()
+        };
+        let result = tauri::async_runtime::block_on(compile_latex_async(
+            &tex_path,
+            temp_dir.path(),
+            &options,
+            None,
+            None,
+        ));
+
+        assert!(!result.success);
+        assert!(result
+            .error_message
+            .unwrap()
+            .contains("Failed to create build directory"));
+        assert_eq!(result.error_kind, Some(BuildErrorKind::IoError));
+    }
+
+    // ============ working directory tests ============
+
+    #[test]
+    fn test_split_tex_path_uses_parent_as_work_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let (work_dir, tex_arg) = split_tex_path(&tex_path);
+        assert_eq!(work_dir.as_deref(), Some(temp_dir.path()));
+        assert_eq!(tex_arg, PathBuf::from("resume.tex"));
+    }
+
+    #[test]
+    fn test_split_tex_path_bare_file_name() {
+        let (work_dir, tex_arg) = split_tex_path(Path::new("resume.tex"));
+        assert!(work_dir.is_none());
+        assert_eq!(tex_arg, PathBuf::from("resume.tex"));
+    }
+
+    #[test]
+    fn test_command_runs_from_source_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let build_dir = temp_dir.path().join("build");
+
+        let mut cmd = Command::new("pdflatex");
+        setup_pdflatex_command_args(&mut cmd, "pdflatex", &build_dir, &tex_path);
+
+        assert_eq!(cmd.get_current_dir(), Some(temp_dir.path()));
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert_eq!(args.last().map(String::as_str), Some("resume.tex"));
+        assert!(!args.iter().any(|a| a.starts_with("-include-directory")));
+    }
+
+    fn env_value(cmd: &Command, key: &str) -> Option<String> {
+        cmd.get_envs()
+            .find(|(k, _)| *k == key)
+            .and_then(|(_, v)| v)
+            .map(|v| v.to_string_lossy().to_string())
+    }
+
+    #[test]
+    fn test_command_texinputs_includes_source_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+
+        let mut cmd = Command::new("pdflatex");
+        setup_pdflatex_command_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path);
+
+        let texinputs = env_value(&cmd, "TEXINPUTS").unwrap();
+        let separator = search_path_separator();
+        assert!(texinputs.starts_with(&format!("{}{}", temp_dir.path().display(), separator)));
+        assert!(texinputs.ends_with(separator));
+    }
+
+    #[test]
+    fn test_texinputs_adds_extra_search_paths_in_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let classes = temp_dir.path().join("classes");
+        let styles = temp_dir.path().join("styles");
+
+        let value = texinputs(Some(temp_dir.path()), &[classes.clone(), styles.clone()]);
+        let separator = search_path_separator();
+        let expected = format!(
+            "{}{sep}{}{sep}{}{sep}",
+            temp_dir.path().display(),
+            classes.display(),
+            styles.display(),
+            sep = separator
+        );
+        assert!(value.to_string_lossy().starts_with(&expected));
+    }
+
+    #[test]
+    fn test_command_writes_synctex() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+
+        let mut cmd = Command::new("pdflatex");
+        setup_pdflatex_command_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path);
+
+        assert!(cmd.get_args().any(|a| a == "-synctex=1"));
+    }
+
+    fn command_args(cmd: &Command) -> Vec<String> {
+        cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect()
+    }
+
+    #[test]
+    fn test_shell_escape_flag_follows_option() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+
+        let mut cmd = Command::new("pdflatex");
+        setup_pdflatex_command_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path);
+        let args = command_args(&cmd);
+        assert!(args.contains(&"-no-shell-escape".to_string()));
+        assert!(!args.contains(&"-shell-escape".to_string()));
+
+        let options = CompileOptions {
+            allow_shell_escape: true,
+            ..Default::default()
+        };
+        let mut cmd = Command::new("pdflatex");
+        let draft = DraftPass::default();
+        setup_engine_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path, draft, &options);
+        let args = command_args(&cmd);
+        assert!(args.contains(&"-shell-escape".to_string()));
+        assert!(!args.contains(&"-no-shell-escape".to_string()));
+    }
+
+    #[test]
+    fn test_interaction_flag_follows_option() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let modes = [
+            (InteractionMode::NonStopMode, "-interaction=nonstopmode"),
+            (InteractionMode::BatchMode, "-interaction=batchmode"),
+            (InteractionMode::ScrollMode, "-interaction=scrollmode"),
+            (InteractionMode::ErrorStopMode, "-interaction=errorstopmode"),
+        ];
+        for (interaction, flag) in modes {
+            let options = CompileOptions {
+                interaction,
+                ..Default::default()
+            };
+            let mut cmd = Command::new("pdflatex");
+            let draft = DraftPass::default();
+            setup_engine_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path, draft, &options);
+            let args = command_args(&cmd);
+            let flags: Vec<_> = args.iter().filter(|a| a.starts_with("-interaction=")).collect();
+            assert_eq!(flags, [flag], "{:?}", interaction);
+        }
+    }
+
+    #[test]
+    fn test_miktex_install_failure_triggers_retry() {
+        let log = concat!(
+            "This is MiKTeX-pdfTeX 4.10 (MiKTeX 22.3)\n",
+            "(resume.tex\n",
+            "LaTeX2e <2022-06-01>\n",
+            "Installing package fontawesome5...\n",
+            "! LaTeX Error: File `fontawesome5.sty' not found.\n",
+        );
+        assert!(is_package_install_failure(log));
+        assert!(is_package_install_failure(
+            "miktex-pdftex.exe: Trying to find fontawesome5.sty"
+        ));
+        assert!(!is_package_install_failure(
+            "! LaTeX Error: File `fontawesome5.sty' not found."
+        ));
+        assert_eq!(CompileOptions::default().install_retries, 1);
+    }
+
+    #[test]
+    fn test_install_retry_needs_miktex() {
+        let log = "! LaTeX Error: File `x.sty' not found.\ntrying to find a fallback\n";
+        let miktex = r"C:\Program Files\MiKTeX\miktex\bin\x64\pdflatex.exe";
+        assert!(should_retry_install(miktex, log));
+        assert!(!should_retry_install("/usr/local/texlive/2024/bin/x86_64-linux/pdflatex", log));
+        assert!(!should_retry_install("pdflatex", log));
+    }
+
+    #[test]
+    fn test_extra_args_come_before_tex_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let options = CompileOptions {
+            extra_args: vec!["-halt-on-error".to_string(), "-file-line-error".to_string()],
+            ..Default::default()
+        };
+
+        let mut cmd = Command::new("pdflatex");
+        let draft = DraftPass::default();
+        setup_engine_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path, draft, &options);
+        let args = command_args(&cmd);
+        let position = |arg: &str| args.iter().position(|a| a == arg).unwrap();
+        assert!(position("-halt-on-error") < position("-file-line-error"));
+        assert!(position("-file-line-error") < position("resume.tex"));
+    }
+
+    #[test]
+    fn test_reproducible_build_args() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let run = |reproducible: bool| {
+            let options = CompileOptions {
+                reproducible,
+                ..Default::default()
+            };
+            let mut cmd = Command::new("pdflatex");
+            let draft = DraftPass::default();
+            setup_engine_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path, draft, &options);
+            let epoch = cmd
+                .get_envs()
+                .find(|(key, _)| *key == "SOURCE_DATE_EPOCH")
+                .and_then(|(_, value)| value.map(|v| v.to_string_lossy().to_string()));
+            (command_args(&cmd), epoch)
+        };
+
+        let (args, epoch) = run(true);
+        assert!(args.iter().any(|a| a == "-output-format=pdf"));
+        if std::env::var_os("SOURCE_DATE_EPOCH").is_none() {
+            assert_eq!(epoch.as_deref(), Some(REPRODUCIBLE_EPOCH));
+        }
+        let (args, epoch) = run(false);
+        assert!(!args.iter().any(|a| a == "-output-format=pdf"));
+        assert_eq!(epoch, None);
+    }
+
+    #[test]
+    fn test_validate_extra_args() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert!(validate_extra_args(&args(&["-halt-on-error", "--file-line-error"])).is_ok());
+        assert!(validate_extra_args(&[]).is_ok());
+
+        let reserved = ["-output-directory=/tmp", "--jobname", "-shell-escape", "--aux-directory=x"];
+        for reserved in reserved {
+            let err = validate_extra_args(&args(&[reserved])).unwrap_err();
+            assert!(err.contains("set by the build"), "{}", err);
+        }
+        assert!(validate_extra_args(&args(&["other.tex"])).is_err());
+        assert!(validate_extra_args(&args(&["--"])).is_err());
+    }
+
+    #[test]
+    fn test_needs_shell_escape() {
+        assert!(needs_shell_escape(
+            "! Package minted Error: You must invoke LaTeX with the -shell-escape flag."
+        ));
+        assert!(needs_shell_escape(
+            "Package svg Warning: You didn't enable `shell escape' (or `write18')"
+        ));
+        assert!(!needs_shell_escape(" restricted \\write18 enabled."));
+        assert!(!needs_shell_escape("Output written on resume.pdf (1 page, 1234 bytes)."));
+    }
+
+    #[test]
+    fn test_draft_pass_args() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let draft = DraftPass {
+            draft_graphics: true,
+            no_output_flag: Some(LatexEngine::PdfLatex.no_output_flag()),
+        };
+
+        let mut cmd = Command::new("pdflatex");
+        let options = CompileOptions::default();
+        setup_engine_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path, draft, &options);
+
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert!(args.contains(&"-draftmode".to_string()));
+        assert!(args.contains(&"-jobname=resume".to_string()));
+        assert_eq!(
+            args.last().map(String::as_str),
+            Some("\\PassOptionsToPackage{draft}{graphicx}\\input{\"resume.tex\"}")
+        );
+    }
+
+    #[test]
+    fn test_draft_pass_quotes_or_skips_unusual_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let draft = DraftPass {
+            draft_graphics: true,
+            no_output_flag: None,
+        };
+        let options = CompileOptions::default();
+        let last_arg = |name: &str| {
+            let mut cmd = Command::new("pdflatex");
+            let tex_path = temp_dir.path().join(name);
+            setup_engine_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path, draft, &options);
+            cmd.get_args().last().unwrap().to_string_lossy().to_string()
+        };
+
+        assert_eq!(
+            last_arg("my resume.tex"),
+            "\\PassOptionsToPackage{draft}{graphicx}\\input{\"my resume.tex\"}"
+        );
+        // TeX would read `#` as a parameter, so the name goes in as is
+        assert_eq!(last_arg("my resume #2.tex"), "my resume #2.tex");
+        assert_eq!(draft_input(Path::new("100%.tex")), None);
+    }
+
+    #[test]
+    fn test_final_draft_pass_writes_pdf() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let draft = DraftPass {
+            draft_graphics: true,
+            no_output_flag: None,
+        };
+
+        let mut cmd = Command::new("pdflatex");
+        let options = CompileOptions::default();
+        setup_engine_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path, draft, &options);
+
+        assert!(!cmd.get_args().any(|a| a == "-draftmode"));
+    }
+
+    #[test]
+    fn test_normal_build_has_no_draft_args() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+
+        let mut cmd = Command::new("pdflatex");
+        setup_pdflatex_command_args(&mut cmd, "pdflatex", temp_dir.path(), &tex_path);
+
+        assert!(!cmd.get_args().any(|a| a == "-draftmode"));
+        assert!(!cmd.get_args().any(|a| a.to_string_lossy().starts_with("-jobname")));
+    }
+
+    #[test]
+    fn test_miktex_command_gets_include_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let miktex = "C:\\Program Files\\MiKTeX\\miktex\\bin\\x64\\pdflatex.exe";
+
+        let mut cmd = Command::new("pdflatex");
+        setup_pdflatex_command_args(&mut cmd, miktex, temp_dir.path(), &tex_path);
+
+        let expected = format!("-include-directory={}", temp_dir.path().to_string_lossy());
+        assert!(cmd.get_args().any(|a| a.to_string_lossy() == expected));
+    }
+
+    #[test]
+    fn test_prepare_include_dirs_mirrors_subfolders() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = "\\include{chapters/work}\n\\include{summary}\n\\input{sections/skills}";
+        prepare_include_dirs(source, temp_dir.path());
+        assert!(temp_dir.path().join("chapters").is_dir());
+        assert!(!temp_dir.path().join("sections").exists());
+    }
+
+    // ============ compile_latex tests ============
+
+    #[test]
+    fn test_compile_latex_creates_output_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("test.tex");
+        let output_dir = temp_dir.path().join("build");
+
+        // Create a minimal tex file
+        let mut file = fs::File::create(&tex_path).unwrap();
+        writeln!(file, "\\documentclass{{article}}").unwrap();
         writeln!(file, "\\begin{{document}}").unwrap();
         writeln!(file, "Hello").unwrap();
         writeln!(file, "\\end{{document}}").unwrap();
@@ -308,6 +1907,158 @@ mod tests {
         assert!(output_dir.exists());
     }
 
+    #[test]
+    fn test_compile_latex_async_cancelled_before_start() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("test.tex");
+        fs::write(&tex_path, "\\documentclass{article}").unwrap();
+
+        let token = CancelToken::default();
+        token.cancel();
+        let result = tauri::async_runtime::block_on(compile_latex_async(
+            &tex_path,
+            temp_dir.path(),
+            &CompileOptions::default(),
+            Some(&token),
+            None,
+        ));
+
+        assert!(!result.success);
+        assert_eq!(result.error_message.as_deref(), Some(BUILD_CANCELLED));
+        assert_eq!(result.error_kind, Some(BuildErrorKind::Cancelled));
+        assert_eq!(result.passes, 0);
+    }
+
+    #[test]
+    fn test_run_cancellable_stops_on_cancel() {
+        let token = CancelToken::default();
+        token.cancel();
+        let mut cmd = AsyncCommand::new("pdflatex");
+        cmd.arg("--version");
+        let result =
+            tauri::async_runtime::block_on(run_cancellable(&mut cmd, 1, Some(&token), None));
+        assert!(result.is_none());
+    }
+
+    // ============ progress tests ============
+
+    #[test]
+    fn test_parse_shipped_page_counts_markers() {
+        assert_eq!(parse_shipped_page("[1] [2] [12]"), Some(12));
+        assert_eq!(parse_shipped_page("[3{/usr/share/texmf/fonts/map/pdftex.map}]"), Some(3));
+        assert_eq!(parse_shipped_page("(./resume.aux) [1"), Some(1));
+        assert_eq!(parse_shipped_page("<cmr10.pfb>] [12]"), Some(12));
+    }
+
+    #[test]
+    fn test_parse_page_count_from_log() {
+        let log = "[1] [2] (./resume.aux) )\nOutput written on resume.pdf (2 pages, 12345 bytes).\n";
+        assert_eq!(parse_page_count(log), Some(2));
+        assert_eq!(
+            parse_page_count("Output written on resume.xdv (1 page, 4242 bytes)."),
+            Some(1)
+        );
+        assert_eq!(parse_page_count("No pages of output."), None);
+    }
+
+    #[test]
+    fn test_parse_page_count_wrapped_line() {
+        // The file name's own parentheses aren't the page count
+        let log = concat!(
+            "Output written on /home/jane/jobs/acme (2024)/resume-final-version.pdf (3\n",
+            " pages, 45678 bytes)."
+        );
+        assert_eq!(parse_page_count(log), Some(3));
+    }
+
+    #[test]
+    fn test_path_warning_for_spaces_and_unicode() {
+        let options = CompileOptions {
+            build_dir: Some(PathBuf::from("/tmp/build")),
+            ..Default::default()
+        };
+        for path in ["/home/jane/My Resume/resume.tex", "/home/jürgen/resume.tex"] {
+            let warning = path_warning(Path::new(path), &options).unwrap();
+            assert_eq!(warning.severity, crate::compiler::diagnostics::Severity::Warning);
+            assert!(warning.message.contains(path), "{}", warning.message);
+        }
+        assert!(path_warning(Path::new("/home/jane/resume.tex"), &options).is_none());
+
+        let options = CompileOptions {
+            build_dir: Some(PathBuf::from("/tmp/Büro/build")),
+            ..Default::default()
+        };
+        let warning = path_warning(Path::new("/home/jane/resume.tex"), &options).unwrap();
+        assert!(warning.message.contains("Büro"));
+    }
+
+    #[test]
+    fn test_summary_diagnostic_counts_errors_and_warnings() {
+        let log = "! Undefined control sequence.\n\
+                   l.12 \\badcommand\n\
+                   \n\
+                   LaTeX Warning: There were undefined references.\n\
+                   Overfull \\hbox (12.3pt too wide) in paragraph at lines 10--12\n";
+        let mut build = BuildResult {
+            success: true,
+            duration_ms: 1234,
+            page_count: Some(2),
+            diagnostics: parse_diagnostics(log),
+            ..Default::default()
+        };
+
+        let summary = summary_diagnostic(&build);
+        assert_eq!(summary.severity, Severity::Info);
+        assert_eq!(
+            summary.message,
+            "Build succeeded in 1.2s, 2 pages, 1 error, 2 warnings"
+        );
+
+        // Collapsed repeats still count once each
+        let repeated = format!("{}{}", log, log);
+        build.diagnostics = dedupe_diagnostics(parse_diagnostics(&repeated), 100);
+        assert_eq!(build.diagnostics.len(), 3);
+        assert_eq!(
+            summary_diagnostic(&build).message,
+            "Build succeeded in 1.2s, 2 pages, 2 errors, 4 warnings"
+        );
+
+        build.success = false;
+        build.page_count = None;
+        build.diagnostics.clear();
+        assert_eq!(
+            summary_diagnostic(&build).message,
+            "Build failed in 1.2s, 0 errors, 0 warnings"
+        );
+    }
+
+    #[test]
+    fn test_page_limit_diagnostic() {
+        let warning = page_limit_diagnostic(Some(2), Some(1)).unwrap();
+        assert_eq!(warning.severity, crate::compiler::diagnostics::Severity::Info);
+        assert!(warning.message.contains("2 pages"));
+        assert!(page_limit_diagnostic(Some(1), Some(1)).is_none());
+        assert!(page_limit_diagnostic(Some(3), None).is_none());
+        assert!(page_limit_diagnostic(None, Some(1)).is_none());
+    }
+
+    #[test]
+    fn test_parse_shipped_page_ignores_option_lists() {
+        assert_eq!(parse_shipped_page("\\documentclass[11pt]{article}"), None);
+        assert_eq!(parse_shipped_page("[utf8]{inputenc}"), None);
+        assert_eq!(parse_shipped_page("LaTeX2e <2022-11-01> patch level 1"), None);
+    }
+
+    #[test]
+    fn test_read_lines_reports_each_line() {
+        let input: &[u8] = b"This is pdfTeX\n[1] [2]\nOutput written";
+        let mut seen = Vec::new();
+        let collected =
+            tauri::async_runtime::block_on(read_lines(input, |line| seen.push(line.to_string())));
+        assert_eq!(seen, vec!["This is pdfTeX", "[1] [2]", "Output written"]);
+        assert_eq!(collected, input);
+    }
+
     #[test]
     fn test_compile_latex_with_nonexistent_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -319,6 +2070,292 @@ mod tests {
         // Should fail (file doesn't exist)
         assert!(!result.success);
         assert!(result.pdf_path.is_none());
+        assert_eq!(result.error_kind, Some(BuildErrorKind::CompileError));
+    }
+
+    #[test]
+    fn test_compile_latex_async_with_nonexistent_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("nonexistent.tex");
+
+        let result = tauri::async_runtime::block_on(compile_latex_async(
+            &tex_path,
+            temp_dir.path(),
+            &CompileOptions::default(),
+            None,
+            None,
+        ));
+
+        assert!(!result.success);
+        assert_eq!(result.error_kind, Some(BuildErrorKind::CompileError));
+        assert!(result.error_message.unwrap().contains("File not found"));
+    }
+
+    #[test]
+    fn test_compile_latex_async_stops_on_include_cycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        fs::write(&tex_path, "\\input{work}").unwrap();
+        fs::write(temp_dir.path().join("work.tex"), "\\input{resume}").unwrap();
+
+        let result = tauri::async_runtime::block_on(compile_latex_async(
+            &tex_path,
+            temp_dir.path(),
+            &CompileOptions::default(),
+            None,
+            None,
+        ));
+
+        assert!(!result.success);
+        assert_eq!(result.error_kind, Some(BuildErrorKind::CompileError));
+        assert!(result.error_message.unwrap().starts_with("Circular include"));
+        assert_eq!(result.passes, 0);
+    }
+
+    #[test]
+    fn test_missing_tool_is_reported_as_tool_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let output = Command::new(temp_dir.path().join("no-such-pdflatex")).output();
+
+        let result = process_compilation_result(
+            output,
+            &tex_path,
+            temp_dir.path(),
+            0,
+            true,
+            &CompileOptions::default(),
+        );
+
+        assert!(!result.success);
+        assert_eq!(result.error_kind, Some(BuildErrorKind::ToolMissing));
+        assert!(result.error_message.unwrap().contains("Make sure TeX Live or MiKTeX"));
+    }
+
+    #[test]
+    fn test_pdf_file_name() {
+        let tex_path = Path::new("/home/jane/main.tex");
+        assert_eq!(pdf_file_name(tex_path, Some("Jane_Doe_Resume")), "Jane_Doe_Resume.pdf");
+        assert_eq!(pdf_file_name(tex_path, Some("Jane Doe.PDF")), "Jane Doe.pdf");
+        assert_eq!(pdf_file_name(tex_path, Some("CV: 2024/25")), "CV_ 2024_25.pdf");
+        for unusable in [None, Some(""), Some("  "), Some(".."), Some("???"), Some(".pdf")] {
+            assert_eq!(pdf_file_name(tex_path, unusable), "main.pdf", "{:?}", unusable);
+        }
+    }
+
+    #[test]
+    fn test_build_log_path() {
+        let tex_path = Path::new("/home/jane/cv/resume.tex");
+        assert_eq!(
+            build_log_path(tex_path, Some(Path::new("/tmp/build"))),
+            Path::new("/tmp/build/resume.log")
+        );
+        assert_eq!(
+            build_log_path(tex_path, None),
+            build_dir_for(tex_path).join("resume.log")
+        );
+    }
+
+    #[test]
+    fn test_output_name_renames_copied_pdf() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("main.tex");
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir(&build_dir).unwrap();
+        std::fs::write(build_dir.join("main.pdf"), b"%PDF-1.5").unwrap();
+        let process = |output_name: Option<&str>| {
+            let output = Ok(std::process::Output {
+                status: exit_status(0),
+                stdout: b"This is pdfTeX, Version 3.141592653".to_vec(),
+                stderr: Vec::new(),
+            });
+            let options = CompileOptions {
+                output_name: output_name.map(str::to_string),
+                ..Default::default()
+            };
+            process_compilation_result(output, &tex_path, &build_dir, 0, true, &options)
+        };
+
+        let result = process(Some("Jane_Doe_Resume"));
+        let expected = temp_dir.path().join("Jane_Doe_Resume.pdf");
+        assert_eq!(result.pdf_path, Some(expected.to_string_lossy().to_string()));
+        assert!(expected.exists());
+        // The engine's own PDF keeps the source's name
+        let built = build_dir.join("main.pdf");
+        assert_eq!(result.build_pdf_path, Some(built.to_string_lossy().to_string()));
+
+        let result = process(Some("/"));
+        let expected = temp_dir.path().join("main.pdf");
+        assert_eq!(result.pdf_path, Some(expected.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn test_not_in_place_leaves_pdf_in_build_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("project");
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir(&project_dir).unwrap();
+        std::fs::create_dir(&build_dir).unwrap();
+        let tex_path = project_dir.join("main.tex");
+        std::fs::write(&tex_path, "\\documentclass{article}").unwrap();
+        std::fs::write(build_dir.join("main.pdf"), b"%PDF-1.5").unwrap();
+        let output = Ok(std::process::Output {
+            status: exit_status(0),
+            stdout: b"This is pdfTeX, Version 3.141592653".to_vec(),
+            stderr: Vec::new(),
+        });
+        let options = CompileOptions {
+            in_place: false,
+            output_name: Some("Jane_Doe_Resume".to_string()),
+            ..Default::default()
+        };
+
+        let result = process_compilation_result(output, &tex_path, &build_dir, 0, true, &options);
+
+        assert!(result.success);
+        let built = build_dir.join("main.pdf");
+        assert_eq!(result.pdf_path, Some(built.to_string_lossy().to_string()));
+        let entries: Vec<_> = std::fs::read_dir(&project_dir).unwrap().flatten().collect();
+        assert_eq!(entries.len(), 1, "{:?}", entries);
+    }
+
+    #[test]
+    fn test_failed_pdf_copy_falls_back_to_build_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let built_pdf = temp_dir.path().join("build").join("resume.pdf");
+        std::fs::create_dir_all(built_pdf.parent().unwrap()).unwrap();
+        std::fs::write(&built_pdf, b"%PDF-1.5").unwrap();
+        // A directory in the way makes the copy fail like a locked file would
+        let final_pdf = temp_dir.path().join("resume.pdf");
+        std::fs::create_dir(&final_pdf).unwrap();
+
+        let (delivered, warning) = copy_pdf_to_source(&built_pdf, &final_pdf);
+        assert_eq!(delivered, built_pdf);
+        let warning = warning.unwrap();
+        assert_eq!(warning.severity, crate::compiler::diagnostics::Severity::Warning);
+        assert!(warning.message.contains("build folder"), "{}", warning.message);
+
+        std::fs::remove_dir(&final_pdf).unwrap();
+        assert_eq!(copy_pdf_to_source(&built_pdf, &final_pdf), (final_pdf, None));
+    }
+
+    /// A finished engine run that printed the pdfTeX banner
+    fn tex_output(code: i32) -> std::io::Result<std::process::Output> {
+        Ok(std::process::Output {
+            status: exit_status(code),
+            stdout: b"This is pdfTeX, Version 3.141592653".to_vec(),
+            stderr: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_build_result_copies_pdf_only_after_success() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir(&build_dir).unwrap();
+        let built_pdf = build_dir.join("resume.pdf");
+        let copied_pdf = temp_dir.path().join("resume.pdf");
+        let process = |code: i32, in_place: bool| {
+            let options = CompileOptions {
+                in_place,
+                ..Default::default()
+            };
+            process_compilation_result(tex_output(code), &tex_path, &build_dir, 0, true, &options)
+        };
+
+        // No PDF: the build fails and nothing is copied
+        let result = process(1, true);
+        assert!(!result.success);
+        assert_eq!(result.pdf_path, None);
+        assert!(!copied_pdf.exists());
+
+        std::fs::write(&built_pdf, b"%PDF-1.5").unwrap();
+        let result = process(0, false);
+        assert!(result.success);
+        assert_eq!(result.pdf_path, Some(built_pdf.to_string_lossy().to_string()));
+        assert!(!copied_pdf.exists());
+
+        let result = process(0, true);
+        assert!(result.success);
+        assert_eq!(result.pdf_path, Some(copied_pdf.to_string_lossy().to_string()));
+        assert_eq!(std::fs::read(&copied_pdf).unwrap(), b"%PDF-1.5");
+    }
+
+    #[test]
+    fn test_build_result_survives_failed_pdf_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir(&build_dir).unwrap();
+        let built_pdf = build_dir.join("resume.pdf");
+        std::fs::write(&built_pdf, b"%PDF-1.5").unwrap();
+        // A directory in the way makes the copy fail like a locked file would
+        std::fs::create_dir(temp_dir.path().join("resume.pdf")).unwrap();
+
+        let options = CompileOptions::default();
+        let result =
+            process_compilation_result(tex_output(0), &tex_path, &build_dir, 0, true, &options);
+
+        assert!(result.success);
+        assert_eq!(result.error_kind, None);
+        assert_eq!(result.pdf_path, Some(built_pdf.to_string_lossy().to_string()));
+        assert!(result.diagnostics.iter().any(|d| {
+            d.severity == crate::compiler::diagnostics::Severity::Warning
+                && d.message.contains("build folder")
+        }));
+    }
+
+    fn exit_status(code: i32) -> std::process::ExitStatus {
+        #[cfg(unix)]
+        use std::os::unix::process::ExitStatusExt;
+        #[cfg(windows)]
+        use std::os::windows::process::ExitStatusExt;
+        #[cfg(unix)]
+        return std::process::ExitStatus::from_raw(code << 8);
+        #[cfg(windows)]
+        return std::process::ExitStatus::from_raw(code as u32);
+    }
+
+    #[test]
+    fn test_stdout_and_stderr_are_kept_apart() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        let output = std::process::Output {
+            status: exit_status(1),
+            stdout: b"This is pdfTeX, Version 3.141592653\n! Emergency stop.".to_vec(),
+            stderr: b"kpathsea: Running mktexfmt pdflatex.fmt".to_vec(),
+        };
+
+        let result = process_compilation_result(
+            Ok(output),
+            &tex_path,
+            temp_dir.path(),
+            0,
+            true,
+            &CompileOptions::default(),
+        );
+
+        assert_eq!(result.error_kind, Some(BuildErrorKind::CompileError));
+        assert_eq!(result.stdout, "This is pdfTeX, Version 3.141592653\n! Emergency stop.");
+        assert_eq!(result.stderr, "kpathsea: Running mktexfmt pdflatex.fmt");
+        assert!(result.log.contains(&result.stdout));
+        assert!(result.log.contains(&result.stderr));
+    }
+
+    #[test]
+    fn test_is_tex_output_recognizes_engine_banners() {
+        assert!(is_tex_output("This is pdfTeX, Version 3.141592653-2.6-1.40.25"));
+        assert!(is_tex_output("This is XeTeX, Version 3.141592653-2.6-0.999995"));
+        assert!(is_tex_output("This is LuaHBTeX, Version 1.17.0"));
+        assert!(!is_tex_output("usage: foo [options]"));
+        assert!(!is_tex_output(""));
+    }
+
+    #[test]
+    fn test_build_error_kind_serializes_snake_case() {
+        let json = serde_json::to_string(&BuildErrorKind::ToolMissing).unwrap();
+        assert_eq!(json, "\"tool_missing\"");
     }
 
     #[test]
@@ -374,6 +2411,342 @@ Hello, World!
         assert!(pdf_path.exists());
     }
 
+    #[test]
+    fn test_build_and_copied_pdf_paths_if_pdflatex_available() {
+        if !pdflatex::is_pdflatex_available() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        fs::write(
+            &tex_path,
+            "\\documentclass{article}\\begin{document}Hi\\end{document}",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            build_dir: Some(temp_dir.path().join("build")),
+            ..Default::default()
+        };
+
+        let result = tauri::async_runtime::block_on(compile_latex_async(
+            &tex_path,
+            temp_dir.path(),
+            &options,
+            None,
+            None,
+        ));
+
+        assert!(result.success, "{:?}", result.error_message);
+        let copied = PathBuf::from(result.pdf_path.unwrap());
+        let built = PathBuf::from(result.build_pdf_path.unwrap());
+        assert_eq!(copied, temp_dir.path().join("resume.pdf"));
+        assert_eq!(built, temp_dir.path().join("build").join("resume.pdf"));
+        assert!(copied.exists() && built.exists());
+        assert!(result.loaded_packages.iter().any(|p| p.name == "article"));
+    }
+
+    #[test]
+    fn test_compile_broken_file_reports_error_diagnostic() {
+        if !pdflatex::is_pdflatex_available() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("broken.tex");
+        let output_dir = temp_dir.path().join("output");
+
+        let content = r#"\documentclass{article}
+\begin{document}
+\badcommand
+\end{document}
+"#;
+        fs::write(&tex_path, content).unwrap();
+
+        let options = CompileOptions {
+            build_dir: Some(output_dir.clone()),
+            in_place: false,
+            ..Default::default()
+        };
+        let build = compile_latex_async(&tex_path, &output_dir, &options, None, None);
+        let result = current_thread_runtime()
+            .block_on(async { tokio::time::timeout(Duration::from_secs(120), build).await })
+            .expect("build timed out");
+
+        assert!(result
+            .diagnostics
+            .iter()
+            .any(|d| d.severity == crate::compiler::diagnostics::Severity::Error));
+    }
+
+    /// A runtime for driving the async build from a test (tokio's test
+    /// macros aren't enabled)
+    fn current_thread_runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_compile_latex_async_cancels_running_build() {
+        if !pdflatex::is_pdflatex_available() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("loop.tex");
+        let output_dir = temp_dir.path().join("output");
+        // Expands forever without using memory, so only cancelling ends it
+        fs::write(&tex_path, "\\def\\x{\\x}\\x").unwrap();
+
+        let token = CancelToken::default();
+        let canceller = token.clone();
+        let cancel_thread = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(500));
+            canceller.cancel();
+        });
+        let options = CompileOptions {
+            build_dir: Some(output_dir.clone()),
+            ..Default::default()
+        };
+        let build = compile_latex_async(&tex_path, &output_dir, &options, Some(&token), None);
+        let result = current_thread_runtime()
+            .block_on(async { tokio::time::timeout(Duration::from_secs(60), build).await })
+            .expect("cancelled build kept running");
+        cancel_thread.join().unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.error_kind, Some(BuildErrorKind::Cancelled));
+    }
+
+    #[test]
+    fn test_compile_latex_async_records_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        fs::write(&tex_path, "\\documentclass{article}").unwrap();
+        let logs_dir = temp_dir.path().join("logs");
+
+        let options = CompileOptions {
+            build_dir: Some(temp_dir.path().join("build")),
+            logs_dir: Some(logs_dir.clone()),
+            ..Default::default()
+        };
+        tauri::async_runtime::block_on(compile_latex_async(
+            &tex_path,
+            temp_dir.path(),
+            &options,
+            None,
+            None,
+        ));
+
+        let history = crate::compiler::history::read_history(&logs_dir, 10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].file, tex_path.to_string_lossy());
+        assert!(logs_dir.join(crate::compiler::history::LAST_LOG_FILE).exists());
+    }
+
+    #[test]
+    fn test_compile_content_removes_scratch_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let options = CompileOptions {
+            build_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        tauri::async_runtime::block_on(compile_content_async(
+            "\\documentclass{article}",
+            &options,
+            None,
+            None,
+        ));
+
+        assert!(!temp_dir.path().join(SCRATCH_DIR).join("scratch.tex").exists());
+    }
+
+    #[test]
+    fn test_reproducible_builds_are_identical_if_pdflatex_available() {
+        if !pdflatex::is_pdflatex_available() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        fs::write(
+            &tex_path,
+            "\\documentclass{article}\\begin{document}Jane Doe\\end{document}",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            build_dir: Some(temp_dir.path().join("build")),
+            reproducible: true,
+            ..Default::default()
+        };
+        let build = || {
+            let result = tauri::async_runtime::block_on(compile_latex_async(
+                &tex_path,
+                temp_dir.path(),
+                &options,
+                None,
+                None,
+            ));
+            assert!(result.success, "{:?}", result.error_message);
+            fs::read(result.pdf_path.unwrap()).unwrap()
+        };
+
+        let first = build();
+        std::thread::sleep(Duration::from_millis(1100));
+        assert_eq!(first, build());
+    }
+
+    #[test]
+    fn test_compile_content_if_pdflatex_available() {
+        if !pdflatex::is_pdflatex_available() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let options = CompileOptions {
+            build_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let compile = |content: &str| {
+            tauri::async_runtime::block_on(compile_content_async(content, &options, None, None))
+        };
+
+        let result = compile("\\documentclass{article}\\begin{document}Draft\\end{document}");
+        assert!(
+            result.success,
+            "Expected success, got: {:?}",
+            result.error_message
+        );
+        assert!(Path::new(&result.pdf_path.unwrap()).exists());
+
+        let result =
+            compile("\\documentclass{article}\\begin{document}\\badcommand\\end{document}");
+        assert!(result
+            .diagnostics
+            .iter()
+            .any(|d| d.severity == crate::compiler::diagnostics::Severity::Error));
+    }
+
+    #[test]
+    fn test_snippet_document_wraps_body() {
+        assert_eq!(
+            snippet_document(None, "\\textbf{Skills}\n"),
+            "\\documentclass{article}\n\\pagestyle{empty}\n\\begin{document}\n\
+             \\textbf{Skills}\n\\end{document}\n"
+        );
+        let preamble = "\\documentclass{article}\n\\usepackage{booktabs}";
+        let document = snippet_document(Some(preamble), "x");
+        assert!(document.starts_with(&format!("{}\n\\begin{{document}}", preamble)));
+        // A blank preamble falls back to the default
+        assert_eq!(snippet_document(Some("  \n"), "x"), snippet_document(None, "x"));
+    }
+
+    #[test]
+    fn test_compile_snippet_if_pdflatex_available() {
+        if !pdflatex::is_pdflatex_available() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let options = CompileOptions {
+            build_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let body = "\\begin{tabular}{ll}\nRust & 5 years \\\\\n\\end{tabular}";
+        let result =
+            tauri::async_runtime::block_on(compile_snippet_async(None, body, &options, None, None));
+        assert!(
+            result.success,
+            "Expected success, got: {:?}",
+            result.error_message
+        );
+        let pdf_path = result.pdf_path.unwrap();
+        assert!(pdf_path.ends_with("snippet.pdf"), "{}", pdf_path);
+        assert!(Path::new(&pdf_path).exists());
+        assert!(!temp_dir.path().join(SCRATCH_DIR).join("snippet.tex").exists());
+    }
+
+    #[test]
+    fn test_compile_resolves_input_in_subdirectory() {
+        if !pdflatex::is_pdflatex_available() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("main.tex");
+        let output_dir = temp_dir.path().join("output");
+        fs::create_dir(temp_dir.path().join("sections")).unwrap();
+        fs::write(
+            temp_dir.path().join("sections").join("experience.tex"),
+            "Experience goes here.\n",
+        )
+        .unwrap();
+
+        let content = r#"\documentclass{article}
+\begin{document}
+\input{sections/experience}
+\end{document}
+"#;
+        fs::write(&tex_path, content).unwrap();
+
+        let result = compile_latex(&tex_path, &output_dir);
+
+        assert!(
+            result.success,
+            "Expected success, got: {:?}",
+            result.error_message
+        );
+    }
+
+    #[test]
+    fn test_compile_runs_bibtex_for_citations() {
+        if !pdflatex::is_pdflatex_available() || !pdflatex::is_tool_available("bibtex") {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("cv.tex");
+        fs::write(
+            temp_dir.path().join("publications.bib"),
+            r#"@article{knuth84,
+  author = {Donald E. Knuth},
+  title = {Literate Programming},
+  journal = {The Computer Journal},
+  year = {1984}
+}
+"#,
+        )
+        .unwrap();
+
+        let content = r#"\documentclass{article}
+\begin{document}
+Publications: \cite{knuth84}
+\bibliographystyle{plain}
+\bibliography{publications}
+\end{document}
+"#;
+        fs::write(&tex_path, content).unwrap();
+
+        let options = CompileOptions {
+            build_dir: Some(temp_dir.path().join("build")),
+            ..Default::default()
+        };
+        let result = tauri::async_runtime::block_on(compile_latex_async(
+            &tex_path,
+            temp_dir.path(),
+            &options,
+            None,
+            None,
+        ));
+
+        assert!(result.success, "Expected success, got: {:?}", result.error_message);
+        assert!(result.bibliography_pass);
+        assert_eq!(result.passes, 1 + PASSES_AFTER_BIBLIOGRAPHY);
+        assert!(!result.log.contains("Citation `knuth84' on page 1 undefined"));
+    }
+
     #[test]
     fn test_pdf_path_derives_from_tex_name() {
         if !pdflatex::is_pdflatex_available() {
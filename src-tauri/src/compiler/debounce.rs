@@ -0,0 +1,139 @@
+//! Coalescing of rapid build requests for auto-build on save
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::build::BuildResult;
+
+/// Quiet period after the last request before a build starts
+pub const DEBOUNCE_DELAY: Duration = Duration::from_millis(500);
+
+/// What happened to a debounced build request
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "status", content = "result", rename_all = "lowercase")]
+pub enum DebouncedBuild {
+    /// The build ran and this is its result
+    Ran(Box<BuildResult>),
+    /// A newer request arrived during the quiet period and took over
+    Debounced,
+    /// A build was already running; one follow-up build is queued
+    Queued,
+    /// Auto-build is turned off in settings
+    Disabled,
+}
+
+/// Decision for a request whose quiet period has elapsed
+#[derive(Debug, PartialEq, Eq)]
+pub enum StartDecision {
+    /// Nothing newer arrived and nothing is running: build now
+    Start,
+    /// A newer request superseded this one
+    Superseded,
+    /// A build is running; a follow-up has been queued instead
+    Queued,
+}
+
+#[derive(Debug, Default)]
+struct DebounceState {
+    /// Incremented on every request; the latest ticket wins
+    generation: u64,
+    running: bool,
+    follow_up: bool,
+}
+
+/// Debounce bookkeeping shared between build requests
+#[derive(Debug, Default)]
+pub struct Debouncer {
+    state: Mutex<DebounceState>,
+}
+
+impl Debouncer {
+    /// Register a request and return its ticket
+    ///
+    /// Returns `None` if a build is running, in which case exactly one
+    /// follow-up build is queued instead.
+    pub fn request(&self) -> Option<u64> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.generation += 1;
+        if state.running {
+            state.follow_up = true;
+            return None;
+        }
+        Some(state.generation)
+    }
+
+    /// Decide whether the request holding `ticket` should build now
+    pub fn try_start(&self, ticket: u64) -> StartDecision {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.generation != ticket {
+            StartDecision::Superseded
+        } else if state.running {
+            state.follow_up = true;
+            StartDecision::Queued
+        } else {
+            state.running = true;
+            StartDecision::Start
+        }
+    }
+
+    /// Mark the running build finished
+    ///
+    /// Returns `true` if a follow-up was queued; the caller should build
+    /// again and call `finish` once more.
+    pub fn finish(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.follow_up {
+            state.follow_up = false;
+            true
+        } else {
+            state.running = false;
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_request_starts() {
+        let debouncer = Debouncer::default();
+        let ticket = debouncer.request().unwrap();
+        assert_eq!(debouncer.try_start(ticket), StartDecision::Start);
+        assert!(!debouncer.finish());
+    }
+
+    #[test]
+    fn test_newer_request_supersedes_older() {
+        let debouncer = Debouncer::default();
+        let first = debouncer.request().unwrap();
+        let second = debouncer.request().unwrap();
+        assert_eq!(debouncer.try_start(first), StartDecision::Superseded);
+        assert_eq!(debouncer.try_start(second), StartDecision::Start);
+    }
+
+    #[test]
+    fn test_requests_while_running_queue_one_follow_up() {
+        let debouncer = Debouncer::default();
+        let ticket = debouncer.request().unwrap();
+        assert_eq!(debouncer.try_start(ticket), StartDecision::Start);
+
+        assert!(debouncer.request().is_none());
+        assert!(debouncer.request().is_none());
+
+        // Exactly one follow-up, then idle
+        assert!(debouncer.finish());
+        assert!(!debouncer.finish());
+        assert!(debouncer.request().is_some());
+    }
+
+    #[test]
+    fn test_debounced_build_serializes_status() {
+        let json = serde_json::to_string(&DebouncedBuild::Queued).unwrap();
+        assert_eq!(json, r#"{"status":"queued"}"#);
+
+        let json = serde_json::to_string(&DebouncedBuild::Ran(Box::default())).unwrap();
+        assert!(json.starts_with(r#"{"status":"ran","result":{"#));
+    }
+}
@@ -0,0 +1,1121 @@
+//! Diagnostics parsed from LaTeX engine output
+
+use std::collections::HashMap;
+
+use crate::latex::strip_line_comment;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// A single error or warning reported by the engine
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// How many times the same message appeared in the log
+    pub occurrences: u32,
+    /// File the engine couldn't find (e.g. `fontawesome.sty`), for offering an install
+    pub missing_file: Option<String>,
+    /// TeX capacity that ran out (e.g. `main memory size`)
+    #[serde(default)]
+    pub capacity: Option<String>,
+    /// Byte range in the source the diagnostic covers, set by `enrich_diagnostics`
+    pub offset_start: Option<usize>,
+    pub offset_end: Option<usize>,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic with no location information
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            file: None,
+            line: None,
+            column: None,
+            occurrences: 1,
+            missing_file: None,
+            capacity: None,
+            offset_start: None,
+            offset_end: None,
+        }
+    }
+
+    /// Create an error diagnostic
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    /// Create a warning diagnostic
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    /// Create an info diagnostic
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(Severity::Info, message)
+    }
+
+    /// Attach the source file the diagnostic refers to
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Attach a 1-based source line
+    pub fn with_line(mut self, line: u32) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    /// Attach a 1-based source column
+    pub fn with_column(mut self, column: u32) -> Self {
+        self.column = Some(column);
+        self
+    }
+}
+
+/// Byte offset at which 1-based `line` starts in `content`
+pub fn line_byte_offset(content: &str, line: u32) -> Option<usize> {
+    line_span(content, line).map(|(start, _)| start)
+}
+
+/// Byte range of 1-based `line` in `content`, without its line break
+fn line_span(content: &str, line: u32) -> Option<(usize, usize)> {
+    let index = (line as usize).checked_sub(1)?;
+    let mut start = 0;
+    let mut count = 0;
+    for text in content.split_inclusive('\n') {
+        if count == index {
+            let text = text.strip_suffix('\n').unwrap_or(text);
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            return Some((start, start + text.len()));
+        }
+        start += text.len();
+        count += 1;
+    }
+    // The empty line after a final line break
+    (index == count && content.ends_with('\n')).then_some((start, start))
+}
+
+/// Set the byte offsets of diagnostics that point into `content`
+///
+/// The range covers the diagnostic's line, starting at its column if it
+/// has one. Diagnostics without a line, or past the end, are left as they are.
+pub fn enrich_diagnostics<'a>(
+    content: &str,
+    diagnostics: impl IntoIterator<Item = &'a mut Diagnostic>,
+) {
+    for diagnostic in diagnostics {
+        let Some((line_start, line_end)) =
+            diagnostic.line.and_then(|line| line_span(content, line))
+        else {
+            continue;
+        };
+        let column_offset = diagnostic.column.and_then(|column| {
+            content[line_start..line_end]
+                .char_indices()
+                .nth((column as usize).checked_sub(1)?)
+                .map(|(offset, _)| offset)
+        });
+        diagnostic.offset_start = Some(line_start + column_offset.unwrap_or(0));
+        diagnostic.offset_end = Some(line_end);
+    }
+}
+
+/// How many lines after a `!` error to search for its `l.NNN` marker
+const LINE_MARKER_LOOKAHEAD: usize = 20;
+
+/// Parse the line number from an `l.123 \badcommand` marker
+pub fn parse_line_number(line: &str) -> Option<u32> {
+    let rest = line.strip_prefix("l.")?;
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Source position and fragment recovered from an error's context lines
+#[derive(Debug, PartialEq)]
+struct ErrorContext {
+    column: Option<u32>,
+    fragment: String,
+}
+
+/// Parse the two-line context TeX prints after an error
+///
+/// TeX splits the offending source line at the error position: the
+/// `l.NNN` line holds the text already read and the following line,
+/// indented to the same width, holds the rest.
+fn parse_error_context(marker_line: &str, next_line: Option<&str>) -> ErrorContext {
+    let digits = marker_line[2..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    let prefix = &marker_line[..2 + digits];
+    let read = marker_line[prefix.len()..]
+        .strip_prefix(' ')
+        .unwrap_or(&marker_line[prefix.len()..]);
+    let prefix_width = marker_line.chars().count() - read.chars().count();
+
+    let continuation = next_line.filter(|l| l.starts_with(' ') && !l.trim().is_empty());
+    let unread = continuation.map(|l| l.trim_start()).unwrap_or("");
+
+    // A leading "..." means TeX truncated the line, so offsets are unreliable
+    let column = if read.starts_with("...") {
+        None
+    } else if let Some(l) = continuation {
+        let indent = l.chars().take_while(|c| *c == ' ').count();
+        indent
+            .checked_sub(prefix_width)
+            .map(|offset| offset as u32 + 1)
+    } else {
+        Some(read.chars().count() as u32 + 1)
+    };
+
+    ErrorContext {
+        column,
+        fragment: format!("{}{}", read, unread).trim().to_string(),
+    }
+}
+
+/// The file name in a "File `X.sty' not found" or "I can't find file `X'" error
+fn parse_missing_file(message: &str) -> Option<String> {
+    let (start, end) = ["File `", "can't find file `"]
+        .iter()
+        .find_map(|prefix| {
+            let start = message.find(prefix)? + prefix.len();
+            let end = start + message[start..].find('\'')?;
+            Some((start, end))
+        })?;
+    let name = message[start..end].trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// The capacity named in "TeX capacity exceeded, sorry [main memory size=5000000]"
+fn parse_capacity(message: &str) -> Option<String> {
+    let rest = message.strip_prefix("TeX capacity exceeded")?;
+    let start = rest.find('[')? + 1;
+    let end = start + rest[start..].find(['=', ']'])?;
+    let name = rest[start..end].trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// What usually exhausts each TeX capacity
+fn capacity_hint(capacity: &str) -> &'static str {
+    match capacity {
+        "main memory size" => {
+            "likely a runaway macro, or a table or picture too large to fit on one page"
+        }
+        "hash size" => "too many commands defined; likely a loop defining new commands",
+        "save size" => "too many nested groups or assignments; likely a runaway macro",
+        "input stack size" => "likely a macro that calls itself without end",
+        "grouping levels" => "too many nested groups; likely unbalanced braces in a macro",
+        _ => "likely a runaway macro or deeply nested content",
+    }
+}
+
+/// Extract the message from a `LaTeX Warning:` / `Package foo Warning:` line
+fn parse_warning(line: &str) -> Option<String> {
+    let index = line.find("Warning: ")?;
+    let prefix = &line[..index];
+    let is_tex_warning = prefix.starts_with("LaTeX")
+        || prefix.starts_with("Package")
+        || prefix.starts_with("Class")
+        || prefix.starts_with("pdfTeX");
+    if !is_tex_warning {
+        return None;
+    }
+    Some(line[index + "Warning: ".len()..].trim().to_string())
+}
+
+/// Split a trailing `on input line N.` off a warning message
+fn split_input_line(message: &str) -> Option<(String, u32)> {
+    let index = message.rfind("on input line ")?;
+    let digits: String = message[index + "on input line ".len()..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    let line = digits.parse().ok()?;
+    let text = message[..index].trim_end();
+    let stripped = if message.ends_with('.') {
+        format!("{}.", text)
+    } else {
+        text.to_string()
+    };
+    Some((stripped, line))
+}
+
+/// Build a warning diagnostic from the log line at `index`
+///
+/// A trailing `on input line N` becomes the diagnostic's line, including
+/// when TeX has wrapped it onto the next log line.
+fn parse_warning_at(lines: &[&str], index: usize) -> Option<Diagnostic> {
+    let message = parse_warning(lines[index])?;
+    let wrapped = lines.get(index + 1).map(|next| {
+        // Package warnings indent continuations with "(package)"
+        let next = next.trim();
+        let next = match next.strip_prefix('(').and_then(|n| n.split_once(')')) {
+            Some((_, rest)) => rest.trim(),
+            None => next,
+        };
+        format!("{} {}", message, next)
+    });
+
+    let parsed = split_input_line(&message).or_else(|| {
+        wrapped
+            .filter(|w| w.contains("input line") && !message.ends_with('.'))
+            .and_then(|w| split_input_line(&w))
+    });
+    Some(match parsed {
+        Some((message, line)) => Diagnostic::warning(message).with_line(line),
+        None => Diagnostic::warning(message),
+    })
+}
+
+/// Box warnings TeX emits when content doesn't fit its container
+const BOX_WARNING_PREFIXES: [&str; 4] = [
+    "Overfull \\hbox",
+    "Underfull \\hbox",
+    "Overfull \\vbox",
+    "Underfull \\vbox",
+];
+
+/// Parse an `Overfull \hbox (12.3pt too wide) ... at lines 10--12` warning
+fn parse_box_warning(line: &str) -> Option<Diagnostic> {
+    let kind = BOX_WARNING_PREFIXES
+        .iter()
+        .find(|prefix| line.starts_with(*prefix))?;
+
+    // "12.3pt too wide" or "badness 10000"
+    let detail = line.find('(').and_then(|open| {
+        line[open + 1..]
+            .find(')')
+            .map(|close| &line[open + 1..open + 1 + close])
+    });
+    let message = match detail {
+        Some(detail) => format!("{}: {}", kind, detail),
+        None => kind.to_string(),
+    };
+
+    let mut diagnostic = Diagnostic::warning(message);
+    let location = line
+        .find("at lines ")
+        .map(|i| &line[i + "at lines ".len()..])
+        .or_else(|| line.find("at line ").map(|i| &line[i + "at line ".len()..]));
+    if let Some(location) = location {
+        let digits: String = location
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if let Ok(line_number) = digits.parse() {
+            diagnostic = diagnostic.with_line(line_number);
+        }
+    }
+    Some(diagnostic)
+}
+
+/// Parse a `Missing character: There is no X in font Y!` line
+///
+/// The character is dropped from the PDF without any error, leaving a
+/// blank where it should be.
+fn parse_missing_character(line: &str) -> Option<Diagnostic> {
+    let rest = line
+        .strip_prefix("Missing character: There is no ")?
+        .trim_end()
+        .trim_end_matches('!');
+    let (character, font) = rest.rsplit_once(" in font ")?;
+    // XeTeX appends features ("[lmroman10-regular]:mapping=tex-text;")
+    let font = match font.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(bracketed),
+        None => font.split(':').next().unwrap_or(font),
+    };
+    let font = font.trim_matches('"');
+    Some(Diagnostic::warning(format!("Missing character {} in font {}", character, font)))
+}
+
+/// Parse engine output into structured diagnostics
+///
+/// Recognizes `! ...` errors (with their `l.NNN` line marker, column and
+/// source fragment), `file.tex:42: ...` errors from `-file-line-error`,
+/// `LaTeX`/`Package`/`Class` warnings, over/underfull box warnings and
+/// characters missing from a font.
+pub fn parse_diagnostics(log: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = log.lines().collect();
+    let mut diagnostics = Vec::new();
+    let mut files = FileStack::default();
+    // Error context echoes user source, whose parentheses aren't file frames
+    let mut context_end: Option<usize> = None;
+    let mut runaway_end: Option<usize> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let parsed = if runaway_end.is_some_and(|end| i <= end) {
+            // Already folded into the runaway diagnostic
+            None
+        } else if is_runaway(line) {
+            let (diagnostic, end) = parse_runaway(&lines, i);
+            runaway_end = Some(end);
+            context_end = Some(end);
+            Some(diagnostic)
+        } else if let Some((file, line_number, message)) = parse_file_line_error(line) {
+            let (diagnostic, marker_index) = parse_error(&lines, i, message);
+            context_end = Some(marker_index.map(|j| j + 1).unwrap_or(i));
+            Some(diagnostic.with_file(file).with_line(line_number))
+        } else if let Some(message) = line.strip_prefix("! ") {
+            let (diagnostic, marker_index) = parse_error(&lines, i, message);
+            context_end = Some(marker_index.map(|j| j + 1).unwrap_or(i));
+            Some(diagnostic)
+        } else if let Some(diagnostic) = parse_box_warning(line) {
+            Some(diagnostic)
+        } else if let Some(diagnostic) = parse_missing_character(line) {
+            Some(diagnostic)
+        } else {
+            parse_warning_at(&lines, i)
+        };
+
+        if let Some(mut diagnostic) = parsed {
+            if let (None, Some(file)) = (&diagnostic.file, files.current()) {
+                diagnostic = diagnostic.with_file(file);
+            }
+            diagnostics.push(diagnostic);
+        }
+
+        match context_end {
+            Some(end) if i <= end => {}
+            _ => files.scan(line),
+        }
+    }
+
+    diagnostics
+}
+
+/// Default cap on the diagnostics returned from a build
+pub const DEFAULT_MAX_DIAGNOSTICS: usize = 100;
+
+/// Collapse repeated diagnostics and cap how many are returned
+///
+/// Diagnostics with the same severity and message are merged into the
+/// first one, keeping its location and counting `occurrences`. Beyond
+/// `limit`, errors are kept before warnings and the rest are summarized
+/// in a final info diagnostic.
+pub fn dedupe_diagnostics(diagnostics: Vec<Diagnostic>, limit: usize) -> Vec<Diagnostic> {
+    let mut seen: HashMap<(Severity, String), usize> = HashMap::new();
+    let mut unique: Vec<Diagnostic> = Vec::new();
+    for diagnostic in diagnostics {
+        let key = (diagnostic.severity, diagnostic.message.clone());
+        match seen.get(&key) {
+            Some(&index) => unique[index].occurrences += diagnostic.occurrences,
+            None => {
+                seen.insert(key, unique.len());
+                unique.push(diagnostic);
+            }
+        }
+    }
+
+    if unique.len() <= limit {
+        return unique;
+    }
+    let suppressed = unique.len() - limit;
+    let rank = |severity: Severity| match severity {
+        Severity::Error => 0,
+        Severity::Warning => 1,
+        Severity::Info => 2,
+    };
+    let mut order: Vec<usize> = (0..unique.len()).collect();
+    order.sort_by_key(|&i| (rank(unique[i].severity), i));
+    let mut keep = vec![false; unique.len()];
+    for &i in &order[..limit] {
+        keep[i] = true;
+    }
+
+    let mut kept: Vec<Diagnostic> = unique
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(diagnostic, keep)| keep.then_some(diagnostic))
+        .collect();
+    kept.push(Diagnostic::info(format!(
+        "{} more diagnostics suppressed",
+        suppressed
+    )));
+    kept
+}
+
+/// Build an error diagnostic from a `! message` line at `index`
+///
+/// Returns the diagnostic and the index of its `l.NNN` marker, if found.
+fn parse_error(lines: &[&str], index: usize, message: &str) -> (Diagnostic, Option<usize>) {
+    let mut diagnostic = Diagnostic::error(message.trim());
+    diagnostic.missing_file = parse_missing_file(message);
+    diagnostic.capacity = parse_capacity(message);
+    if let Some(capacity) = &diagnostic.capacity {
+        diagnostic.message = format!(
+            "TeX capacity exceeded ({}): {}",
+            capacity,
+            capacity_hint(capacity)
+        );
+    }
+    let marker = lines
+        .iter()
+        .enumerate()
+        .skip(index + 1)
+        .take(LINE_MARKER_LOOKAHEAD)
+        .take_while(|(_, l)| !is_error_line(l))
+        .find_map(|(j, l)| parse_line_number(l).map(|n| (j, n)));
+
+    let Some((j, line_number)) = marker else {
+        return (diagnostic, None);
+    };
+
+    diagnostic = diagnostic.with_line(line_number);
+    let context = parse_error_context(lines[j], lines.get(j + 1).copied());
+    if let Some(column) = context.column {
+        diagnostic = diagnostic.with_column(column);
+    }
+    if !context.fragment.is_empty() {
+        diagnostic.message = format!("{} (near `{}`)", diagnostic.message, context.fragment);
+    }
+    (diagnostic, Some(j))
+}
+
+/// Source file types that `-file-line-error` errors point into
+const SOURCE_EXTENSIONS: [&str; 3] = [".tex", ".sty", ".cls"];
+
+/// Split a `-file-line-error` error such as `./resume.tex:42: Undefined
+/// control sequence.` into file, line and message
+///
+/// The file may be a Windows path, so the first `:` isn't necessarily the
+/// separator.
+fn parse_file_line_error(line: &str) -> Option<(&str, u32, &str)> {
+    line.match_indices(':').find_map(|(index, _)| {
+        let file = &line[..index];
+        if !SOURCE_EXTENSIONS.iter().any(|ext| file.ends_with(ext)) {
+            return None;
+        }
+        let rest = &line[index + 1..];
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        let message = rest[digits..].strip_prefix(": ")?;
+        Some((file, rest[..digits].parse().ok()?, message))
+    })
+}
+
+/// Whether `line` starts an error, in either format
+fn is_error_line(line: &str) -> bool {
+    line.starts_with("! ") || parse_file_line_error(line).is_some()
+}
+
+/// Whether `line` opens a "Runaway argument?" (or text, definition...) block
+fn is_runaway(line: &str) -> bool {
+    line.starts_with("Runaway ") && line.trim_end().ends_with('?')
+}
+
+/// Build one error from a runaway block starting at `index`
+///
+/// TeX prints `Runaway argument?`, the tokens it swallowed, then the `!`
+/// error (usually "Paragraph ended before \foo was complete") and its
+/// `l.NNN` marker. The whole block becomes a single diagnostic; returns it
+/// with the index of the block's last line.
+fn parse_runaway(lines: &[&str], index: usize) -> (Diagnostic, usize) {
+    // "argument", "text", "definition", ...
+    let what = lines[index]["Runaway ".len()..].trim_end().trim_end_matches('?');
+    let mut tokens = Vec::new();
+    let mut error = None;
+    for (j, line) in lines
+        .iter()
+        .enumerate()
+        .skip(index + 1)
+        .take(LINE_MARKER_LOOKAHEAD)
+    {
+        if let Some(message) = line.strip_prefix("! ") {
+            error = Some((j, message, None));
+            break;
+        }
+        if let Some((file, _, message)) = parse_file_line_error(line) {
+            error = Some((j, message, Some(file)));
+            break;
+        }
+        if parse_line_number(line).is_some() {
+            break;
+        }
+        tokens.push(line.trim());
+    }
+
+    let tokens = tokens.join(" ");
+    let hint = match tokens.trim() {
+        "" => "likely a missing closing brace".to_string(),
+        tokens => format!("likely a missing closing brace after `{}`", tokens),
+    };
+    let Some((j, message, file)) = error else {
+        let diagnostic = Diagnostic::error(format!("Runaway {}: {}", what, hint));
+        return (diagnostic, index);
+    };
+
+    let (mut diagnostic, marker) = parse_error(lines, j, message);
+    diagnostic.message = format!("{} (runaway {}: {})", diagnostic.message, what, hint);
+    diagnostic.file = file.map(str::to_string);
+    (diagnostic, marker.map(|m| m + 1).unwrap_or(j))
+}
+
+/// Tracks the input file being read from the `(file ... )` frames in the log
+#[derive(Debug, Default)]
+struct FileStack {
+    /// One entry per open parenthesis; `None` for parens that aren't files
+    frames: Vec<Option<String>>,
+}
+
+impl FileStack {
+    /// The innermost open file, if any
+    fn current(&self) -> Option<&str> {
+        self.frames.iter().rev().find_map(|frame| frame.as_deref())
+    }
+
+    /// Push and pop frames for every paren on a log line
+    fn scan(&mut self, line: &str) {
+        let mut rest = line;
+        while let Some(i) = rest.find(['(', ')']) {
+            if rest[i..].starts_with(')') {
+                self.frames.pop();
+                rest = &rest[i + 1..];
+                continue;
+            }
+
+            let after = &rest[i + 1..];
+            let (token, consumed) = match after.strip_prefix('"') {
+                // MiKTeX quotes paths containing spaces
+                Some(quoted) => match quoted.find('"') {
+                    Some(end) => (&quoted[..end], end + 2),
+                    None => (quoted, after.len()),
+                },
+                None => {
+                    let end = after
+                        .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+                        .unwrap_or(after.len());
+                    (&after[..end], end)
+                }
+            };
+            if looks_like_file(token) {
+                self.frames.push(Some(token.to_string()));
+                rest = &after[consumed..];
+            } else {
+                self.frames.push(None);
+                rest = after;
+            }
+        }
+    }
+}
+
+/// Heuristic for whether the text after `(` is a file path rather than prose
+fn looks_like_file(token: &str) -> bool {
+    let starts_like_path = token.starts_with('.')
+        || token.starts_with('/')
+        || token.starts_with('\\')
+        || token
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic());
+    let has_extension = token
+        .rsplit_once('.')
+        .map(|(stem, ext)| {
+            !stem.is_empty()
+                && (1..=5).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+                && ext.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        })
+        .unwrap_or(false);
+    starts_like_path && has_extension
+}
+
+/// Quick structural check of a source file before it is compiled
+///
+/// Warns when `\begin{document}` has no matching `\end{document}`, a
+/// common cause of "no PDF generated". Files without a document
+/// environment (e.g. `\input` fragments) are not checked.
+pub fn check_document_structure(source: &str) -> Option<Diagnostic> {
+    let mut begin_line = None;
+    let mut ends = 0;
+    for (index, line) in source.lines().enumerate() {
+        let code = strip_line_comment(line);
+        if begin_line.is_none() && code.contains("\\begin{document}") {
+            begin_line = Some(index as u32 + 1);
+        }
+        ends += code.matches("\\end{document}").count();
+    }
+
+    let begin_line = begin_line?;
+    (ends == 0).then(|| {
+        Diagnostic::warning("Missing \\end{document} for \\begin{document}").with_line(begin_line)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_number() {
+        assert_eq!(parse_line_number("l.42 \\badcommand"), Some(42));
+        assert_eq!(parse_line_number("l.7"), Some(7));
+        assert_eq!(parse_line_number("line 7"), None);
+        assert_eq!(parse_line_number("l.abc"), None);
+    }
+
+    #[test]
+    fn test_parse_undefined_control_sequence() {
+        let log = "! Undefined control sequence.\n\
+                   l.12 \\badcommand\n\
+                   \n\
+                   The control sequence at the end of the top line\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(
+            diagnostics[0].message,
+            "Undefined control sequence. (near `\\badcommand`)"
+        );
+        assert_eq!(diagnostics[0].line, Some(12));
+        assert_eq!(diagnostics[0].column, Some(12));
+    }
+
+    #[test]
+    fn test_parse_runaway_argument_block() {
+        let log = concat!(
+            "(./resume.tex\n",
+            "Runaway argument?\n",
+            "{Software Engineer \\item Built the (internal) billing\n",
+            "! Paragraph ended before \\textbf was complete.\n",
+            "<to be read again> \n",
+            "                   \\par \n",
+            "l.14 \n",
+            "     \n",
+            "I suspect you've forgotten a `}', causing me to apply this\n",
+        );
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].line, Some(14));
+        assert_eq!(diagnostics[0].file.as_deref(), Some("./resume.tex"));
+        assert_eq!(
+            diagnostics[0].message,
+            concat!(
+                "Paragraph ended before \\textbf was complete. (runaway argument: ",
+                "likely a missing closing brace after ",
+                "`{Software Engineer \\item Built the (internal) billing`)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_file_line_error() {
+        let log = concat!(
+            "(./resume.tex\n",
+            "./sections/work.tex:42: Undefined control sequence.\n",
+            "l.42 \\badcommand\n",
+            "\n",
+        );
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].file.as_deref(), Some("./sections/work.tex"));
+        assert_eq!(diagnostics[0].line, Some(42));
+        assert_eq!(diagnostics[0].column, Some(12));
+        assert_eq!(
+            diagnostics[0].message,
+            "Undefined control sequence. (near `\\badcommand`)"
+        );
+    }
+
+    #[test]
+    fn test_parse_file_line_error_with_windows_path() {
+        let log = "C:\\Users\\Jane Doe\\resume.tex:7: LaTeX Error: File `cv.cls' not found.\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].file.as_deref(), Some("C:\\Users\\Jane Doe\\resume.tex"));
+        assert_eq!(diagnostics[0].line, Some(7));
+        assert_eq!(diagnostics[0].missing_file.as_deref(), Some("cv.cls"));
+        assert_eq!(diagnostics[0].message, "LaTeX Error: File `cv.cls' not found.");
+    }
+
+    #[test]
+    fn test_file_line_error_requires_line_number() {
+        assert_eq!(parse_file_line_error("./resume.tex: hello"), None);
+        assert_eq!(parse_file_line_error("see resume.tex:12 for details"), None);
+        assert_eq!(parse_file_line_error("./a.sty:3: Oops"), Some(("./a.sty", 3, "Oops")));
+    }
+
+    #[test]
+    fn test_parse_missing_characters() {
+        let log = concat!(
+            "Missing character: There is no \u{2b24} (U+2B24) in font ",
+            "[lmroman10-regular]:mapping=tex-text;!\n",
+            "Missing character: There is no \u{fc} in font cmr10!\n",
+            "Missing character: There is no \u{2b24} (U+2B24) in font ",
+            "[lmroman10-regular]:mapping=tex-text;!\n",
+            "Missing character: There is no \u{2713} in font ",
+            "\"Latin Modern Roman/OT\":script=latn;!\n",
+        );
+        let diagnostics = dedupe_diagnostics(parse_diagnostics(log), DEFAULT_MAX_DIAGNOSTICS);
+        assert_eq!(diagnostics.len(), 3);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+        assert_eq!(
+            diagnostics[0].message,
+            "Missing character \u{2b24} (U+2B24) in font lmroman10-regular"
+        );
+        assert_eq!(diagnostics[0].occurrences, 2);
+        assert_eq!(diagnostics[1].message, "Missing character \u{fc} in font cmr10");
+        assert_eq!(
+            diagnostics[2].message,
+            "Missing character \u{2713} in font Latin Modern Roman/OT"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_column_from_continuation_line() {
+        let log = "! Undefined control sequence.\n\
+                   l.5 Experience at \\badcommand\n                             {Company}\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(5));
+        // `{` follows the 25 characters already read
+        assert_eq!(diagnostics[0].column, Some(26));
+        assert_eq!(
+            diagnostics[0].message,
+            "Undefined control sequence. (near `Experience at \\badcommand{Company}`)"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_column_with_multi_digit_line() {
+        let log = "! Missing $ inserted.\n\
+                   <inserted text> \n\
+                   \x20               $\n\
+                   l.123 Salary: 100_\n                  000\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics[0].line, Some(123));
+        assert_eq!(diagnostics[0].column, Some(13));
+        assert!(diagnostics[0].message.contains("Salary: 100_000"));
+    }
+
+    #[test]
+    fn test_parse_error_truncated_context_has_no_column() {
+        let context = parse_error_context("l.88 ...long line \\oops", Some("    rest"));
+        assert_eq!(context.column, None);
+    }
+
+    #[test]
+    fn test_parse_latex_warning() {
+        let log = "LaTeX Warning: There were undefined references.\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].message, "There were undefined references.");
+    }
+
+    #[test]
+    fn test_parse_package_warning() {
+        let log = "Package hyperref Warning: Token not allowed in a PDF string.\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Token not allowed in a PDF string.");
+    }
+
+    #[test]
+    fn test_parse_overfull_hbox() {
+        let log = "Overfull \\hbox (12.3pt too wide) in paragraph at lines 10--12\n\
+                   []\\T1/cmr/m/n/10 Software Engineer|\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].message, "Overfull \\hbox: 12.3pt too wide");
+        assert_eq!(diagnostics[0].line, Some(10));
+    }
+
+    #[test]
+    fn test_parse_underfull_hbox_badness() {
+        let log = "Underfull \\hbox (badness 10000) in paragraph at lines 5--6\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Underfull \\hbox: badness 10000");
+        assert_eq!(diagnostics[0].line, Some(5));
+    }
+
+    #[test]
+    fn test_parse_overfull_hbox_detected_at_line() {
+        let log = "Overfull \\hbox (0.8pt too wide) detected at line 42\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics[0].line, Some(42));
+    }
+
+    #[test]
+    fn test_parse_vbox_warnings_without_line() {
+        let log = "Overfull \\vbox (3.0pt too high) has occurred while \\output is active\n\
+                   Underfull \\vbox (badness 10000) has occurred while \\output is active\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "Overfull \\vbox: 3.0pt too high");
+        assert_eq!(diagnostics[0].line, None);
+        assert_eq!(diagnostics[1].message, "Underfull \\vbox: badness 10000");
+    }
+
+    #[test]
+    fn test_parse_error_in_nested_input_file() {
+        let log = "(./resume.tex\n\
+                   LaTeX2e <2022-11-01>\n\
+                   (/usr/share/texlive/texmf-dist/tex/latex/base/article.cls\n\
+                   Document Class: article 2022/07/02 v1.4n Standard LaTeX document class\n\
+                   (/usr/share/texlive/texmf-dist/tex/latex/base/size10.clo))\n\
+                   (./sections/experience.tex (./sections/jobs.tex\n\
+                   ! Undefined control sequence.\n\
+                   l.3 \\badcommand\n\
+                   \n\
+                   ) [1])\n\
+                   Overfull \\hbox (2.0pt too wide) in paragraph at lines 20--21\n\
+                   ) )\n\
+                   LaTeX Warning: There were undefined references.\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].file.as_deref(), Some("./sections/jobs.tex"));
+        assert_eq!(diagnostics[0].line, Some(3));
+        assert_eq!(diagnostics[1].file.as_deref(), Some("./resume.tex"));
+        assert_eq!(diagnostics[2].file, None);
+    }
+
+    #[test]
+    fn test_file_stack_ignores_non_file_parens() {
+        let mut files = FileStack::default();
+        files.scan("(./resume.tex (badness 10000) (see the transcript file)");
+        assert_eq!(files.current(), Some("./resume.tex"));
+        files.scan("(./a.tex) (./b.tex");
+        assert_eq!(files.current(), Some("./b.tex"));
+        files.scan("))");
+        assert_eq!(files.current(), None);
+    }
+
+    #[test]
+    fn test_file_stack_handles_quoted_paths() {
+        let mut files = FileStack::default();
+        files.scan("(\"C:\\Program Files\\MiKTeX\\tex\\latex\\base\\article.cls\"");
+        assert_eq!(
+            files.current(),
+            Some("C:\\Program Files\\MiKTeX\\tex\\latex\\base\\article.cls")
+        );
+    }
+
+    #[test]
+    fn test_parse_clean_log_has_no_diagnostics() {
+        let log = "This is pdfTeX, Version 3.141592653\nOutput written on resume.pdf (1 page).\n";
+        assert!(parse_diagnostics(log).is_empty());
+    }
+
+    #[test]
+    fn test_diagnostic_serializes() {
+        let diagnostic = Diagnostic::error("Missing $ inserted.")
+            .with_file("resume.tex")
+            .with_line(3);
+        let json = serde_json::to_string(&diagnostic).unwrap();
+        assert!(json.contains("\"severity\":\"error\""));
+        assert!(json.contains("\"file\":\"resume.tex\""));
+        assert!(json.contains("\"line\":3"));
+        assert!(json.contains("\"column\":null"));
+    }
+
+    #[test]
+    fn test_line_byte_offset_multibyte() {
+        // "é" and "ü" are two bytes each, "€" three
+        let content = "Résumé\r\nMüller – €5\n\nEnd";
+        assert_eq!(line_byte_offset(content, 1), Some(0));
+        assert_eq!(line_byte_offset(content, 2), Some(10));
+        assert_eq!(line_byte_offset(content, 3), Some(27));
+        assert_eq!(line_byte_offset(content, 4), Some(28));
+        assert_eq!(line_byte_offset(content, 5), None);
+        assert_eq!(line_byte_offset(content, 0), None);
+        assert_eq!(&content[10..16], "Mülle");
+    }
+
+    #[test]
+    fn test_enrich_diagnostics_sets_byte_ranges() {
+        let content = "Résumé\r\nMüller – €5\n";
+        let mut diagnostics = vec![
+            Diagnostic::error("whole line").with_line(2),
+            Diagnostic::error("from column").with_line(2).with_column(10),
+            Diagnostic::error("past the end").with_line(4),
+            Diagnostic::warning("no line"),
+        ];
+        enrich_diagnostics(content, &mut diagnostics);
+
+        let ranges: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.offset_start, d.offset_end))
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                (Some(10), Some(26)),
+                (Some(22), Some(26)),
+                (None, None),
+                (None, None),
+            ]
+        );
+        assert_eq!(&content[22..26], "€5");
+        assert_eq!(diagnostics[3].offset_start, None);
+    }
+
+    #[test]
+    fn test_structure_missing_end_document() {
+        let source = "\\documentclass{article}\n\\begin{document}\nHello\n";
+        let diagnostic = check_document_structure(source).unwrap();
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.line, Some(2));
+    }
+
+    #[test]
+    fn test_structure_ignores_commented_end_document() {
+        let source = "\\begin{document}\nHello\n% \\end{document}\n";
+        assert!(check_document_structure(source).is_some());
+
+        let source = "\\begin{document}\n50\\% done\n\\end{document}\n";
+        assert!(check_document_structure(source).is_none());
+    }
+
+    #[test]
+    fn test_structure_skips_fragments() {
+        assert!(check_document_structure("\\section{Experience}\n").is_none());
+    }
+
+    #[test]
+    fn test_dedupe_collapses_identical_warnings() {
+        let mut diagnostics: Vec<Diagnostic> = (1..=50)
+            .map(|line| Diagnostic::warning("Font shape `OT1/cmr/bx/sc' undefined").with_line(line))
+            .collect();
+        diagnostics.insert(10, Diagnostic::error("Undefined control sequence").with_line(7));
+
+        let deduped = dedupe_diagnostics(diagnostics, DEFAULT_MAX_DIAGNOSTICS);
+        assert_eq!(deduped.len(), 2);
+        assert_eq!(deduped[0].occurrences, 50);
+        assert_eq!(deduped[0].line, Some(1));
+        assert_eq!(deduped[1].severity, Severity::Error);
+        assert_eq!(deduped[1].occurrences, 1);
+    }
+
+    #[test]
+    fn test_dedupe_keeps_same_message_with_different_severity() {
+        let diagnostics = vec![Diagnostic::warning("Same"), Diagnostic::info("Same")];
+        assert_eq!(dedupe_diagnostics(diagnostics, 10).len(), 2);
+    }
+
+    #[test]
+    fn test_dedupe_caps_and_prefers_errors() {
+        let mut diagnostics: Vec<Diagnostic> = (0..5)
+            .map(|i| Diagnostic::warning(format!("Warning {}", i)))
+            .collect();
+        diagnostics.push(Diagnostic::error("Fatal"));
+
+        let capped = dedupe_diagnostics(diagnostics, 3);
+        let messages: Vec<&str> = capped.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["Warning 0", "Warning 1", "Fatal", "3 more diagnostics suppressed"]
+        );
+        assert_eq!(capped[3].severity, Severity::Info);
+    }
+
+    #[test]
+    fn test_missing_file_is_extracted() {
+        let log = "\
+! LaTeX Error: File `fontawesome.sty' not found.
+
+Type X to quit or <RETURN> to proceed,
+! LaTeX Error: File `moderncv.cls' not found.
+! LaTeX Error: File `t2aenc.def' not found.
+! Undefined control sequence.
+";
+        let diagnostics = parse_diagnostics(log);
+        let missing: Vec<Option<&str>> = diagnostics
+            .iter()
+            .map(|d| d.missing_file.as_deref())
+            .collect();
+        assert_eq!(
+            missing,
+            [
+                Some("fontawesome.sty"),
+                Some("moderncv.cls"),
+                Some("t2aenc.def"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_capacity_exceeded_names_the_capacity() {
+        let log = concat!(
+            "(./resume.tex\n",
+            "! TeX capacity exceeded, sorry [main memory size=5000000].\n",
+            "\\cell ->\\cell \n",
+            "                \\relax \n",
+            "l.42 \\cell\n",
+            "            \n",
+            "If you really absolutely need more capacity,\n",
+        );
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].capacity.as_deref(), Some("main memory size"));
+        assert_eq!(diagnostics[0].line, Some(42));
+        assert!(diagnostics[0]
+            .message
+            .starts_with("TeX capacity exceeded (main memory size): likely a runaway macro"));
+    }
+
+    #[test]
+    fn test_parse_capacity() {
+        assert_eq!(
+            parse_capacity("TeX capacity exceeded, sorry [save size=80000]."),
+            Some("save size".to_string())
+        );
+        assert_eq!(parse_capacity("Undefined control sequence."), None);
+    }
+
+    #[test]
+    fn test_missing_input_file_is_extracted() {
+        assert_eq!(
+            parse_missing_file("I can't find file `sections/work.tex'."),
+            Some("sections/work.tex".to_string())
+        );
+    }
+
+    #[test]
+    fn test_warning_on_input_line() {
+        let log = "LaTeX Warning: Reference `sec:work' on page 1 undefined on input line 88.\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Reference `sec:work' on page 1 undefined.");
+        assert_eq!(diagnostics[0].line, Some(88));
+    }
+
+    #[test]
+    fn test_wrapped_package_warning_on_input_line() {
+        let log = "Package hyperref Warning: Token not allowed in a PDF string (Unicode):\n\
+                   (hyperref)                removing `\\\\' on input line 12.\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(12));
+        assert_eq!(
+            diagnostics[0].message,
+            "Token not allowed in a PDF string (Unicode): removing `\\\\'."
+        );
+    }
+
+    #[test]
+    fn test_error_line_not_taken_from_input_line_text() {
+        let log = "! Undefined control sequence.\n\
+                   l.7 \\foo\n\
+                   LaTeX Warning: Citation `x' undefined on input line 9.\n";
+        let diagnostics = parse_diagnostics(log);
+        assert_eq!(diagnostics[0].line, Some(7));
+        assert_eq!(diagnostics[1].line, Some(9));
+    }
+}
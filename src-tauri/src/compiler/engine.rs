@@ -0,0 +1,159 @@
+//! LaTeX engine selection
+
+use std::fmt;
+use std::str::FromStr;
+
+/// TeX engine used to compile a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LatexEngine {
+    #[default]
+    PdfLatex,
+    XeLatex,
+    LuaLatex,
+}
+
+impl LatexEngine {
+    /// All supported engines, in order of preference
+    pub const ALL: [LatexEngine; 3] = [
+        LatexEngine::PdfLatex,
+        LatexEngine::XeLatex,
+        LatexEngine::LuaLatex,
+    ];
+
+    /// Flag that runs the engine without writing a PDF, for intermediate passes
+    pub fn no_output_flag(&self) -> &'static str {
+        match self {
+            LatexEngine::PdfLatex | LatexEngine::LuaLatex => "-draftmode",
+            LatexEngine::XeLatex => "-no-pdf",
+        }
+    }
+
+    /// Flag pinning the output to PDF; xelatex has no such option and always
+    /// writes PDF unless told `-no-pdf`
+    pub fn output_format_flag(&self) -> Option<&'static str> {
+        match self {
+            LatexEngine::PdfLatex | LatexEngine::LuaLatex => Some("-output-format=pdf"),
+            LatexEngine::XeLatex => None,
+        }
+    }
+
+    /// Name of the engine executable (without extension)
+    pub fn binary_name(&self) -> &'static str {
+        match self {
+            LatexEngine::PdfLatex => "pdflatex",
+            LatexEngine::XeLatex => "xelatex",
+            LatexEngine::LuaLatex => "lualatex",
+        }
+    }
+}
+
+impl fmt::Display for LatexEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.binary_name())
+    }
+}
+
+impl FromStr for LatexEngine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "pdflatex" => Ok(LatexEngine::PdfLatex),
+            "xelatex" => Ok(LatexEngine::XeLatex),
+            "lualatex" => Ok(LatexEngine::LuaLatex),
+            other => Err(format!("Unknown LaTeX engine: {}", other)),
+        }
+    }
+}
+
+/// How the engine behaves when it hits an error (`-interaction=`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InteractionMode {
+    /// Keep going past errors without stopping; the usual build
+    #[default]
+    NonStopMode,
+    /// Like nonstop, but print nothing to the terminal
+    BatchMode,
+    /// Keep going past errors, but stop when a file can't be found
+    ScrollMode,
+    /// Stop at the first error; with no input to give, the build ends there
+    ErrorStopMode,
+}
+
+impl InteractionMode {
+    /// The engine flag selecting this mode
+    pub fn flag(&self) -> &'static str {
+        match self {
+            InteractionMode::NonStopMode => "-interaction=nonstopmode",
+            InteractionMode::BatchMode => "-interaction=batchmode",
+            InteractionMode::ScrollMode => "-interaction=scrollmode",
+            InteractionMode::ErrorStopMode => "-interaction=errorstopmode",
+        }
+    }
+}
+
+impl FromStr for InteractionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "nonstopmode" => Ok(InteractionMode::NonStopMode),
+            "batchmode" => Ok(InteractionMode::BatchMode),
+            "scrollmode" => Ok(InteractionMode::ScrollMode),
+            "errorstopmode" => Ok(InteractionMode::ErrorStopMode),
+            other => Err(format!("Unknown interaction mode: {}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_engine_is_pdflatex() {
+        assert_eq!(LatexEngine::default(), LatexEngine::PdfLatex);
+    }
+
+    #[test]
+    fn test_binary_names() {
+        assert_eq!(LatexEngine::PdfLatex.binary_name(), "pdflatex");
+        assert_eq!(LatexEngine::XeLatex.binary_name(), "xelatex");
+        assert_eq!(LatexEngine::LuaLatex.binary_name(), "lualatex");
+    }
+
+    #[test]
+    fn test_parse_engine_case_insensitive() {
+        assert_eq!("XeLaTeX".parse::<LatexEngine>(), Ok(LatexEngine::XeLatex));
+        assert_eq!(
+            " lualatex ".parse::<LatexEngine>(),
+            Ok(LatexEngine::LuaLatex)
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_engine() {
+        assert!("context".parse::<LatexEngine>().is_err());
+    }
+
+    #[test]
+    fn test_parse_interaction_mode() {
+        assert_eq!(
+            "batchmode".parse::<InteractionMode>(),
+            Ok(InteractionMode::BatchMode)
+        );
+        assert_eq!(
+            " ErrorStopMode".parse::<InteractionMode>(),
+            Ok(InteractionMode::ErrorStopMode)
+        );
+        assert!("halt".parse::<InteractionMode>().is_err());
+    }
+
+    #[test]
+    fn test_engine_serializes_lowercase() {
+        let json = serde_json::to_string(&LatexEngine::XeLatex).unwrap();
+        assert_eq!(json, "\"xelatex\"");
+    }
+}
@@ -0,0 +1,332 @@
+//! Cancellation of in-progress compilations
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Token a running compilation watches to know it should stop
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+    /// Process ids of the engine/tool the compilation is running
+    children: Arc<Mutex<Vec<u32>>>,
+}
+
+impl CancelToken {
+    /// Request cancellation and wake anything waiting on it
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Whether cancellation has been requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolve once cancellation has been requested
+    pub async fn cancelled(&self) {
+        loop {
+            // Register before checking the flag so a concurrent cancel isn't missed
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Record a child process the compilation spawned until the guard is dropped
+    pub fn register_child(&self, pid: u32) -> ChildRegistration {
+        if let Ok(mut children) = self.children.lock() {
+            children.push(pid);
+        }
+        ChildRegistration {
+            children: self.children.clone(),
+            pid,
+        }
+    }
+
+    /// Kill every registered child process; returns how many were killed
+    ///
+    /// Cancelling alone relies on the build task dropping its child, which
+    /// never happens once the app is exiting.
+    pub fn kill_children(&self) -> usize {
+        let pids = match self.children.lock() {
+            Ok(mut children) => std::mem::take(&mut *children),
+            Err(_) => return 0,
+        };
+        pids.into_iter().filter(|&pid| kill_process(pid)).count()
+    }
+
+    fn same_as(&self, other: &CancelToken) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+/// A child process registered with [`CancelToken::register_child`]
+pub struct ChildRegistration {
+    children: Arc<Mutex<Vec<u32>>>,
+    pid: u32,
+}
+
+impl Drop for ChildRegistration {
+    fn drop(&mut self) {
+        if let Ok(mut children) = self.children.lock() {
+            children.retain(|&pid| pid != self.pid);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn kill_process(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: kill only sends a signal; a stale pid just fails with ESRCH
+    unsafe { libc::kill(pid, libc::SIGKILL) == 0 }
+}
+
+#[cfg(windows)]
+fn kill_process(pid: u32) -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    // /T also stops anything the engine started, e.g. MiKTeX's package installer
+    std::process::Command::new("taskkill")
+        .args(["/F", "/T", "/PID", &pid.to_string()])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Error returned when a build is started while another one is running
+pub const BUILD_ALREADY_RUNNING: &str = "A build is already running";
+
+/// Tracks the compilation currently running so it can be cancelled
+#[derive(Debug, Default)]
+pub struct CompilationHandle {
+    current: Mutex<Option<CancelToken>>,
+}
+
+impl CompilationHandle {
+    /// Register a new compilation and return the token it should watch
+    pub fn begin(&self) -> CancelToken {
+        let token = CancelToken::default();
+        if let Ok(mut current) = self.current.lock() {
+            *current = Some(token.clone());
+        }
+        token
+    }
+
+    /// Register a new compilation unless one is already running
+    ///
+    /// Builds share the build directory, so a second one would clobber the
+    /// first one's aux files. The returned guard unregisters the compilation
+    /// when dropped, even if the build fails or is abandoned.
+    pub fn try_begin(&self) -> Result<CompilationGuard<'_>, String> {
+        let mut current = self.current.lock().map_err(|e| e.to_string())?;
+        if current.is_some() {
+            return Err(BUILD_ALREADY_RUNNING.to_string());
+        }
+        let token = CancelToken::default();
+        *current = Some(token.clone());
+        Ok(CompilationGuard {
+            handle: self,
+            token,
+        })
+    }
+
+    /// Unregister a compilation once it has finished
+    pub fn finish(&self, token: &CancelToken) {
+        if let Ok(mut current) = self.current.lock() {
+            // A newer build may have replaced this one in the meantime
+            if current.as_ref().is_some_and(|t| t.same_as(token)) {
+                *current = None;
+            }
+        }
+    }
+
+    /// Cancel the running compilation; returns whether anything was running
+    ///
+    /// The compilation stays registered until it has actually stopped, since
+    /// its engine may still be writing to the build directory.
+    pub fn cancel(&self) -> bool {
+        let token = match self.current.lock() {
+            Ok(current) => current.as_ref().cloned(),
+            Err(_) => None,
+        };
+        match token {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel the running compilation and kill its child processes
+    ///
+    /// Called when the app exits so no TeX process outlives it. Returns
+    /// the number of processes killed.
+    pub fn shutdown(&self) -> usize {
+        let token = match self.current.lock() {
+            Ok(current) => current.as_ref().cloned(),
+            Err(_) => None,
+        };
+        match token {
+            Some(token) => {
+                token.cancel();
+                token.kill_children()
+            }
+            None => 0,
+        }
+    }
+
+    /// Whether a compilation is currently registered
+    pub fn is_running(&self) -> bool {
+        self.current
+            .lock()
+            .map(|current| current.is_some())
+            .unwrap_or(false)
+    }
+}
+
+/// A running compilation registered with [`CompilationHandle::try_begin`]
+pub struct CompilationGuard<'a> {
+    handle: &'a CompilationHandle,
+    token: CancelToken,
+}
+
+impl CompilationGuard<'_> {
+    /// Token the compilation should watch for cancellation
+    pub fn token(&self) -> &CancelToken {
+        &self.token
+    }
+}
+
+impl Drop for CompilationGuard<'_> {
+    fn drop(&mut self) {
+        self.handle.finish(&self.token);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_with_nothing_running() {
+        let handle = CompilationHandle::default();
+        assert!(!handle.cancel());
+    }
+
+    #[test]
+    fn test_cancel_running_compilation() {
+        let handle = CompilationHandle::default();
+        let token = handle.begin();
+        assert!(handle.is_running());
+        assert!(handle.cancel());
+        assert!(token.is_cancelled());
+        // Registered until the compilation reports that it has stopped
+        assert!(handle.is_running());
+        handle.finish(&token);
+        // Nothing left to cancel
+        assert!(!handle.cancel());
+    }
+
+    #[test]
+    fn test_finish_clears_handle() {
+        let handle = CompilationHandle::default();
+        let token = handle.begin();
+        handle.finish(&token);
+        assert!(!handle.is_running());
+        assert!(!handle.cancel());
+        assert!(!token.is_cancelled());
+    }
+
+    #[test]
+    fn test_finish_keeps_newer_compilation() {
+        let handle = CompilationHandle::default();
+        let old = handle.begin();
+        let new = handle.begin();
+        handle.finish(&old);
+        assert!(handle.is_running());
+        assert!(handle.cancel());
+        assert!(new.is_cancelled());
+        assert!(!old.is_cancelled());
+    }
+
+    #[test]
+    fn test_child_registration_is_removed_on_drop() {
+        let token = CancelToken::default();
+        let registration = token.register_child(u32::MAX);
+        assert_eq!(*token.children.lock().unwrap(), vec![u32::MAX]);
+        drop(registration);
+        assert!(token.children.lock().unwrap().is_empty());
+        assert_eq!(token.kill_children(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shutdown_kills_registered_child() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let handle = CompilationHandle::default();
+        let token = handle.begin();
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let _registration = token.register_child(child.id());
+
+        assert_eq!(handle.shutdown(), 1);
+        assert!(token.is_cancelled());
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGKILL));
+        assert_eq!(handle.shutdown(), 0);
+        handle.finish(&token);
+        assert!(!handle.is_running());
+    }
+
+    #[test]
+    fn test_cancelled_resolves_after_cancel() {
+        let token = CancelToken::default();
+        let waiter = token.clone();
+        let task = tauri::async_runtime::spawn(async move { waiter.cancelled().await });
+        token.cancel();
+        tauri::async_runtime::block_on(task).unwrap();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn test_try_begin_rejects_concurrent_build() {
+        let handle = CompilationHandle::default();
+        let guard = handle.try_begin().unwrap();
+        assert_eq!(handle.try_begin().err().as_deref(), Some(BUILD_ALREADY_RUNNING));
+
+        drop(guard);
+        assert!(!handle.is_running());
+        assert!(handle.try_begin().is_ok());
+    }
+
+    #[test]
+    fn test_try_begin_guard_can_be_cancelled() {
+        let handle = CompilationHandle::default();
+        let guard = handle.try_begin().unwrap();
+        assert!(handle.cancel());
+        assert!(guard.token().is_cancelled());
+        drop(guard);
+        assert!(!handle.is_running());
+    }
+
+    #[test]
+    fn test_try_begin_waits_for_cancelled_build_to_stop() {
+        let handle = CompilationHandle::default();
+        let guard = handle.try_begin().unwrap();
+        assert!(handle.cancel());
+        // The cancelled engine may still be writing to the build directory
+        assert_eq!(handle.try_begin().err().as_deref(), Some(BUILD_ALREADY_RUNNING));
+
+        drop(guard);
+        assert!(handle.try_begin().is_ok());
+    }
+}
@@ -0,0 +1,262 @@
+//! History of builds, kept in the workspace logs directory
+//!
+//! Each build appends one JSON line to `builds.jsonl`, and the full engine
+//! log of the latest build is written to `last-build.log`. The diagnostics
+//! of each file's latest build are kept in `diagnostics/<hash>.json`.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::build::{stable_hash, BuildResult};
+use super::diagnostics::Diagnostic;
+use super::engine::LatexEngine;
+use crate::workspace::get_logs_dir;
+
+/// File in the logs directory holding one record per build
+pub const HISTORY_FILE: &str = "builds.jsonl";
+
+/// File in the logs directory holding the latest build's engine log
+pub const LAST_LOG_FILE: &str = "last-build.log";
+
+/// Folder in the logs directory holding each file's latest diagnostics
+pub const DIAGNOSTICS_DIR: &str = "diagnostics";
+
+/// Size past which the history is trimmed to its newer half
+pub const MAX_HISTORY_BYTES: u64 = 512 * 1024;
+
+/// One line of the build history
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BuildRecord {
+    /// When the build finished, in milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub file: String,
+    pub engine: LatexEngine,
+    pub success: bool,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+impl BuildRecord {
+    /// Record of a build of `tex_path` that just finished
+    pub fn new(tex_path: &Path, engine: LatexEngine, result: &BuildResult) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            file: tex_path.to_string_lossy().to_string(),
+            engine,
+            success: result.success,
+            duration_ms: result.duration_ms,
+            error: result.error_message.clone(),
+        }
+    }
+}
+
+/// Append `record` to the history in `dir` and keep `log` as the latest log
+pub fn record_build(dir: &Path, record: &BuildRecord, log: &str) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create logs directory: {}", e))?;
+    let path = dir.join(HISTORY_FILE);
+    trim_history(&path)?;
+
+    let line = serde_json::to_string(record)
+        .map_err(|e| format!("Failed to serialize build record: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open build history: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write build history: {}", e))?;
+
+    fs::write(dir.join(LAST_LOG_FILE), log).map_err(|e| format!("Failed to write build log: {}", e))
+}
+
+/// Drop the older half of the history once it grows past [`MAX_HISTORY_BYTES`]
+fn trim_history(path: &Path) -> Result<(), String> {
+    let too_big = fs::metadata(path).is_ok_and(|m| m.len() > MAX_HISTORY_BYTES);
+    if !too_big {
+        return Ok(());
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read build history: {}", e))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let kept = lines[lines.len() / 2..].join("\n");
+    fs::write(path, format!("{}\n", kept))
+        .map_err(|e| format!("Failed to trim build history: {}", e))
+}
+
+/// The most recent `limit` builds in `dir`, newest first
+///
+/// Lines that don't parse (e.g. cut off by a crash) are skipped.
+pub fn read_history(dir: &Path, limit: usize) -> Result<Vec<BuildRecord>, String> {
+    let path = dir.join(HISTORY_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read build history: {}", e))?;
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect())
+}
+
+/// The most recent `limit` builds in the workspace, newest first
+pub fn build_history(limit: usize) -> Result<Vec<BuildRecord>, String> {
+    let dir = get_logs_dir().ok_or("Could not determine logs directory")?;
+    read_history(&dir, limit)
+}
+
+/// Diagnostics of a file's latest build and the source they were built from
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CachedDiagnostics {
+    content_hash: u64,
+    diagnostics: Vec<Diagnostic>,
+}
+
+fn diagnostics_path(dir: &Path, tex_path: &Path) -> PathBuf {
+    let hash = stable_hash(tex_path.to_string_lossy().as_bytes());
+    dir.join(DIAGNOSTICS_DIR).join(format!("{:016x}.json", hash))
+}
+
+/// Hash of source content; line endings don't count, since saving may change them
+fn content_hash(content: &str) -> u64 {
+    stable_hash(content.replace('\r', "").as_bytes())
+}
+
+/// Keep the diagnostics of a build of `tex_path` with `content`, replacing older ones
+pub fn save_diagnostics(
+    dir: &Path,
+    tex_path: &Path,
+    content: &str,
+    diagnostics: &[Diagnostic],
+) -> Result<(), String> {
+    let path = diagnostics_path(dir, tex_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create diagnostics directory: {}", e))?;
+    }
+    let cached = CachedDiagnostics {
+        content_hash: content_hash(content),
+        diagnostics: diagnostics.to_vec(),
+    };
+    let json = serde_json::to_string(&cached)
+        .map_err(|e| format!("Failed to serialize diagnostics: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write diagnostics: {}", e))
+}
+
+/// Diagnostics of the latest build of `tex_path`, if it built exactly `content`
+pub fn load_diagnostics(dir: &Path, tex_path: &Path, content: &str) -> Option<Vec<Diagnostic>> {
+    let json = fs::read_to_string(diagnostics_path(dir, tex_path)).ok()?;
+    let cached: CachedDiagnostics = serde_json::from_str(&json).ok()?;
+    (cached.content_hash == content_hash(content)).then_some(cached.diagnostics)
+}
+
+/// Diagnostics of the latest build of `tex_path` in the workspace, if still current
+pub fn last_diagnostics(tex_path: &Path, content: &str) -> Option<Vec<Diagnostic>> {
+    load_diagnostics(&get_logs_dir()?, tex_path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(file: &str, success: bool) -> BuildRecord {
+        BuildRecord {
+            timestamp: 1_700_000_000_000,
+            file: file.to_string(),
+            engine: LatexEngine::PdfLatex,
+            success,
+            duration_ms: 1200,
+            error: (!success).then(|| "Compilation failed - no PDF generated".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_record_appends_one_line_and_reads_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = record("resume.tex", true);
+        record_build(temp_dir.path(), &first, "This is pdfTeX").unwrap();
+
+        let content = fs::read_to_string(temp_dir.path().join(HISTORY_FILE)).unwrap();
+        assert_eq!(content.lines().count(), 1);
+        assert_eq!(read_history(temp_dir.path(), 10).unwrap(), [first]);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(LAST_LOG_FILE)).unwrap(),
+            "This is pdfTeX"
+        );
+    }
+
+    #[test]
+    fn test_history_is_newest_first_and_limited() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["a.tex", "b.tex", "c.tex"] {
+            record_build(temp_dir.path(), &record(name, false), "").unwrap();
+        }
+        fs::OpenOptions::new()
+            .append(true)
+            .open(temp_dir.path().join(HISTORY_FILE))
+            .unwrap()
+            .write_all(b"{\"truncated\n")
+            .unwrap();
+
+        let files: Vec<String> = read_history(temp_dir.path(), 2)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.file)
+            .collect();
+        assert_eq!(files, ["c.tex", "b.tex"]);
+    }
+
+    #[test]
+    fn test_missing_history_is_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(read_history(temp_dir.path(), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics_returned_while_content_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = Path::new("/home/jane/resume.tex");
+        let diagnostics = vec![Diagnostic::error("Undefined control sequence")];
+        let content = "\\documentclass{article}\r\n\\foo\r\n";
+        save_diagnostics(temp_dir.path(), tex_path, content, &diagnostics).unwrap();
+
+        let loaded = load_diagnostics(temp_dir.path(), tex_path, content);
+        assert_eq!(loaded.as_deref(), Some(diagnostics.as_slice()));
+        // The editor may hold the same text with other line endings
+        let unix = content.replace("\r\n", "\n");
+        assert_eq!(load_diagnostics(temp_dir.path(), tex_path, &unix), Some(diagnostics));
+    }
+
+    #[test]
+    fn test_diagnostics_missed_after_content_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let tex_path = Path::new("/home/jane/resume.tex");
+        let diagnostics = [Diagnostic::warning("Overfull \\hbox")];
+        save_diagnostics(temp_dir.path(), tex_path, "old", &diagnostics).unwrap();
+
+        assert_eq!(load_diagnostics(temp_dir.path(), tex_path, "new"), None);
+        assert_eq!(load_diagnostics(temp_dir.path(), Path::new("other.tex"), "old"), None);
+    }
+
+    #[test]
+    fn test_large_history_is_trimmed() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(HISTORY_FILE);
+        let line = serde_json::to_string(&record("old.tex", true)).unwrap();
+        let count = MAX_HISTORY_BYTES as usize / line.len() + 10;
+        fs::write(&path, format!("{}\n", line).repeat(count)).unwrap();
+
+        record_build(temp_dir.path(), &record("new.tex", true), "").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!((content.len() as u64) < MAX_HISTORY_BYTES);
+        assert_eq!(read_history(temp_dir.path(), 1).unwrap()[0].file, "new.tex");
+    }
+}
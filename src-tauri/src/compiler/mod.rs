@@ -1,11 +1,42 @@
 //! LaTeX compilation module
 //!
-//! This module handles compiling .tex files to PDF using pdflatex (TeX Live/MiKTeX).
+//! This module handles compiling .tex files to PDF using pdflatex, xelatex or
+//! lualatex (TeX Live/MiKTeX).
 
+pub mod artifacts;
+pub mod bibliography;
 pub mod build;
+pub mod debounce;
+pub mod diagnostics;
+pub mod engine;
+pub mod handle;
+pub mod history;
+pub mod packages;
 pub mod pdflatex;
+pub mod probe;
 pub mod requirements;
+pub mod selftest;
+pub mod synctex;
 
-pub use build::{compile_latex, compile_latex_async, BuildResult};
-pub use requirements::{check_requirements, RequirementsStatus};
-
+pub use artifacts::{list_artifacts_in, ArtifactInfo};
+pub use bibliography::BibliographyTool;
+pub use build::{
+    build_dir_for, build_log_path, compile_content_async, compile_latex, compile_latex_async,
+    compile_snippet_async, ensure_writable_dir,
+    get_build_dir, path_warning, validate_extra_args, BuildErrorKind, BuildProgress, BuildResult,
+    CompileOptions, DEFAULT_INSTALL_RETRIES, REPRODUCIBLE_EPOCH,
+};
+pub use diagnostics::{
+    dedupe_diagnostics, enrich_diagnostics, parse_diagnostics, Diagnostic, Severity,
+};
+pub use engine::{InteractionMode, LatexEngine};
+pub use packages::PackageInfo;
+pub use probe::{probe_engines, EngineResult};
+pub use handle::{
+    CancelToken, ChildRegistration, CompilationGuard, CompilationHandle, BUILD_ALREADY_RUNNING,
+};
+pub use requirements::{
+    check_requirements, check_requirements_async, EngineStatus, Platform, RequirementsStatus,
+    ToolRequirement, ToolStatus,
+};
+pub use selftest::{run_selftest, SelftestReport};
@@ -0,0 +1,226 @@
+//! The classes and packages a build loaded, read from the engine's log
+//!
+//! Every class and package announces itself in the `.log` file with a
+//! `Document Class:` or `Package:` line giving its date and version.
+//! Comparing these lists between machines shows why a document builds
+//! differently on each, as do the font shapes LaTeX had to substitute.
+
+/// Prefixes of the lines announcing a loaded class or package
+const ANNOUNCEMENTS: [&str; 2] = ["Document Class: ", "Package: "];
+
+/// A class or package loaded during a build
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PackageInfo {
+    pub name: String,
+    /// Release date and version, e.g. "2022/06/12 v2.14"
+    pub version: Option<String>,
+}
+
+/// A font shape the document asked for that LaTeX replaced with another
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct FontSubstitution {
+    /// Shape requested, e.g. "OT1/cmr/bx/sc"
+    pub requested: String,
+    /// Shape used instead
+    pub used: String,
+}
+
+/// The classes and packages announced in an engine log, in load order
+///
+/// A package loaded twice is listed once.
+pub fn parse_loaded_packages(log: &str) -> Vec<PackageInfo> {
+    let mut packages: Vec<PackageInfo> = Vec::new();
+    for line in log.lines() {
+        let Some(rest) = ANNOUNCEMENTS
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))
+        else {
+            continue;
+        };
+        let mut words = rest.split_whitespace();
+        let Some(name) = words.next() else {
+            continue;
+        };
+        if packages.iter().any(|p| p.name == name) {
+            continue;
+        }
+        packages.push(PackageInfo {
+            name: name.to_string(),
+            version: parse_version(words),
+        });
+    }
+    packages
+}
+
+/// Font shapes substituted in an engine log, each pair listed once
+///
+/// LaTeX wraps these warnings over `(Font)` continuation lines, e.g.
+/// ```text
+/// LaTeX Font Warning: Font shape `OT1/cmr/bx/sc' undefined
+/// (Font)              using `OT1/cmr/bx/n' instead on input line 12.
+/// ```
+pub fn parse_font_substitutions(log: &str) -> Vec<FontSubstitution> {
+    let mut substitutions: Vec<FontSubstitution> = Vec::new();
+    let mut lines = log.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(rest) = line.strip_prefix("LaTeX Font Warning: Font shape ") else {
+            continue;
+        };
+        let mut warning = rest.to_string();
+        while let Some(continuation) = lines.peek().and_then(|l| l.strip_prefix("(Font)")) {
+            warning.push(' ');
+            warning.push_str(continuation.trim());
+            lines.next();
+        }
+        let Some(substitution) = parse_substitution(&warning) else {
+            continue;
+        };
+        if !substitutions.contains(&substitution) {
+            substitutions.push(substitution);
+        }
+    }
+    substitutions
+}
+
+/// The requested and replacement shapes of one joined font warning
+///
+/// The replacement is the last quoted shape before `instead`, which covers
+/// both "using `X' instead" and "Font shape `X' tried instead".
+fn parse_substitution(warning: &str) -> Option<FontSubstitution> {
+    let (before, _) = warning.split_once(" instead")?;
+    let shapes = quoted_shapes(before);
+    match shapes.as_slice() {
+        [requested, .., used] => Some(FontSubstitution {
+            requested: requested.to_string(),
+            used: used.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+/// The `...' quoted names in `text`
+fn quoted_shapes(text: &str) -> Vec<&str> {
+    let mut shapes = Vec::new();
+    let mut rest = text;
+    while let Some((_, after)) = rest.split_once('`') {
+        let Some((shape, remainder)) = after.split_once('\'') else {
+            break;
+        };
+        shapes.push(shape);
+        rest = remainder;
+    }
+    shapes
+}
+
+/// The date and `vX.Y` version at the start of an announcement's details
+fn parse_version<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<String> {
+    let date = words.next().filter(|word| is_date(word))?;
+    match words.next() {
+        Some(word) if is_version(word) => Some(format!("{} {}", date, word)),
+        _ => Some(date.to_string()),
+    }
+}
+
+/// `2022/06/12` or `2023-02-07`
+fn is_date(word: &str) -> bool {
+    word.len() == 10
+        && word.chars().enumerate().all(|(i, c)| {
+            if i == 4 || i == 7 {
+                c == '/' || c == '-'
+            } else {
+                c.is_ascii_digit()
+            }
+        })
+}
+
+/// `v2.14`, `v1.4n`, ...
+fn is_version(word: &str) -> bool {
+    word.strip_prefix('v')
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: Option<&str>) -> PackageInfo {
+        PackageInfo {
+            name: name.to_string(),
+            version: version.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_loaded_packages() {
+        let log = concat!(
+            "(/usr/share/texlive/texmf-dist/tex/latex/base/article.cls\n",
+            "Document Class: article 2023/05/17 v1.4n Standard LaTeX document class\n",
+            "(/usr/share/texlive/texmf-dist/tex/latex/xcolor/xcolor.sty\n",
+            "Package: xcolor 2022/06/12 v2.14 LaTeX color extensions (UK)\n",
+            "File: color.cfg 2016/01/02 v1.6 sample color configuration\n",
+            "Package: hyperref 2023-02-07 v7.00v Hypertext links for LaTeX\n",
+            "Package: titlesec 2021/07/05\n",
+            "Package: homemade\n",
+            "Package: xcolor 2022/06/12 v2.14 LaTeX color extensions (UK)\n",
+        );
+
+        assert_eq!(
+            parse_loaded_packages(log),
+            [
+                package("article", Some("2023/05/17 v1.4n")),
+                package("xcolor", Some("2022/06/12 v2.14")),
+                package("hyperref", Some("2023-02-07 v7.00v")),
+                package("titlesec", Some("2021/07/05")),
+                package("homemade", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_packages_in_empty_log() {
+        assert!(parse_loaded_packages("This is pdfTeX, Version 3.141592653").is_empty());
+    }
+
+    fn substitution(requested: &str, used: &str) -> FontSubstitution {
+        FontSubstitution {
+            requested: requested.to_string(),
+            used: used.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_font_substitutions() {
+        let log = concat!(
+            "Package: xcolor 2022/06/12 v2.14 LaTeX color extensions (UK)\n",
+            "\n",
+            "LaTeX Font Warning: Font shape `OT1/cmr/bx/sc' undefined\n",
+            "(Font)              using `OT1/cmr/bx/n' instead on input line 12.\n",
+            "\n",
+            "LaTeX Font Warning: Font shape `T1/lmr/m/scit' undefined\n",
+            "(Font)              using `T1/lmr/m/scsl' instead\n",
+            "(Font)              for shape `T1/lmr/m/scit' on input line 30.\n",
+            "\n",
+            "LaTeX Font Warning: Font shape `OMS/cmr/m/n' in size <10> not available\n",
+            "(Font)              Font shape `OMS/cmsy/m/n' tried instead on input line 41.\n",
+            "\n",
+            "LaTeX Font Warning: Font shape `OT1/cmr/bx/sc' undefined\n",
+            "(Font)              using `OT1/cmr/bx/n' instead on input line 57.\n",
+            "\n",
+            "LaTeX Font Warning: Some font shapes were not available, defaults substituted.\n",
+        );
+
+        assert_eq!(
+            parse_font_substitutions(log),
+            [
+                substitution("OT1/cmr/bx/sc", "OT1/cmr/bx/n"),
+                substitution("T1/lmr/m/scit", "T1/lmr/m/scsl"),
+                substitution("OMS/cmr/m/n", "OMS/cmsy/m/n"),
+            ]
+        );
+        assert_eq!(
+            parse_loaded_packages(log),
+            [package("xcolor", Some("2022/06/12 v2.14"))]
+        );
+    }
+}
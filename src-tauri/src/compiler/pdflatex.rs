@@ -1,67 +1,233 @@
 //! pdflatex command discovery and utilities
 
+use std::collections::HashMap;
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+use super::engine::LatexEngine;
 
 /// Get the pdflatex command - tries PATH first, then common locations
 pub fn get_pdflatex_command() -> String {
+    get_engine_command(LatexEngine::PdfLatex)
+}
+
+/// Get the command for a LaTeX engine - tries PATH first, then common locations
+pub fn get_engine_command(engine: LatexEngine) -> String {
+    get_tool_command(engine.binary_name())
+}
+
+/// Get the command for a TeX distribution tool (engine, bibtex, biber, ...)
+/// - tries PATH first, then common locations
+///
+/// Falls back to the bare binary name when the tool can't be found.
+pub fn get_tool_command(binary: &str) -> String {
+    find_tool_command(binary).unwrap_or_else(|| binary.to_string())
+}
+
+/// Find a working command for a tool, remembering it once found
+pub fn find_tool_command(binary: &str) -> Option<String> {
+    find_tool(binary, &[])
+}
+
+/// Find a working command for a tool (pdflatex, bibtex, biber, makeindex, ...)
+///
+/// Tries the path the user configured for it, then PATH, then
+/// `extra_candidates`, then common install locations. Discovered commands
+/// are remembered; the configured path always wins.
+pub fn find_tool(binary: &str, extra_candidates: &[String]) -> Option<String> {
+    if let Some(path) = existing_override(tool_override(binary).as_deref()) {
+        return Some(path);
+    }
+    command_cache().get_or_discover(binary, |binary| {
+        discover_tool_command(binary, extra_candidates)
+    })
+}
+
+/// Tool paths configured by the user, keyed by binary name
+fn tool_overrides() -> std::sync::MutexGuard<'static, HashMap<String, String>> {
+    static OVERRIDES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    OVERRIDES
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+fn tool_override(binary: &str) -> Option<String> {
+    tool_overrides().get(binary).cloned()
+}
+
+/// Use `path` for a tool instead of discovering it; `None` goes back to discovery
+///
+/// The path is trusted as is; validate it with `verify_tool_command` first.
+pub fn set_tool_override(binary: &str, path: Option<String>) {
+    let mut overrides = tool_overrides();
+    match path {
+        Some(path) => overrides.insert(binary.to_string(), path),
+        None => overrides.remove(binary),
+    };
+}
+
+/// Replace every tool override, e.g. with the paths from the settings
+pub fn set_tool_overrides(paths: HashMap<String, String>) {
+    *tool_overrides() = paths;
+}
+
+/// The override, unless it no longer exists (e.g. TeX was uninstalled)
+fn existing_override(path: Option<&str>) -> Option<String> {
+    path.filter(|p| std::path::Path::new(p).is_file())
+        .map(str::to_string)
+}
+
+/// Check that `command --version` runs successfully
+pub fn verify_tool_command(command: &str) -> Result<(), String> {
+    let output = Command::new(command)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", command, e))?;
+    if !output.status.success() {
+        return Err(format!("{} --version exited with {}", command, output.status));
+    }
+    Ok(())
+}
+
+/// Forget discovered commands, e.g. after the user installs TeX
+pub fn clear_command_cache() {
+    command_cache().clear();
+}
+
+/// Discovered tool commands, keyed by binary name
+///
+/// Only successful discoveries are stored, so a tool installed while the
+/// app is running is still picked up.
+#[derive(Default)]
+struct CommandCache {
+    commands: Mutex<HashMap<String, String>>,
+}
+
+impl CommandCache {
+    fn get_or_discover(
+        &self,
+        binary: &str,
+        discover: impl FnOnce(&str) -> Option<String>,
+    ) -> Option<String> {
+        if let Some(command) = self.lock().get(binary) {
+            return Some(command.clone());
+        }
+        // Discover without holding the lock; it spawns processes
+        let command = discover(binary)?;
+        self.lock().insert(binary.to_string(), command.clone());
+        Some(command)
+    }
+
+    fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        // The map is always left consistent, so a poisoned lock is still usable
+        self.commands.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn command_cache() -> &'static CommandCache {
+    static CACHE: OnceLock<CommandCache> = OnceLock::new();
+    CACHE.get_or_init(CommandCache::default)
+}
+
+/// Look for a tool on PATH, then in `extra_candidates` and common install locations
+fn discover_tool_command(binary: &str, extra_candidates: &[String]) -> Option<String> {
     // Try PATH first
-    if Command::new("pdflatex")
+    if Command::new(binary)
         .arg("--version")
         .output()
         .map(|o| o.status.success())
         .unwrap_or(false)
     {
-        return "pdflatex".to_string();
+        return Some(binary.to_string());
     }
 
-    // Try common MiKTeX locations on Windows
-    #[cfg(windows)]
-    {
-        let home = std::env::var("USERPROFILE").unwrap_or_else(|_| "C:\\Users\\Default".to_string());
-        let miktex_paths = [
-            format!(
-                "{}\\AppData\\Local\\Programs\\MiKTeX\\miktex\\bin\\x64\\pdflatex.exe",
-                home
-            ),
-            "C:\\Program Files\\MiKTeX\\miktex\\bin\\x64\\pdflatex.exe".to_string(),
-            "C:\\Program Files (x86)\\MiKTeX\\miktex\\bin\\x64\\pdflatex.exe".to_string(),
-            "C:\\MiKTeX\\miktex\\bin\\x64\\pdflatex.exe".to_string(),
-        ];
-
-        for path in &miktex_paths {
-            if std::path::Path::new(path).exists() {
-                // Verify it actually works
-                if Command::new(path)
-                    .arg("--version")
-                    .output()
-                    .map(|o| o.status.success())
-                    .unwrap_or(false)
-                {
-                    return path.clone();
-                }
-                // Even if --version fails, if file exists, try to use it
-                return path.clone();
-            }
-        }
+    // Even if --version fails, if the file exists, try to use it
+    extra_candidates
+        .iter()
+        .cloned()
+        .chain(candidate_paths(binary))
+        .find(|path| std::path::Path::new(path).is_file())
+}
+
+/// Common install locations for a tool, most likely first
+#[cfg(windows)]
+fn candidate_paths(binary: &str) -> Vec<String> {
+    let home = std::env::var("USERPROFILE").unwrap_or_else(|_| "C:\\Users\\Default".to_string());
+    vec![
+        format!(
+            "{}\\AppData\\Local\\Programs\\MiKTeX\\miktex\\bin\\x64\\{}.exe",
+            home, binary
+        ),
+        format!("C:\\Program Files\\MiKTeX\\miktex\\bin\\x64\\{}.exe", binary),
+        format!("C:\\Program Files (x86)\\MiKTeX\\miktex\\bin\\x64\\{}.exe", binary),
+        format!("C:\\MiKTeX\\miktex\\bin\\x64\\{}.exe", binary),
+    ]
+}
+
+/// Common install locations for a tool, most likely first
+///
+/// GUI apps don't inherit the shell's PATH, so MacTeX's `texbin` is
+/// usually missing from it.
+#[cfg(target_os = "macos")]
+fn candidate_paths(binary: &str) -> Vec<String> {
+    let mut paths = vec![
+        format!("/Library/TeX/texbin/{}", binary),
+        format!("/opt/homebrew/bin/{}", binary),
+        format!("/usr/local/bin/{}", binary),
+    ];
+    paths.extend(glob_newest_first(&format!("/usr/local/texlive/*/bin/*/{}", binary)));
+    paths
+}
+
+/// Common install locations for a tool, most likely first
+#[cfg(all(unix, not(target_os = "macos")))]
+fn candidate_paths(binary: &str) -> Vec<String> {
+    let mut paths = vec![
+        format!("/usr/bin/{}", binary),
+        format!("/usr/local/bin/{}", binary),
+    ];
+    paths.extend(glob_newest_first(&format!("/usr/local/texlive/*/bin/*/{}", binary)));
+    if let Some(home) = dirs::home_dir() {
+        let home = glob::Pattern::escape(&home.to_string_lossy());
+        paths.extend(glob_newest_first(&format!("{}/.texlive*/bin/*/{}", home, binary)));
     }
+    paths.push(format!("/opt/texbin/{}", binary));
+    paths
+}
 
-    // Fallback to just "pdflatex"
-    "pdflatex".to_string()
+/// Paths matching a pattern, in reverse order so the newest TeX Live year comes first
+#[cfg(unix)]
+fn glob_newest_first(pattern: &str) -> Vec<String> {
+    let mut paths: Vec<String> = glob::glob(pattern)
+        .map(|matches| {
+            matches
+                .flatten()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.reverse();
+    paths
 }
 
 /// Check if pdflatex is available on the system
 pub fn is_pdflatex_available() -> bool {
-    let cmd = get_pdflatex_command();
-    // If it's a full path, just check if file exists
-    if cmd.contains('\\') || cmd.contains('/') {
-        return std::path::Path::new(&cmd).exists();
-    }
-    // If it's just "pdflatex", try to run it
-    Command::new(&cmd)
-        .arg("--version")
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+    is_engine_available(LatexEngine::PdfLatex)
+}
+
+/// Check if a LaTeX engine is available on the system
+pub fn is_engine_available(engine: LatexEngine) -> bool {
+    is_tool_available(engine.binary_name())
+}
+
+/// Check if a TeX distribution tool is available on the system
+pub fn is_tool_available(binary: &str) -> bool {
+    find_tool_command(binary).is_some()
 }
 
 /// Get debug information about pdflatex paths
@@ -84,13 +250,7 @@ pub fn debug_pdflatex() -> String {
 #[cfg(not(windows))]
 pub fn debug_pdflatex() -> String {
     let home = std::env::var("HOME").unwrap_or_else(|_| "NOT_FOUND".to_string());
-    let common_paths = [
-        "/usr/bin/pdflatex",
-        "/usr/local/bin/pdflatex",
-        "/opt/texbin/pdflatex",
-        format!("{}/.texlive/bin/x86_64-linux/pdflatex", home),
-        format!("{}/.texlive/bin/universal-darwin/pdflatex", home),
-    ];
+    let common_paths = candidate_paths("pdflatex");
     
     let mut info = format!("HOME: {}\n", home);
     info.push_str("Common paths:\n");
@@ -123,6 +283,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::overly_complex_bool_expr)]
     fn test_is_pdflatex_available_returns_bool() {
         let available = is_pdflatex_available();
         // Just verify it returns a boolean without panicking
@@ -144,6 +305,20 @@ mod tests {
         assert!(!cmd.is_empty());
     }
 
+    #[test]
+    fn test_get_engine_command_uses_engine_binary() {
+        for engine in LatexEngine::ALL {
+            let cmd = get_engine_command(engine);
+            assert!(cmd.contains(engine.binary_name()));
+        }
+    }
+
+    #[test]
+    fn test_get_tool_command_uses_binary_name() {
+        assert!(get_tool_command("bibtex").contains("bibtex"));
+        assert!(get_tool_command("biber").contains("biber"));
+    }
+
     #[test]
     fn test_debug_pdflatex_returns_string() {
         let debug = debug_pdflatex();
@@ -155,5 +330,154 @@ mod tests {
         #[cfg(not(windows))]
         assert!(debug.contains("HOME"));
     }
-}
 
+    #[test]
+    fn test_engine_availability_matches_discovery() {
+        assert_eq!(
+            is_pdflatex_available(),
+            find_tool_command("pdflatex").is_some()
+        );
+    }
+
+    #[test]
+    fn test_command_cache_skips_discovery_once_populated() {
+        let cache = CommandCache::default();
+        let mut runs = 0;
+        let mut discover = |binary: &str| {
+            runs += 1;
+            Some(format!("/usr/bin/{}", binary))
+        };
+
+        assert_eq!(
+            cache.get_or_discover("pdflatex", &mut discover).as_deref(),
+            Some("/usr/bin/pdflatex")
+        );
+        assert_eq!(
+            cache.get_or_discover("pdflatex", &mut discover).as_deref(),
+            Some("/usr/bin/pdflatex")
+        );
+        cache.get_or_discover("bibtex", &mut discover);
+        assert_eq!(runs, 2);
+    }
+
+    #[test]
+    fn test_command_cache_retries_failed_discovery() {
+        let cache = CommandCache::default();
+        let mut runs = 0;
+        let mut discover = |_: &str| {
+            runs += 1;
+            None
+        };
+
+        assert_eq!(cache.get_or_discover("pdflatex", &mut discover), None);
+        assert_eq!(cache.get_or_discover("pdflatex", &mut discover), None);
+        assert_eq!(runs, 2);
+    }
+
+    #[test]
+    fn test_command_cache_clear_forces_rediscovery() {
+        let cache = CommandCache::default();
+        let mut runs = 0;
+        let mut discover = |binary: &str| {
+            runs += 1;
+            Some(binary.to_string())
+        };
+
+        cache.get_or_discover("pdflatex", &mut discover);
+        cache.clear();
+        cache.get_or_discover("pdflatex", &mut discover);
+        assert_eq!(runs, 2);
+    }
+
+    #[test]
+    fn test_existing_override_is_used() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let path = temp.path().to_string_lossy().to_string();
+        assert_eq!(existing_override(Some(&path)), Some(path));
+    }
+
+    #[test]
+    fn test_find_tool_honors_override() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let path = temp.path().to_string_lossy().to_string();
+        let binary = "resumeide-test-override";
+
+        set_tool_override(binary, Some(path.clone()));
+        assert_eq!(find_tool(binary, &[]), Some(path));
+        set_tool_override(binary, None);
+        assert_eq!(find_tool(binary, &[]), None);
+    }
+
+    #[test]
+    fn test_find_tool_falls_back_to_path_and_candidates() {
+        // Whatever runs these tests has rustc on PATH
+        assert_eq!(find_tool("rustc", &[]).as_deref(), Some("rustc"));
+
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let candidate = temp.path().to_string_lossy().to_string();
+        let binary = "resumeide-test-candidate";
+        let candidates = vec![candidate.clone()];
+        assert_eq!(find_tool(binary, &candidates), Some(candidate));
+    }
+
+    #[test]
+    fn test_missing_override_falls_back_to_discovery() {
+        assert_eq!(existing_override(Some("/no/such/dir/pdflatex")), None);
+        assert_eq!(existing_override(None), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_tool_command_accepts_working_binary() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let script = temp_dir.path().join("pdflatex");
+        std::fs::write(&script, "#!/bin/sh\necho 'pdfTeX 3.141592653-2.6-1.40.24'\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(verify_tool_command(&script.to_string_lossy()).is_ok());
+    }
+
+    #[test]
+    fn test_verify_tool_command_rejects_invalid_path() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let not_a_binary = temp_dir.path().join("pdflatex.txt");
+        std::fs::write(&not_a_binary, "not a program").unwrap();
+
+        assert!(verify_tool_command("/no/such/dir/pdflatex").is_err());
+        assert!(verify_tool_command(&not_a_binary.to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn test_candidate_paths_not_empty() {
+        let paths = candidate_paths("pdflatex");
+        assert!(!paths.is_empty());
+        for path in &paths {
+            assert!(path.contains("pdflatex"), "{}", path);
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_candidate_paths_include_mactex() {
+        assert!(candidate_paths("pdflatex").contains(&"/Library/TeX/texbin/pdflatex".to_string()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_glob_newest_first_orders_texlive_years() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for year in ["2021", "2023", "2022"] {
+            let bin = temp_dir.path().join(year).join("bin").join("x86_64-linux");
+            std::fs::create_dir_all(&bin).unwrap();
+            std::fs::write(bin.join("pdflatex"), "").unwrap();
+        }
+
+        let root = glob::Pattern::escape(&temp_dir.path().to_string_lossy());
+        let paths = glob_newest_first(&format!("{}/*/bin/*/pdflatex", root));
+        assert_eq!(paths.len(), 3);
+        assert!(paths[0].contains("2023"));
+        assert!(paths[2].contains("2021"));
+    }
+}
@@ -0,0 +1,160 @@
+//! Find out which engines can build a document
+//!
+//! A downloaded template often names no engine. The probe compiles the
+//! document with every installed engine at once, each in its own build
+//! folder, and reports how each one fared.
+
+use std::path::Path;
+
+use super::build::{compile_content_async, BuildResult, CompileOptions};
+use super::diagnostics::Severity;
+use super::engine::LatexEngine;
+use super::handle::CancelToken;
+use super::pdflatex::is_engine_available;
+
+/// How one engine fared building the probed document
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EngineResult {
+    pub engine: LatexEngine,
+    pub success: bool,
+    pub duration_ms: u64,
+    /// The first error the engine reported, if the build failed
+    pub first_error: Option<String>,
+}
+
+impl EngineResult {
+    fn new(engine: LatexEngine, build: BuildResult) -> Self {
+        let first_error = build
+            .diagnostics
+            .iter()
+            .find(|d| d.severity == Severity::Error)
+            .map(|d| d.message.clone())
+            .or(build.error_message);
+        Self {
+            engine,
+            success: build.success,
+            duration_ms: build.duration_ms,
+            first_error: first_error.filter(|_| !build.success),
+        }
+    }
+}
+
+/// Folder in `build_dir` an engine's probe build runs in
+pub fn probe_dir(build_dir: &Path, engine: LatexEngine) -> std::path::PathBuf {
+    build_dir.join(engine.binary_name())
+}
+
+/// Build `content` with every installed engine concurrently
+///
+/// Engines that aren't installed are left out of the results.
+pub async fn probe_engines(
+    content: &str,
+    build_dir: &Path,
+    cancel: Option<&CancelToken>,
+) -> Vec<EngineResult> {
+    // Each engine builds in its own task; discovery may run the engine, so
+    // it stays off the async worker threads
+    let builds: Vec<_> = LatexEngine::ALL
+        .into_iter()
+        .map(|engine| {
+            let content = content.to_string();
+            let build_dir = probe_dir(build_dir, engine);
+            let cancel = cancel.cloned();
+            tauri::async_runtime::spawn(async move {
+                let available =
+                    tauri::async_runtime::spawn_blocking(move || is_engine_available(engine))
+                        .await
+                        .unwrap_or(false);
+                if !available {
+                    return None;
+                }
+                let options = CompileOptions {
+                    engine,
+                    build_dir: Some(build_dir),
+                    ..Default::default()
+                };
+                let build = compile_content_async(&content, &options, cancel.as_ref(), None).await;
+                Some(EngineResult::new(engine, build))
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for build in builds {
+        if let Ok(Some(result)) = build.await {
+            results.push(result);
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::diagnostics::Diagnostic;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_first_error_only_for_failed_builds() {
+        let failed = BuildResult {
+            success: false,
+            error_message: Some("Compilation failed - no PDF generated".to_string()),
+            diagnostics: vec![
+                Diagnostic::info("Build failed in 0.4s, 1 error, 0 warnings"),
+                Diagnostic::error("Undefined control sequence."),
+            ],
+            ..Default::default()
+        };
+        let result = EngineResult::new(LatexEngine::XeLatex, failed);
+        assert_eq!(
+            result.first_error.as_deref(),
+            Some("Undefined control sequence.")
+        );
+
+        let succeeded = BuildResult {
+            success: true,
+            diagnostics: vec![Diagnostic::error("Missing character")],
+            ..Default::default()
+        };
+        let result = EngineResult::new(LatexEngine::LuaLatex, succeeded);
+        assert_eq!(result.first_error, None);
+    }
+
+    #[test]
+    fn test_probe_builds_each_installed_engine_separately() {
+        let temp_dir = TempDir::new().unwrap();
+        let content = "\\documentclass{article}\\begin{document}Jane Doe\\end{document}";
+
+        let results = tauri::async_runtime::block_on(probe_engines(content, temp_dir.path(), None));
+
+        let installed: Vec<LatexEngine> = LatexEngine::ALL
+            .into_iter()
+            .filter(|&engine| is_engine_available(engine))
+            .collect();
+        let probed: Vec<LatexEngine> = results.iter().map(|r| r.engine).collect();
+        assert_eq!(probed, installed);
+        for engine in LatexEngine::ALL {
+            let dir = probe_dir(temp_dir.path(), engine);
+            assert_eq!(dir.exists(), installed.contains(&engine), "{}", engine);
+        }
+        for result in &results {
+            assert!(
+                result.success,
+                "{}: {:?}",
+                result.engine, result.first_error
+            );
+            // Each engine's log sits in its own folder
+            let log = probe_dir(temp_dir.path(), result.engine)
+                .join("scratch")
+                .join("build")
+                .join("scratch.log");
+            let log = std::fs::read_to_string(log).unwrap();
+            let banner = match result.engine {
+                LatexEngine::PdfLatex => "This is pdfTeX",
+                LatexEngine::XeLatex => "This is XeTeX",
+                LatexEngine::LuaLatex => "This is Lua",
+            };
+            assert!(log.contains(banner), "{}", result.engine);
+        }
+    }
+}
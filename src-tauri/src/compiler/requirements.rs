@@ -1,7 +1,10 @@
 //! System requirements checking
 
-use std::process::Command;
+use std::process::{Command, Output};
 
+use tokio::process::Command as AsyncCommand;
+
+use super::engine::LatexEngine;
 use super::pdflatex;
 
 /// Get system requirements status
@@ -9,63 +12,342 @@ use super::pdflatex;
 pub struct RequirementsStatus {
     pub pdflatex_available: bool,
     pub pdflatex_path: Option<String>,
+    /// First line of `pdflatex --version`, e.g. "pdfTeX 3.141592653-2.6-1.40.24 (TeX Live 2022)"
+    pub pdflatex_version: Option<String>,
+    /// "TeX Live" or "MiKTeX", derived from the version line
+    pub distribution: Option<String>,
+    pub engines: Vec<EngineStatus>,
+    pub tools: Vec<ToolStatus>,
+    /// Whether every required tool is available
     pub all_satisfied: bool,
+    /// How to install TeX on this platform, when pdflatex is missing
+    pub remediation: Option<String>,
+    /// Where to download a TeX distribution for this platform, when pdflatex is missing
+    pub download_url: Option<String>,
+}
+
+/// Operating systems with their own way of installing TeX
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    MacOs,
+    Linux,
+}
+
+impl Platform {
+    /// The platform this build runs on; other Unixes get the Linux advice
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+
+    /// What to tell a user without a TeX distribution
+    pub fn install_hint(&self) -> &'static str {
+        match self {
+            Platform::Windows => {
+                "Install MiKTeX, then restart ResumeIDE. Let MiKTeX install missing \
+                 packages on the fly."
+            }
+            Platform::MacOs => {
+                "Install MacTeX (or BasicTeX with `brew install --cask basictex`), then \
+                 restart ResumeIDE."
+            }
+            Platform::Linux => {
+                "Install TeX Live with your package manager, e.g. `sudo apt install \
+                 texlive-latex-extra` on Debian or Ubuntu, then restart ResumeIDE."
+            }
+        }
+    }
+
+    /// Download page of the TeX distribution recommended on this platform
+    pub fn download_url(&self) -> &'static str {
+        match self {
+            Platform::Windows => "https://miktex.org/download",
+            Platform::MacOs => "https://www.tug.org/mactex/",
+            Platform::Linux => "https://www.tug.org/texlive/",
+        }
+    }
+}
+
+/// Availability of a single LaTeX engine
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EngineStatus {
+    pub engine: LatexEngine,
+    pub available: bool,
+    pub path: Option<String>,
+}
+
+/// A tool to probe for, and whether builds can't work without it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolRequirement {
+    pub name: &'static str,
+    pub required: bool,
+}
+
+impl ToolRequirement {
+    /// A tool builds need
+    pub const fn required(name: &'static str) -> Self {
+        Self {
+            name,
+            required: true,
+        }
+    }
+
+    /// A tool only some documents need
+    pub const fn optional(name: &'static str) -> Self {
+        Self {
+            name,
+            required: false,
+        }
+    }
+}
+
+/// Tools probed by `check_requirements`
+pub const DEFAULT_TOOLS: [ToolRequirement; 7] = [
+    ToolRequirement::required("pdflatex"),
+    ToolRequirement::required("bibtex"),
+    ToolRequirement::required("kpsewhich"),
+    ToolRequirement::optional("xelatex"),
+    ToolRequirement::optional("lualatex"),
+    ToolRequirement::optional("biber"),
+    ToolRequirement::optional("makeindex"),
+];
+
+/// Availability and version of a single tool
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ToolStatus {
+    pub name: String,
+    pub required: bool,
+    pub available: bool,
+    pub path: Option<String>,
+    /// First line of `--version` output
+    pub version: Option<String>,
+}
+
+impl RequirementsStatus {
+    /// Derive the summary fields from the probed engines and tools
+    pub fn from_statuses(engines: Vec<EngineStatus>, tools: Vec<ToolStatus>) -> Self {
+        let pdflatex = engines
+            .iter()
+            .find(|status| status.engine == LatexEngine::PdfLatex);
+        let pdflatex_version = tools
+            .iter()
+            .find(|tool| tool.name == LatexEngine::PdfLatex.binary_name())
+            .and_then(|tool| tool.version.clone());
+        let pdflatex_available = pdflatex.is_some_and(|status| status.available);
+        let platform = (!pdflatex_available).then(Platform::current);
+        Self {
+            distribution: pdflatex_version.as_deref().and_then(parse_distribution),
+            pdflatex_version,
+            pdflatex_available,
+            remediation: platform.map(|p| p.install_hint().to_string()),
+            download_url: platform.map(|p| p.download_url().to_string()),
+            pdflatex_path: pdflatex.and_then(|status| status.path.clone()),
+            all_satisfied: tools
+                .iter()
+                .filter(|tool| tool.required)
+                .all(|tool| tool.available),
+            engines,
+            tools,
+        }
+    }
 }
 
 /// Check all requirements
 pub fn check_requirements() -> RequirementsStatus {
-    let pdflatex_cmd = pdflatex::get_pdflatex_command();
-    
-    // Check if available - if it's a path, just check existence
-    let pdflatex_available = if pdflatex_cmd.contains('\\') || pdflatex_cmd.contains('/') {
-        std::path::Path::new(&pdflatex_cmd).exists()
+    check_requirements_for(&DEFAULT_TOOLS)
+}
+
+/// Check requirements for a custom list of tools
+pub fn check_requirements_for(tools: &[ToolRequirement]) -> RequirementsStatus {
+    let tools: Vec<ToolStatus> = tools.iter().map(check_tool).collect();
+
+    // Reuse the probe for engines that are also in the tool list
+    let engines = LatexEngine::ALL
+        .iter()
+        .map(|&engine| probed_engine(engine, &tools).unwrap_or_else(|| check_engine(engine)))
+        .collect();
+
+    RequirementsStatus::from_statuses(engines, tools)
+}
+
+/// Check all requirements without blocking the calling thread
+pub async fn check_requirements_async() -> RequirementsStatus {
+    check_requirements_for_async(&DEFAULT_TOOLS).await
+}
+
+/// Async version of `check_requirements_for`
+pub async fn check_requirements_for_async(tools: &[ToolRequirement]) -> RequirementsStatus {
+    let mut statuses = Vec::with_capacity(tools.len());
+    for tool in tools {
+        statuses.push(check_tool_async(tool).await);
+    }
+
+    let mut engines = Vec::with_capacity(LatexEngine::ALL.len());
+    for &engine in LatexEngine::ALL.iter() {
+        engines.push(match probed_engine(engine, &statuses) {
+            Some(status) => status,
+            None => check_engine_async(engine).await,
+        });
+    }
+
+    RequirementsStatus::from_statuses(engines, statuses)
+}
+
+/// The status of an engine that was already probed as a tool
+fn probed_engine(engine: LatexEngine, tools: &[ToolStatus]) -> Option<EngineStatus> {
+    tools
+        .iter()
+        .find(|tool| tool.name == engine.binary_name())
+        .map(|tool| EngineStatus {
+            engine,
+            available: tool.available,
+            path: tool.path.clone(),
+        })
+}
+
+/// Check whether a single engine is installed and where it lives
+pub fn check_engine(engine: LatexEngine) -> EngineStatus {
+    let tool = check_tool(&ToolRequirement::required(engine.binary_name()));
+    EngineStatus {
+        engine,
+        available: tool.available,
+        path: tool.path,
+    }
+}
+
+/// Async version of `check_engine`
+pub async fn check_engine_async(engine: LatexEngine) -> EngineStatus {
+    let tool = check_tool_async(&ToolRequirement::required(engine.binary_name())).await;
+    EngineStatus {
+        engine,
+        available: tool.available,
+        path: tool.path,
+    }
+}
+
+/// Probe a tool: whether it runs, where it lives and which version it is
+pub fn check_tool(tool: &ToolRequirement) -> ToolStatus {
+    let tool_cmd = pdflatex::get_tool_command(tool.name);
+    let output = Command::new(&tool_cmd)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|o| o.status.success());
+
+    let available = is_available(&tool_cmd, &output);
+    // A bare name is in PATH, try to find the actual path
+    let path = if available && tool_cmd == tool.name {
+        find_in_path(&tool_cmd)
     } else {
-        Command::new(&pdflatex_cmd)
-            .arg("--version")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+        available.then_some(tool_cmd)
     };
+    tool_status(tool, available, path, output)
+}
 
-    // Get the path we're using
-    let pdflatex_path = if pdflatex_available {
-        if pdflatex_cmd == "pdflatex" {
-            // It's in PATH, try to find the actual path
-            #[cfg(windows)]
-            {
-                Command::new("where")
-                    .arg("pdflatex")
-                    .output()
-                    .ok()
-                    .and_then(|o| {
-                        String::from_utf8(o.stdout)
-                            .ok()
-                            .map(|s| s.lines().next().unwrap_or("").trim().to_string())
-                    })
-                    .filter(|s| !s.is_empty())
-            }
-            #[cfg(not(windows))]
-            {
-                Command::new("which")
-                    .arg("pdflatex")
-                    .output()
-                    .ok()
-                    .and_then(|o| String::from_utf8(o.stdout).ok())
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-            }
-        } else {
-            // We're using a direct path
-            Some(pdflatex_cmd)
-        }
+/// Async version of `check_tool`, running `--version` on tokio
+pub async fn check_tool_async(tool: &ToolRequirement) -> ToolStatus {
+    // Discovery may run the tool; keep it off the async worker threads
+    let name = tool.name;
+    let tool_cmd = tauri::async_runtime::spawn_blocking(move || pdflatex::get_tool_command(name))
+        .await
+        .unwrap_or_else(|_| name.to_string());
+    let output = AsyncCommand::new(&tool_cmd)
+        .arg("--version")
+        .output()
+        .await
+        .ok()
+        .filter(|o| o.status.success());
+
+    let available = is_available(&tool_cmd, &output);
+    let path = if available && tool_cmd == tool.name {
+        find_in_path_async(&tool_cmd).await
     } else {
-        None
+        available.then_some(tool_cmd)
     };
+    tool_status(tool, available, path, output)
+}
+
+/// Whether a probed tool can be used - for a path, whether it exists
+fn is_available(tool_cmd: &str, output: &Option<Output>) -> bool {
+    if tool_cmd.contains('\\') || tool_cmd.contains('/') {
+        std::path::Path::new(tool_cmd).exists()
+    } else {
+        output.is_some()
+    }
+}
+
+fn tool_status(
+    tool: &ToolRequirement,
+    available: bool,
+    path: Option<String>,
+    output: Option<Output>,
+) -> ToolStatus {
+    let version = output.and_then(|o| parse_version_line(&String::from_utf8_lossy(&o.stdout)));
+
+    ToolStatus {
+        name: tool.name.to_string(),
+        required: tool.required,
+        available,
+        path,
+        version,
+    }
+}
+
+/// The version line of `--version` output: its first non-empty line
+pub fn parse_version_line(output: &str) -> Option<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Program that resolves a command name to its path
+#[cfg(windows)]
+const PATH_LOOKUP: &str = "where";
+#[cfg(not(windows))]
+const PATH_LOOKUP: &str = "which";
+
+/// Resolve a bare command name to its full path using `where`/`which`
+fn find_in_path(binary: &str) -> Option<String> {
+    let output = Command::new(PATH_LOOKUP).arg(binary).output().ok()?;
+    parse_path_lookup(output.stdout)
+}
+
+async fn find_in_path_async(binary: &str) -> Option<String> {
+    let output = AsyncCommand::new(PATH_LOOKUP)
+        .arg(binary)
+        .output()
+        .await
+        .ok()?;
+    parse_path_lookup(output.stdout)
+}
+
+/// The first path `where`/`which` printed
+fn parse_path_lookup(stdout: Vec<u8>) -> Option<String> {
+    String::from_utf8(stdout)
+        .ok()
+        .map(|s| s.lines().next().unwrap_or("").trim().to_string())
+        .filter(|s| !s.is_empty())
+}
 
-    RequirementsStatus {
-        pdflatex_available,
-        pdflatex_path,
-        all_satisfied: pdflatex_available,
+/// Name the TeX distribution an engine's version line comes from
+///
+/// TeX Live prints e.g. "pdfTeX 3.141592653-2.6-1.40.24 (TeX Live 2022)",
+/// MiKTeX "MiKTeX-pdfTeX 4.10 (MiKTeX 22.3)".
+pub fn parse_distribution(version_line: &str) -> Option<String> {
+    if version_line.contains("MiKTeX") {
+        Some("MiKTeX".to_string())
+    } else if version_line.contains("TeX Live") {
+        Some("TeX Live".to_string())
+    } else {
+        None
     }
 }
 
@@ -78,7 +360,13 @@ mod tests {
         let status = RequirementsStatus {
             pdflatex_available: true,
             pdflatex_path: Some("/usr/bin/pdflatex".to_string()),
+            pdflatex_version: None,
+            distribution: None,
+            engines: vec![],
+            tools: vec![],
             all_satisfied: true,
+            remediation: None,
+            download_url: None,
         };
 
         let json = serde_json::to_string(&status).unwrap();
@@ -92,7 +380,13 @@ mod tests {
         let status = RequirementsStatus {
             pdflatex_available: false,
             pdflatex_path: None,
+            pdflatex_version: None,
+            distribution: None,
+            engines: vec![],
+            tools: vec![],
             all_satisfied: false,
+            remediation: None,
+            download_url: None,
         };
 
         let json = serde_json::to_string(&status).unwrap();
@@ -106,7 +400,13 @@ mod tests {
         let status = RequirementsStatus {
             pdflatex_available: true,
             pdflatex_path: Some("/path".to_string()),
+            pdflatex_version: None,
+            distribution: None,
+            engines: vec![],
+            tools: vec![],
             all_satisfied: true,
+            remediation: None,
+            download_url: None,
         };
 
         let cloned = status.clone();
@@ -120,7 +420,13 @@ mod tests {
         let status = RequirementsStatus {
             pdflatex_available: false,
             pdflatex_path: None,
+            pdflatex_version: None,
+            distribution: None,
+            engines: vec![],
+            tools: vec![],
             all_satisfied: false,
+            remediation: None,
+            download_url: None,
         };
 
         let debug_str = format!("{:?}", status);
@@ -128,11 +434,82 @@ mod tests {
         assert!(debug_str.contains("pdflatex_available: false"));
     }
 
+    #[test]
+    fn test_install_hint_per_platform() {
+        assert!(Platform::Windows.install_hint().contains("MiKTeX"));
+        assert_eq!(Platform::Windows.download_url(), "https://miktex.org/download");
+        assert!(Platform::MacOs.install_hint().contains("MacTeX"));
+        assert_eq!(Platform::MacOs.download_url(), "https://www.tug.org/mactex/");
+        assert!(Platform::Linux.install_hint().contains("apt install texlive"));
+        assert_eq!(Platform::Linux.download_url(), "https://www.tug.org/texlive/");
+
+        let expected = if cfg!(windows) {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        };
+        assert_eq!(Platform::current(), expected);
+    }
+
+    #[test]
+    fn test_remediation_only_when_pdflatex_missing() {
+        let engine = |available| EngineStatus {
+            engine: LatexEngine::PdfLatex,
+            available,
+            path: None,
+        };
+
+        let missing = RequirementsStatus::from_statuses(vec![engine(false)], vec![]);
+        let platform = Platform::current();
+        assert_eq!(missing.remediation.as_deref(), Some(platform.install_hint()));
+        assert_eq!(missing.download_url.as_deref(), Some(platform.download_url()));
+
+        let found = RequirementsStatus::from_statuses(vec![engine(true)], vec![]);
+        assert_eq!(found.remediation, None);
+        assert_eq!(found.download_url, None);
+    }
+
+    #[test]
+    fn test_engine_status_serializes() {
+        let status = EngineStatus {
+            engine: LatexEngine::XeLatex,
+            available: true,
+            path: Some("/usr/bin/xelatex".to_string()),
+        };
+
+        let json = serde_json::to_string(&status).unwrap();
+        assert!(json.contains("\"engine\":\"xelatex\""));
+        assert!(json.contains("\"available\":true"));
+    }
+
+    #[test]
+    fn test_check_requirements_reports_every_engine() {
+        let status = check_requirements();
+        assert_eq!(status.engines.len(), LatexEngine::ALL.len());
+        let pdflatex = status
+            .engines
+            .iter()
+            .find(|e| e.engine == LatexEngine::PdfLatex)
+            .unwrap();
+        assert_eq!(pdflatex.available, status.pdflatex_available);
+    }
+
     #[test]
     fn test_check_requirements_returns_status() {
         let status = check_requirements();
-        // all_satisfied should match pdflatex_available
-        assert_eq!(status.all_satisfied, status.pdflatex_available);
+        // all_satisfied requires pdflatex and every other required tool
+        assert_eq!(status.tools.len(), DEFAULT_TOOLS.len());
+        assert!(!status.all_satisfied || status.pdflatex_available);
+        assert_eq!(
+            status.all_satisfied,
+            status
+                .tools
+                .iter()
+                .filter(|t| t.required)
+                .all(|t| t.available)
+        );
     }
 
     #[test]
@@ -143,5 +520,129 @@ mod tests {
             assert!(status.pdflatex_path.is_some());
         }
     }
-}
 
+    #[test]
+    fn test_async_check_matches_sync() {
+        let sync = check_requirements();
+        let status = tauri::async_runtime::block_on(check_requirements_async());
+
+        assert_eq!(status.pdflatex_available, sync.pdflatex_available);
+        assert_eq!(status.pdflatex_path, sync.pdflatex_path);
+        assert_eq!(status.all_satisfied, sync.all_satisfied);
+        let summary = |status: &RequirementsStatus| {
+            status
+                .tools
+                .iter()
+                .map(|t| {
+                    (
+                        t.name.clone(),
+                        t.available,
+                        t.path.clone(),
+                        t.version.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(summary(&status), summary(&sync));
+        assert_eq!(status.engines.len(), sync.engines.len());
+    }
+
+    fn tool(name: &str, required: bool, available: bool) -> ToolStatus {
+        ToolStatus {
+            name: name.to_string(),
+            required,
+            available,
+            path: available.then(|| format!("/usr/bin/{}", name)),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_tool_status_serializes() {
+        let status = ToolStatus {
+            version: Some("BibTeX 0.99d (TeX Live 2022)".to_string()),
+            ..tool("bibtex", true, true)
+        };
+
+        let json = serde_json::to_string(&status).unwrap();
+        assert!(json.contains("\"name\":\"bibtex\""));
+        assert!(json.contains("\"available\":true"));
+        assert!(json.contains("\"path\":\"/usr/bin/bibtex\""));
+        assert!(json.contains("\"version\":\"BibTeX 0.99d (TeX Live 2022)\""));
+    }
+
+    #[test]
+    fn test_requirements_status_serializes_tools() {
+        let status =
+            RequirementsStatus::from_statuses(vec![], vec![tool("kpsewhich", true, false)]);
+        let json = serde_json::to_string(&status).unwrap();
+        assert!(json.contains("\"tools\":[{\"name\":\"kpsewhich\""));
+    }
+
+    #[test]
+    fn test_all_satisfied_false_when_required_tool_missing() {
+        let engines = vec![EngineStatus {
+            engine: LatexEngine::PdfLatex,
+            available: true,
+            path: Some("/usr/bin/pdflatex".to_string()),
+        }];
+        let tools = vec![tool("pdflatex", true, true), tool("bibtex", true, false)];
+
+        let status = RequirementsStatus::from_statuses(engines, tools);
+        assert!(status.pdflatex_available);
+        assert_eq!(status.pdflatex_path.as_deref(), Some("/usr/bin/pdflatex"));
+        assert!(!status.all_satisfied);
+    }
+
+    #[test]
+    fn test_all_satisfied_ignores_optional_tools() {
+        let tools = vec![tool("pdflatex", true, true), tool("biber", false, false)];
+        assert!(RequirementsStatus::from_statuses(vec![], tools).all_satisfied);
+    }
+
+    #[test]
+    fn test_parse_version_line() {
+        let output = "\nkpathsea version 6.3.4\nCopyright 2022 Karl Berry & Olaf Weber.\n";
+        assert_eq!(
+            parse_version_line(output).as_deref(),
+            Some("kpathsea version 6.3.4")
+        );
+        assert_eq!(parse_version_line("  \n"), None);
+    }
+
+    #[test]
+    fn test_parse_tex_live_version() {
+        let output = "pdfTeX 3.141592653-2.6-1.40.24 (TeX Live 2022)\n\
+                      kpathsea version 6.3.4\n";
+        let version = parse_version_line(output).unwrap();
+        assert_eq!(version, "pdfTeX 3.141592653-2.6-1.40.24 (TeX Live 2022)");
+        assert_eq!(parse_distribution(&version).as_deref(), Some("TeX Live"));
+    }
+
+    #[test]
+    fn test_parse_miktex_version() {
+        let output = "MiKTeX-pdfTeX 4.10 (MiKTeX 22.3)\n\u{a9} 1982 D. E. Knuth\n";
+        let version = parse_version_line(output).unwrap();
+        assert_eq!(version, "MiKTeX-pdfTeX 4.10 (MiKTeX 22.3)");
+        assert_eq!(parse_distribution(&version).as_deref(), Some("MiKTeX"));
+    }
+
+    #[test]
+    fn test_parse_unknown_distribution() {
+        assert_eq!(parse_distribution("pdfTeX 3.14159265-2.6-1.40.21"), None);
+    }
+
+    #[test]
+    fn test_requirements_status_reports_pdflatex_version() {
+        let tools = vec![ToolStatus {
+            version: Some("pdfTeX 3.141592653-2.6-1.40.24 (TeX Live 2022)".to_string()),
+            ..tool("pdflatex", true, true)
+        }];
+        let status = RequirementsStatus::from_statuses(vec![], tools);
+        assert_eq!(
+            status.pdflatex_version.as_deref(),
+            Some("pdfTeX 3.141592653-2.6-1.40.24 (TeX Live 2022)")
+        );
+        assert_eq!(status.distribution.as_deref(), Some("TeX Live"));
+    }
+}
@@ -0,0 +1,97 @@
+//! End-to-end check that this machine can build a document
+//!
+//! `check_requirements` only looks for the engine's executable. The
+//! self-test compiles a small document using the packages the bundled
+//! templates need, which also catches installs with missing packages or a
+//! broken format file.
+
+use std::path::Path;
+
+use super::build::{compile_content_async, CompileOptions};
+use super::engine::LatexEngine;
+use super::requirements::check_engine;
+
+/// The document the self-test compiles
+const PROBE_DOCUMENT: &str = include_str!("selftest.tex");
+
+/// What the self-test found
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SelftestReport {
+    pub engine: LatexEngine,
+    pub engine_found: bool,
+    pub engine_path: Option<String>,
+    /// Whether the build reported success
+    pub compile_succeeded: bool,
+    /// Whether a PDF was actually written
+    pub pdf_produced: bool,
+    pub duration_ms: u64,
+    /// Why the self-test failed, if it did
+    pub error_message: Option<String>,
+}
+
+/// Compile the probe document with `engine`, putting build files in `build_dir`
+pub async fn run_selftest(engine: LatexEngine, build_dir: &Path) -> SelftestReport {
+    let status = check_engine(engine);
+    let mut report = SelftestReport {
+        engine,
+        engine_found: status.available,
+        engine_path: status.path,
+        compile_succeeded: false,
+        pdf_produced: false,
+        duration_ms: 0,
+        error_message: None,
+    };
+    if !report.engine_found {
+        report.error_message = Some(format!("{} was not found", engine));
+        return report;
+    }
+
+    let options = CompileOptions {
+        engine,
+        build_dir: Some(build_dir.to_path_buf()),
+        ..Default::default()
+    };
+    let result = compile_content_async(PROBE_DOCUMENT, &options, None, None).await;
+    report.compile_succeeded = result.success;
+    report.pdf_produced = result
+        .pdf_path
+        .as_deref()
+        .and_then(|path| std::fs::read(path).ok())
+        .is_some_and(|pdf| pdf.starts_with(b"%PDF"));
+    report.duration_ms = result.duration_ms;
+    report.error_message = result.error_message;
+    if report.error_message.is_none() && !report.pdf_produced {
+        report.error_message = Some("The build did not produce a PDF".to_string());
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_selftest_report_is_populated() {
+        let temp_dir = TempDir::new().unwrap();
+        let engine = LatexEngine::PdfLatex;
+
+        let report = tauri::async_runtime::block_on(run_selftest(engine, temp_dir.path()));
+
+        assert_eq!(report.engine, engine);
+        assert_eq!(report.engine_found, check_engine(engine).available);
+        if !report.engine_found {
+            assert!(!report.compile_succeeded && !report.pdf_produced);
+            assert_eq!(
+                report.error_message.as_deref(),
+                Some("pdflatex was not found")
+            );
+            return;
+        }
+
+        // The engine is installed, so the whole toolchain should work
+        assert!(report.compile_succeeded, "{:?}", report.error_message);
+        assert!(report.pdf_produced);
+        assert!(report.duration_ms > 0);
+    }
+}
@@ -0,0 +1,453 @@
+//! SyncTeX lookups between PDF positions and source lines
+//!
+//! Builds run with `-synctex=1`, which writes `<stem>.synctex.gz` to the
+//! build directory. Positions are reported in PDF points measured from the
+//! top-left corner of the page, the way viewers show them.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+
+use super::build::resolve_build_dir;
+
+/// Scaled points per PDF point (big point)
+const SP_PER_BP: f64 = 65781.76;
+
+/// A position in a source file
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+}
+
+/// A region of a PDF page, in points from the page's top-left corner
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PdfLocation {
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// What kind of node a record describes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecordKind {
+    /// A vertical list, e.g. the page body or a paragraph
+    VBox,
+    /// A horizontal list, e.g. one line of a paragraph
+    HBox,
+    /// A point such as a glyph run, glue or kern
+    Point,
+}
+
+/// One typeset node with the source line that produced it
+#[derive(Debug, Clone)]
+struct Record {
+    kind: RecordKind,
+    page: u32,
+    tag: u32,
+    line: u32,
+    /// Left edge and baseline, in points
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    depth: f64,
+}
+
+impl Record {
+    fn top(&self) -> f64 {
+        self.y - self.height
+    }
+
+    fn bottom(&self) -> f64 {
+        self.y + self.depth
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.top() && y <= self.bottom()
+    }
+
+    /// Distance from a point to this record's rectangle
+    fn distance(&self, x: f64, y: f64) -> f64 {
+        let dx = (self.x - x).max(x - (self.x + self.width)).max(0.0);
+        let dy = (self.top() - y).max(y - self.bottom()).max(0.0);
+        dx.hypot(dy)
+    }
+}
+
+/// A parsed SyncTeX file
+#[derive(Debug, Default)]
+pub struct SyncTex {
+    inputs: HashMap<u32, String>,
+    records: Vec<Record>,
+}
+
+impl SyncTex {
+    /// Load a `.synctex.gz` (or uncompressed `.synctex`) file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("Failed to read SyncTeX file: {}", e))?;
+        let text = if bytes.starts_with(&[0x1f, 0x8b]) {
+            let mut text = String::new();
+            GzDecoder::new(&bytes[..])
+                .read_to_string(&mut text)
+                .map_err(|e| format!("Failed to decompress SyncTeX file: {}", e))?;
+            text
+        } else {
+            String::from_utf8_lossy(&bytes).to_string()
+        };
+        Self::parse(&text)
+    }
+
+    /// Parse the text of a SyncTeX file
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut synctex = SyncTex::default();
+        let mut magnification = 1000.0;
+        let mut unit = 1.0;
+        let mut x_offset = 0.0;
+        let mut y_offset = 0.0;
+        let mut in_content = false;
+        let mut page = 0;
+
+        for line in text.lines() {
+            if !in_content {
+                if let Some(input) = line.strip_prefix("Input:") {
+                    if let Some((tag, path)) = input.split_once(':') {
+                        if let Ok(tag) = tag.parse() {
+                            synctex.inputs.insert(tag, path.to_string());
+                        }
+                    }
+                } else if let Some(value) = line.strip_prefix("Magnification:") {
+                    magnification = value.trim().parse().unwrap_or(1000.0);
+                } else if let Some(value) = line.strip_prefix("Unit:") {
+                    unit = value.trim().parse().unwrap_or(1.0);
+                } else if let Some(value) = line.strip_prefix("X Offset:") {
+                    x_offset = value.trim().parse().unwrap_or(0.0);
+                } else if let Some(value) = line.strip_prefix("Y Offset:") {
+                    y_offset = value.trim().parse().unwrap_or(0.0);
+                } else if line.starts_with("Content:") {
+                    in_content = true;
+                }
+                continue;
+            }
+
+            if line.starts_with("Postamble:") {
+                break;
+            }
+            if let Some(number) = line.strip_prefix('{') {
+                page = number.trim().parse().unwrap_or(page + 1);
+                continue;
+            }
+
+            let kind = match line.as_bytes().first() {
+                Some(b'[') | Some(b'v') => RecordKind::VBox,
+                Some(b'(') | Some(b'h') => RecordKind::HBox,
+                Some(b'x') | Some(b'k') | Some(b'g') | Some(b'$') => RecordKind::Point,
+                _ => continue,
+            };
+            let Some(values) = parse_record_values(&line[1..]) else {
+                continue;
+            };
+
+            let scale = unit * magnification / 1000.0 / SP_PER_BP;
+            let to_bp = |value: Option<&f64>| value.map_or(0.0, |v| v * scale);
+            synctex.records.push(Record {
+                kind,
+                page,
+                tag: values[0] as u32,
+                line: values[1] as u32,
+                x: to_bp(values.get(2)) + x_offset * scale,
+                y: to_bp(values.get(3)) + y_offset * scale,
+                // Kerns only record a width; points have no size
+                width: if kind == RecordKind::Point {
+                    0.0
+                } else {
+                    to_bp(values.get(4))
+                },
+                height: to_bp(values.get(5)),
+                depth: to_bp(values.get(6)),
+            });
+        }
+
+        if !in_content {
+            return Err("Failed to parse SyncTeX file: no content section".to_string());
+        }
+        Ok(synctex)
+    }
+
+    /// Find the source line that produced the content at a point on a page
+    ///
+    /// Prefers the innermost line box containing the point, then the
+    /// nearest node on the page.
+    pub fn resolve(&self, page: u32, x: f64, y: f64) -> Option<SourceLocation> {
+        let on_page = || {
+            self.records
+                .iter()
+                .filter(move |r| r.page == page && r.line > 0 && r.kind != RecordKind::VBox)
+        };
+
+        let record = on_page()
+            .filter(|r| r.kind == RecordKind::HBox && r.contains(x, y))
+            .min_by(|a, b| a.width.total_cmp(&b.width))
+            .or_else(|| on_page().min_by(|a, b| a.distance(x, y).total_cmp(&b.distance(x, y))))?;
+
+        Some(SourceLocation {
+            file: normalize_input(self.inputs.get(&record.tag)?),
+            line: record.line,
+        })
+    }
+
+    /// Find where a source line was typeset
+    ///
+    /// Falls back to the closest line with output (preferring earlier
+    /// lines) since e.g. blank lines produce nothing.
+    pub fn forward(&self, file: &str, line: u32) -> Option<PdfLocation> {
+        let tags: Vec<u32> = self
+            .inputs
+            .iter()
+            .filter(|(_, input)| same_file(input, file))
+            .map(|(tag, _)| *tag)
+            .collect();
+        let candidates = || {
+            self.records
+                .iter()
+                .filter(|r| tags.contains(&r.tag) && r.line > 0 && r.kind != RecordKind::VBox)
+        };
+
+        let best_line = candidates()
+            .map(|r| r.line)
+            .min_by_key(|&l| (l.abs_diff(line), l > line))?;
+        let record = candidates()
+            .filter(|r| r.line == best_line)
+            .min_by_key(|r| (r.page, r.kind != RecordKind::HBox))?;
+
+        Some(PdfLocation {
+            page: record.page,
+            x: record.x,
+            y: record.top(),
+            width: record.width,
+            height: record.height + record.depth,
+        })
+    }
+}
+
+/// Parse `tag,line:x,y:width,height,depth`; position and size are optional
+fn parse_record_values(text: &str) -> Option<Vec<f64>> {
+    let mut values = Vec::new();
+    for (index, group) in text.split(':').enumerate() {
+        let numbers: Vec<f64> = group
+            .split(',')
+            .map(|n| n.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .ok()?;
+        // A third value in the first group is a column, which isn't used
+        let take = if index == 0 { 2 } else { numbers.len() };
+        values.extend(numbers.into_iter().take(take));
+    }
+    (values.len() >= 2).then_some(values)
+}
+
+/// Drop the `./` pdfTeX leaves in input paths
+fn normalize_input(input: &str) -> String {
+    Path::new(input)
+        .components()
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Whether a SyncTeX input path and a path from the editor name the same file
+///
+/// Either may be relative, so they match when one ends with the other.
+fn same_file(input: &str, file: &str) -> bool {
+    let input = Path::new(input);
+    let file = Path::new(file);
+    input.ends_with(file) || file.ends_with(input)
+}
+
+/// Locate the SyncTeX file written when building `tex_path`
+pub fn find_synctex(tex_path: &Path, build_dir: Option<&Path>) -> Result<PathBuf, String> {
+    let dir = resolve_build_dir(build_dir, tex_path);
+    let stem = tex_path
+        .file_stem()
+        .ok_or("Cannot determine SyncTeX file name")?
+        .to_string_lossy();
+    [".synctex.gz", ".synctex"]
+        .iter()
+        .map(|ext| dir.join(format!("{}{}", stem, ext)))
+        .find(|path| path.exists())
+        .ok_or_else(|| "No SyncTeX data found; build the document first".to_string())
+}
+
+/// Locate the SyncTeX file for the document being edited
+///
+/// The build may have been of `tex_path` itself or of its project's main
+/// file `main_path`. The delivered PDF can't be used instead: its name
+/// follows `output_name` and it may live in the build directory.
+pub fn find_synctex_for(
+    tex_path: &Path,
+    main_path: &Path,
+    build_dir: Option<&Path>,
+) -> Result<PathBuf, String> {
+    find_synctex(tex_path, build_dir).or_else(|e| {
+        if main_path == tex_path {
+            Err(e)
+        } else {
+            find_synctex(main_path, build_dir)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const FIXTURE: &str = include_str!("testdata/resume.synctex");
+
+    fn fixture() -> SyncTex {
+        SyncTex::parse(FIXTURE).unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_fixture() {
+        let synctex = fixture();
+        assert_eq!(synctex.inputs.len(), 3);
+        assert_eq!(synctex.records.len(), 14);
+        assert!(synctex.records.iter().any(|r| r.page == 2));
+    }
+
+    #[test]
+    fn test_parse_rejects_non_synctex() {
+        assert!(SyncTex::parse("This is pdfTeX\n").is_err());
+    }
+
+    #[test]
+    fn test_load_gzipped_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.synctex.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(FIXTURE.as_bytes()).unwrap();
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let synctex = SyncTex::load(&path).unwrap();
+        assert_eq!(synctex.records.len(), 14);
+    }
+
+    #[test]
+    fn test_resolve_point_in_line() {
+        let location = fixture().resolve(1, 100.0, 90.0).unwrap();
+        assert_eq!(location.file, "/home/user/resume/resume.tex");
+        assert_eq!(location.line, 6);
+    }
+
+    #[test]
+    fn test_resolve_point_in_included_file() {
+        let location = fixture().resolve(1, 100.0, 150.0).unwrap();
+        assert_eq!(location.file, "/home/user/resume/sections/experience.tex");
+        assert_eq!(location.line, 2);
+    }
+
+    #[test]
+    fn test_resolve_between_lines_uses_nearest() {
+        let location = fixture().resolve(1, 100.0, 160.0).unwrap();
+        assert_eq!(location.line, 2);
+        assert_eq!(fixture().resolve(2, 100.0, 300.0).unwrap().line, 12);
+    }
+
+    #[test]
+    fn test_resolve_missing_page() {
+        assert_eq!(fixture().resolve(5, 100.0, 100.0), None);
+    }
+
+    #[test]
+    fn test_forward_exact_line() {
+        let location = fixture().forward("resume.tex", 8).unwrap();
+        assert_eq!(location.page, 1);
+        assert_close(location.x, 72.0);
+        assert_close(location.y, (9_000_000.0 - 655_360.0) / SP_PER_BP);
+        assert_close(location.width, 20_000_000.0 / SP_PER_BP);
+        assert_close(location.height, (655_360.0 + 196_608.0) / SP_PER_BP);
+    }
+
+    #[test]
+    fn test_forward_other_page_and_file() {
+        assert_eq!(fixture().forward("resume.tex", 12).unwrap().page, 2);
+        let location = fixture()
+            .forward("/home/user/resume/sections/experience.tex", 2)
+            .unwrap();
+        assert_close(location.y, (10_000_000.0 - 655_360.0) / SP_PER_BP);
+    }
+
+    #[test]
+    fn test_forward_line_without_output_uses_previous() {
+        let location = fixture().forward("resume.tex", 7).unwrap();
+        assert_close(location.y, (6_099_101.0 - 655_360.0) / SP_PER_BP);
+    }
+
+    #[test]
+    fn test_forward_unknown_file() {
+        assert_eq!(fixture().forward("cover-letter.tex", 1), None);
+    }
+
+    #[test]
+    fn test_find_synctex_in_build_dir() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("resume.tex");
+        assert!(find_synctex(&tex_path, Some(temp_dir.path())).is_err());
+
+        let synctex_path = temp_dir.path().join("resume.synctex.gz");
+        std::fs::write(&synctex_path, "").unwrap();
+        assert_eq!(
+            find_synctex(&tex_path, Some(temp_dir.path())).unwrap(),
+            synctex_path
+        );
+    }
+
+    #[test]
+    fn test_find_synctex_for_pdf_left_in_build_dir() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tex_path = temp_dir.path().join("project").join("resume.tex");
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir(&build_dir).unwrap();
+        // A renamed PDF kept in the build directory, next to the SyncTeX data
+        std::fs::write(build_dir.join("Jane_Doe_Resume.pdf"), b"%PDF-1.5").unwrap();
+        let synctex_path = build_dir.join("resume.synctex.gz");
+        std::fs::write(&synctex_path, "").unwrap();
+
+        assert_eq!(
+            find_synctex_for(&tex_path, &tex_path, Some(&build_dir)).unwrap(),
+            synctex_path
+        );
+    }
+
+    #[test]
+    fn test_find_synctex_for_falls_back_to_main_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let section = temp_dir.path().join("sections").join("work.tex");
+        let main = temp_dir.path().join("main.tex");
+        let synctex_path = temp_dir.path().join("main.synctex");
+        std::fs::write(&synctex_path, "").unwrap();
+
+        assert_eq!(
+            find_synctex_for(&section, &main, Some(temp_dir.path())).unwrap(),
+            synctex_path
+        );
+        assert!(find_synctex_for(&section, &section, Some(temp_dir.path())).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! Line diffs between the editor content and the file on disk
+//!
+//! Used to review changes before saving and to see what another program
+//! changed. Hunks carry a few lines of context, like `diff -u`.
+
+/// Unchanged lines shown around each change
+pub const CONTEXT_LINES: usize = 3;
+
+/// How a line differs between the two versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Unchanged,
+}
+
+/// One line of a hunk
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DiffLine {
+    pub kind: ChangeKind,
+    pub content: String,
+    /// 1-based line in the old version; `None` for added lines
+    pub old_line: Option<u32>,
+    /// 1-based line in the new version; `None` for removed lines
+    pub new_line: Option<u32>,
+}
+
+/// A run of changes and their context
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DiffHunk {
+    /// 1-based first line of the hunk in the old version
+    pub old_start: u32,
+    pub old_lines: u32,
+    /// 1-based first line of the hunk in the new version
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Diff `old` against `new` line by line; identical texts have no hunks
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffHunk> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    hunks(edit_script(&old, &new))
+}
+
+/// Every line of both versions, in order, marked as kept, removed or added
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    // Only the part between the common prefix and suffix needs the table
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    // lcs[i][j]: longest common subsequence of old_mid[i..] and new_mid[j..]
+    let mut lcs = vec![vec![0u32; new_mid.len() + 1]; old_mid.len() + 1];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i][j] = if old_mid[i] == new_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut script = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    let mut push = |kind, content: &str, i: usize, j: usize| {
+        script.push(DiffLine {
+            kind,
+            content: content.to_string(),
+            old_line: (kind != ChangeKind::Added).then_some(i as u32 + 1),
+            new_line: (kind != ChangeKind::Removed).then_some(j as u32 + 1),
+        })
+    };
+    for (k, line) in old[..prefix].iter().enumerate() {
+        push(ChangeKind::Unchanged, line, k, k);
+    }
+    while i < old_mid.len() || j < new_mid.len() {
+        let (oi, nj) = (prefix + i, prefix + j);
+        if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+            push(ChangeKind::Unchanged, old_mid[i], oi, nj);
+            i += 1;
+            j += 1;
+        } else if i < old_mid.len() && (j == new_mid.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removals first, so a replaced line reads old then new
+            push(ChangeKind::Removed, old_mid[i], oi, nj);
+            i += 1;
+        } else {
+            push(ChangeKind::Added, new_mid[j], oi, nj);
+            j += 1;
+        }
+    }
+    for k in 0..suffix {
+        let (oi, nj) = (old.len() - suffix + k, new.len() - suffix + k);
+        push(ChangeKind::Unchanged, old[oi], oi, nj);
+    }
+    script
+}
+
+/// Group the changes in an edit script with `CONTEXT_LINES` of context
+fn hunks(script: Vec<DiffLine>) -> Vec<DiffHunk> {
+    let changed: Vec<usize> = script
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.kind != ChangeKind::Unchanged)
+        .map(|(index, _)| index)
+        .collect();
+
+    // Ranges of script indices, merged where their context overlaps
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for index in changed {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(script.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let before = &script[..start];
+            let lines = script[start..end].to_vec();
+            let count = |lines: &[DiffLine], kind| {
+                lines.iter().filter(|line| line.kind != kind).count() as u32
+            };
+            DiffHunk {
+                old_start: count(before, ChangeKind::Added) + 1,
+                old_lines: count(&lines, ChangeKind::Added),
+                new_start: count(before, ChangeKind::Removed) + 1,
+                new_lines: count(&lines, ChangeKind::Removed),
+                lines,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(hunk: &DiffHunk) -> Vec<(ChangeKind, &str)> {
+        hunk.lines
+            .iter()
+            .map(|line| (line.kind, line.content.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_content_has_no_hunks() {
+        let text = "\\section{Skills}\nRust\n";
+        assert!(diff_lines(text, text).is_empty());
+        assert!(diff_lines("", "").is_empty());
+    }
+
+    #[test]
+    fn test_added_line() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nb\nc\nd\nnew\ne\nf\ng\nh\n";
+        let hunks = diff_lines(old, new);
+
+        assert_eq!(hunks.len(), 1);
+        let hunk = &hunks[0];
+        assert_eq!(
+            (
+                hunk.old_start,
+                hunk.old_lines,
+                hunk.new_start,
+                hunk.new_lines
+            ),
+            (2, 6, 2, 7)
+        );
+        assert_eq!(hunk.lines[3].kind, ChangeKind::Added);
+        assert_eq!(hunk.lines[3].content, "new");
+        assert_eq!(
+            (hunk.lines[3].old_line, hunk.lines[3].new_line),
+            (None, Some(5))
+        );
+        assert_eq!(hunk.lines[4].old_line, Some(5));
+        assert_eq!(hunk.lines[4].new_line, Some(6));
+    }
+
+    #[test]
+    fn test_removed_line() {
+        let hunks = diff_lines("one\ntwo\nthree\n", "one\nthree\n");
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(
+            kinds(&hunks[0]),
+            [
+                (ChangeKind::Unchanged, "one"),
+                (ChangeKind::Removed, "two"),
+                (ChangeKind::Unchanged, "three"),
+            ]
+        );
+        assert_eq!(hunks[0].lines[1].old_line, Some(2));
+        assert_eq!((hunks[0].old_lines, hunks[0].new_lines), (3, 2));
+    }
+
+    #[test]
+    fn test_distant_changes_make_separate_hunks() {
+        let old: String = (1..=20).map(|n| format!("{}\n", n)).collect();
+        let new: String = (1..=20)
+            .map(|n| match n {
+                2 => "two\n".to_string(),
+                19 => "nineteen\n".to_string(),
+                _ => format!("{}\n", n),
+            })
+            .collect();
+        let hunks = diff_lines(&old, &new);
+
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].old_start, hunks[0].new_start), (1, 1));
+        assert_eq!((hunks[1].old_start, hunks[1].new_start), (16, 16));
+        assert_eq!(
+            kinds(&hunks[1])[3..5],
+            [(ChangeKind::Removed, "19"), (ChangeKind::Added, "nineteen")]
+        );
+    }
+}
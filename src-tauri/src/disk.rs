@@ -0,0 +1,170 @@
+//! Free disk space checks
+//!
+//! Builds and backups write files of unknown size; on a nearly full disk
+//! they fail halfway through with confusing errors. Checking first lets
+//! them refuse to start with a clear message instead.
+
+use std::path::Path;
+
+/// Error returned when an operation is refused for lack of space
+pub const INSUFFICIENT_DISK_SPACE: &str = "Insufficient disk space";
+
+/// Space that must remain free after a write
+pub const MIN_FREE_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Free space on the volume holding a path
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DiskStatus {
+    pub path: String,
+    /// Bytes the current user can still write
+    pub available_bytes: u64,
+    /// Whether builds and backups will run
+    pub sufficient: bool,
+}
+
+/// Free space on the volume holding `path`
+///
+/// `path` doesn't need to exist yet; its nearest existing ancestor is used.
+pub fn disk_status(path: &Path) -> Result<DiskStatus, String> {
+    let available_bytes = available_bytes(existing_ancestor(path))?;
+    Ok(DiskStatus {
+        path: path.to_string_lossy().to_string(),
+        available_bytes,
+        sufficient: has_enough_space(available_bytes, 0),
+    })
+}
+
+/// Whether writing `needed` bytes leaves at least `MIN_FREE_BYTES` free
+pub fn has_enough_space(available: u64, needed: u64) -> bool {
+    available >= needed.saturating_add(MIN_FREE_BYTES)
+}
+
+/// Fail with `INSUFFICIENT_DISK_SPACE` if writing `needed` bytes under
+/// `path` would nearly fill its volume
+///
+/// If free space can't be determined the write is allowed, since the
+/// check is only there to give a better error.
+pub fn ensure_disk_space(path: &Path, needed: u64) -> Result<(), String> {
+    match available_bytes(existing_ancestor(path)) {
+        Ok(available) => check_space(available, needed),
+        Err(_) => Ok(()),
+    }
+}
+
+fn check_space(available: u64, needed: u64) -> Result<(), String> {
+    if has_enough_space(available, needed) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: only {} MB free",
+            INSUFFICIENT_DISK_SPACE,
+            available / (1024 * 1024)
+        ))
+    }
+}
+
+fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|dir| dir.exists())
+        .unwrap_or(Path::new("."))
+}
+
+#[cfg(unix)]
+fn available_bytes(path: &Path) -> Result<u64, String> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| format!("Failed to read free disk space: {}", e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(format!(
+            "Failed to read free disk space: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    // The field types differ between platforms
+    #[allow(clippy::useless_conversion)]
+    Ok(u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize)))
+}
+
+#[cfg(windows)]
+fn available_bytes(path: &Path) -> Result<u64, String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut available = 0u64;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(format!(
+            "Failed to read free disk space: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn available_bytes(_path: &Path) -> Result<u64, String> {
+    Err("Free disk space can't be determined on this platform".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_threshold_decision() {
+        assert!(has_enough_space(MIN_FREE_BYTES, 0));
+        assert!(!has_enough_space(MIN_FREE_BYTES - 1, 0));
+        assert!(has_enough_space(MIN_FREE_BYTES + 10 * MB, 10 * MB));
+        assert!(!has_enough_space(MIN_FREE_BYTES + 10 * MB, 10 * MB + 1));
+        assert!(!has_enough_space(u64::MAX - 1, u64::MAX));
+    }
+
+    #[test]
+    fn test_check_space_error_message() {
+        assert!(check_space(MIN_FREE_BYTES, 0).is_ok());
+        let err = check_space(12 * MB, 0).unwrap_err();
+        assert_eq!(err, "Insufficient disk space: only 12 MB free");
+    }
+
+    #[test]
+    fn test_disk_status_serialization() {
+        let status = DiskStatus {
+            path: "/home/jane".to_string(),
+            available_bytes: 5 * MB,
+            sufficient: false,
+        };
+        let json = serde_json::to_string(&status).unwrap();
+        assert_eq!(
+            json,
+            r#"{"path":"/home/jane","available_bytes":5242880,"sufficient":false}"#
+        );
+    }
+
+    #[test]
+    fn test_disk_status_for_missing_path_uses_ancestor() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let status = disk_status(&temp_dir.path().join("not/yet/created")).unwrap();
+        assert!(status.available_bytes > 0);
+        assert_eq!(
+            status.sufficient,
+            has_enough_space(status.available_bytes, 0)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Text encoding detection for source files
+//!
+//! Files are decoded to UTF-8 for the editor and written back in the
+//! encoding they were read with.
+
+/// UTF-8 byte order mark
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// UTF-16 little-endian byte order mark, as Windows Notepad writes "Unicode" files
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+
+/// UTF-16 big-endian byte order mark
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Encoding a text file is stored in on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    /// UTF-8 with a leading byte order mark
+    Utf8Bom,
+    /// ISO-8859-1, common for older Windows resumes using `inputenc[latin1]`
+    Latin1,
+    /// UTF-16 little-endian with a byte order mark
+    Utf16Le,
+    /// UTF-16 big-endian with a byte order mark
+    Utf16Be,
+}
+
+/// Decode file bytes, detecting their encoding
+///
+/// A BOM wins; otherwise valid UTF-8 is taken as-is and anything else is
+/// treated as Latin-1, which can represent every byte.
+pub fn decode(bytes: &[u8]) -> (String, TextEncoding) {
+    if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        return (
+            String::from_utf8_lossy(rest).to_string(),
+            TextEncoding::Utf8Bom,
+        );
+    }
+    if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+        return (decode_utf16(rest, u16::from_le_bytes), TextEncoding::Utf16Le);
+    }
+    if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+        return (decode_utf16(rest, u16::from_be_bytes), TextEncoding::Utf16Be);
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), TextEncoding::Utf8),
+        Err(_) => (
+            bytes.iter().map(|&b| char::from(b)).collect(),
+            TextEncoding::Latin1,
+        ),
+    }
+}
+
+/// Decode UTF-16 code units; a dangling odd byte becomes a replacement character
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let chunks = bytes.chunks_exact(2);
+    let dangling = !chunks.remainder().is_empty();
+    let units: Vec<u16> = chunks.map(|pair| unit([pair[0], pair[1]])).collect();
+    let mut text = String::from_utf16_lossy(&units);
+    if dangling {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    text
+}
+
+fn encode_utf16(content: &str, bom: &[u8], unit: fn(u16) -> [u8; 2]) -> Vec<u8> {
+    let mut bytes = bom.to_vec();
+    bytes.extend(content.encode_utf16().flat_map(unit));
+    bytes
+}
+
+/// Encode text for writing in the given encoding
+pub fn encode(content: &str, encoding: TextEncoding) -> Result<Vec<u8>, String> {
+    match encoding {
+        TextEncoding::Utf8 => Ok(content.as_bytes().to_vec()),
+        TextEncoding::Utf8Bom => {
+            let mut bytes = UTF8_BOM.to_vec();
+            bytes.extend_from_slice(content.as_bytes());
+            Ok(bytes)
+        }
+        TextEncoding::Latin1 => content
+            .chars()
+            .map(|c| {
+                u8::try_from(u32::from(c)).map_err(|_| {
+                    format!(
+                        "Character '{}' cannot be saved in Latin-1; convert the file to UTF-8",
+                        c
+                    )
+                })
+            })
+            .collect(),
+        TextEncoding::Utf16Le => Ok(encode_utf16(content, UTF16_LE_BOM, u16::to_le_bytes)),
+        TextEncoding::Utf16Be => Ok(encode_utf16(content, UTF16_BE_BOM, u16::to_be_bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_plain_utf8() {
+        let (text, encoding) = decode("Café résumé".as_bytes());
+        assert_eq!(text, "Café résumé");
+        assert_eq!(encoding, TextEncoding::Utf8);
+    }
+
+    #[test]
+    fn test_decode_utf8_bom() {
+        let bytes = b"\xEF\xBB\xBF\\documentclass{article}";
+        let (text, encoding) = decode(bytes);
+        assert_eq!(text, "\\documentclass{article}");
+        assert_eq!(encoding, TextEncoding::Utf8Bom);
+    }
+
+    #[test]
+    fn test_decode_latin1_fallback() {
+        // "Café" with é as the single Latin-1 byte 0xE9
+        let bytes = b"Caf\xE9 M\xFCller";
+        let (text, encoding) = decode(bytes);
+        assert_eq!(text, "Café Müller");
+        assert_eq!(encoding, TextEncoding::Latin1);
+    }
+
+    #[test]
+    fn test_decode_utf16_le_bom() {
+        // "Café" with a BOM, as Notepad saves "Unicode" text
+        let bytes = b"\xFF\xFEC\x00a\x00f\x00\xE9\x00";
+        let (text, encoding) = decode(bytes);
+        assert_eq!(text, "Café");
+        assert_eq!(encoding, TextEncoding::Utf16Le);
+    }
+
+    #[test]
+    fn test_decode_utf16_be_bom() {
+        // A check mark (not in Latin-1), a space and a backslash
+        let bytes = b"\xFE\xFF\x27\x13\x00 \x00\\";
+        let (text, encoding) = decode(bytes);
+        assert_eq!(text, "✓ \\");
+        assert_eq!(encoding, TextEncoding::Utf16Be);
+    }
+
+    #[test]
+    fn test_decode_utf16_odd_length() {
+        let (text, _) = decode(b"\xFF\xFEA\x00B");
+        assert_eq!(text, "A\u{FFFD}");
+    }
+
+    #[test]
+    fn test_round_trip_preserves_bytes() {
+        for bytes in [
+            &b"plain ascii"[..],
+            &b"\xEF\xBB\xBFwith bom \xC3\xA9"[..],
+            &b"Caf\xE9 M\xFCller"[..],
+            &b"\xFF\xFEC\x00a\x00f\x00\xE9\x00\x3D\xD8\x00\xDE"[..],
+            &b"\xFE\xFF\x00C\x00a\x00f\x00\xE9"[..],
+        ] {
+            let (text, encoding) = decode(bytes);
+            assert_eq!(encode(&text, encoding).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_encode_latin1_rejects_unrepresentable() {
+        let err = encode("Résumé ✓", TextEncoding::Latin1).unwrap_err();
+        assert!(err.contains("Latin-1"));
+    }
+
+    #[test]
+    fn test_encoding_serializes_kebab_case() {
+        let json = serde_json::to_string(&TextEncoding::Utf8Bom).unwrap();
+        assert_eq!(json, "\"utf8-bom\"");
+    }
+}
@@ -3,22 +3,257 @@
 //! Handles reading and writing .tex files
 
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use crate::encoding::{decode, encode, TextEncoding};
+use crate::line_endings::{detect_line_ending, LineEnding};
 
 /// Read a file and return its contents
 pub fn read_file(path: &Path) -> Result<String, String> {
-    fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))
+    read_file_with_encoding(path).map(|(content, _)| content)
+}
+
+/// Read a file, returning its contents decoded to UTF-8 and the encoding
+/// it is stored in
+pub fn read_file_with_encoding(path: &Path) -> Result<(String, TextEncoding), String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(decode(&bytes))
+}
+
+/// Encoding of an existing file; new or unreadable files default to UTF-8
+pub fn file_encoding(path: &Path) -> TextEncoding {
+    fs::read(path)
+        .map(|bytes| decode(&bytes).1)
+        .unwrap_or_default()
+}
+
+/// The line ending to save `path` with under the `setting`
+///
+/// `Preserve` keeps the dominant ending of the file on disk; new files use
+/// whatever the content itself mostly uses.
+pub fn line_ending_for(path: &Path, setting: LineEnding) -> LineEnding {
+    match setting {
+        LineEnding::Preserve => read_file(path)
+            .map(|existing| detect_line_ending(&existing))
+            .unwrap_or(LineEnding::Preserve),
+        ending => ending,
+    }
 }
 
-/// Write content to a file
+/// Write content to a file, keeping the encoding of the file it replaces
 pub fn write_file(path: &Path, content: &str) -> Result<(), String> {
-    fs::write(path, content).map_err(|e| format!("Failed to write file: {}", e))
+    write_file_with_encoding(path, content, file_encoding(path))
+}
+
+/// Write content to a file in the given encoding
+///
+/// The content goes to a temporary file next to the target first and is
+/// renamed over it, so a crash mid-write never leaves a truncated file.
+pub fn write_file_with_encoding(
+    path: &Path,
+    content: &str,
+    encoding: TextEncoding,
+) -> Result<(), String> {
+    let bytes = encode(content, encoding).map_err(|e| format!("Failed to write file: {}", e))?;
+    write_bytes(path, &bytes)
+}
+
+/// Write raw bytes to a file the same crash-safe way as `write_file_with_encoding`
+pub fn write_bytes(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    // Renaming over a read-only file would succeed, so check up front
+    check_writable(path)?;
+    let temp_path = temp_path_for(path);
+    let result = write_and_sync(&temp_path, bytes)
+        .and_then(|_| replace_file(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.map_err(describe_write_error)
+}
+
+/// Check that `path` can be saved, explaining why not in terms a user understands
+pub fn check_writable(path: &Path) -> Result<(), String> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => Err("Path is a folder, not a file".to_string()),
+        Ok(metadata) if metadata.permissions().readonly() => Err("File is read-only".to_string()),
+        // Opening for writing (without truncating) catches files locked by other programs
+        Ok(_) => fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map(|_| ())
+            .map_err(describe_write_error),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                Some(parent) if !parent.is_dir() => Err("Folder does not exist".to_string()),
+                _ => Ok(()),
+            }
+        }
+        Err(e) => Err(describe_write_error(e)),
+    }
+}
+
+/// Message for an OS error from writing a file
+fn describe_write_error(e: std::io::Error) -> String {
+    if is_locked_error(&e) {
+        return "File is locked by another program".to_string();
+    }
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied => {
+            "Permission denied: you can't write to this location".to_string()
+        }
+        std::io::ErrorKind::NotFound => "Folder does not exist".to_string(),
+        _ => format!("Failed to write file: {}", e),
+    }
+}
+
+/// Whether an error means another program has the file open exclusively
+#[cfg(windows)]
+pub(crate) fn is_locked_error(e: &std::io::Error) -> bool {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    matches!(
+        e.raw_os_error(),
+        Some(ERROR_SHARING_VIOLATION) | Some(ERROR_LOCK_VIOLATION)
+    )
+}
+
+/// Whether an error means another program has the file open exclusively
+///
+/// Locks are advisory outside Windows, so they never block a write.
+#[cfg(not(windows))]
+pub(crate) fn is_locked_error(_e: &std::io::Error) -> bool {
+    false
+}
+
+/// Temporary sibling used while writing, e.g. `resume.tex.tmp`
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Write bytes and flush them to disk before returning
+fn write_and_sync(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// Move the temporary file over the target
+///
+/// Only a rename is atomic. The temporary file is in the same folder, so
+/// the rename can't cross devices; any other failure (a locked or
+/// read-only target) is returned rather than copying over the original,
+/// which a crash could leave truncated.
+fn replace_file(temp_path: &Path, path: &Path) -> std::io::Result<()> {
+    fs::rename(temp_path, path)
+}
+
+/// Error for a path outside the directories file operations are limited to
+pub const PATH_NOT_ALLOWED: &str = "Path outside allowed directories";
+
+/// Resolve `path` to an absolute path with no `..`, `.` or symlinks
+///
+/// A file that doesn't exist yet (e.g. a save target) is resolved through
+/// its folder, which must exist.
+pub fn canonical_path(path: &Path) -> Result<PathBuf, String> {
+    match fs::canonicalize(path) {
+        Ok(path) => Ok(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let name = match path.components().next_back() {
+                Some(Component::Normal(name)) => name,
+                _ => return Err(format!("Failed to resolve path: {}", e)),
+            };
+            let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+            let parent = fs::canonicalize(parent.unwrap_or(Path::new(".")))
+                .map_err(|e| format!("Failed to resolve path: {}", e))?;
+            Ok(parent.join(name))
+        }
+        Err(e) => Err(format!("Failed to resolve path: {}", e)),
+    }
+}
+
+/// Absolute form of a path the frontend passed in, as shown to the user
+///
+/// Symlinks are resolved where the path exists. A save target in a folder
+/// that doesn't exist yet is made absolute without touching the disk.
+/// Windows' `\\?\` prefix is dropped, since it confuses the engines.
+pub fn normalized_path(path: &Path) -> Result<PathBuf, String> {
+    let resolved = match canonical_path(path) {
+        Ok(resolved) => resolved,
+        Err(_) => {
+            let cwd = std::env::current_dir()
+                .map_err(|e| format!("Failed to resolve path: {}", e))?;
+            lexically_normalized(&cwd.join(path))
+        }
+    };
+    if cfg!(windows) {
+        let text = resolved.to_string_lossy();
+        if let Some(simplified) = strip_verbatim_prefix(&text) {
+            return Ok(PathBuf::from(simplified));
+        }
+    }
+    Ok(resolved)
+}
+
+/// Drop `.` segments and fold `..` into the segment before it
+fn lexically_normalized(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// `\\?\C:\x` becomes `C:\x` and `\\?\UNC\server\share` becomes `\\server\share`
+fn strip_verbatim_prefix(path: &str) -> Option<String> {
+    let rest = path.strip_prefix(r"\\?\")?;
+    if let Some(unc) = rest.strip_prefix(r"UNC\") {
+        return Some(format!(r"\\{}", unc));
+    }
+    // Only drive paths are safe without the prefix
+    let bytes = rest.as_bytes();
+    (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+        .then(|| rest.to_string())
 }
 
-/// Check if a path has a .tex extension
+/// Check that `path` lies inside one of the `allowed` directories
+///
+/// Both sides are canonicalized, so `..` and symlinks can't escape.
+/// Returns the resolved path.
+pub fn check_path_allowed(path: &Path, allowed: &[PathBuf]) -> Result<PathBuf, String> {
+    let resolved = canonical_path(path).map_err(|_| PATH_NOT_ALLOWED.to_string())?;
+    let inside = allowed
+        .iter()
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .any(|dir| resolved.starts_with(dir));
+    if inside {
+        Ok(resolved)
+    } else {
+        Err(PATH_NOT_ALLOWED.to_string())
+    }
+}
+
+/// Extensions of LaTeX source files, compared case-insensitively
+pub const TEX_EXTENSIONS: [&str; 3] = ["tex", "ltx", "latex"];
+
+/// Extension given to new LaTeX files
+pub const DEFAULT_TEX_EXTENSION: &str = TEX_EXTENSIONS[0];
+
+/// Check if a path has one of the `TEX_EXTENSIONS`
 pub fn is_tex_file(path: &Path) -> bool {
     path.extension()
-        .map(|ext| ext.to_string_lossy().to_lowercase() == "tex")
+        .map(|ext| {
+            let ext = ext.to_string_lossy().to_lowercase();
+            TEX_EXTENSIONS.contains(&ext.as_str())
+        })
         .unwrap_or(false)
 }
 
@@ -29,12 +264,27 @@ pub fn get_file_name(path: &Path) -> String {
         .unwrap_or_else(|| "Untitled".to_string())
 }
 
+/// Split a file name into its stem and extension
+///
+/// Only the last dot separates the extension (`my.resume.tex` is
+/// `my.resume` + `tex`), and a leading dot doesn't (`.hidden` has no
+/// extension). The extension is empty when there is none.
+pub fn file_name_parts(path: &Path) -> (String, String) {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Untitled".to_string());
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
+    (stem, extension)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Write;
-    use std::path::PathBuf;
-    use tempfile::NamedTempFile;
+    use tempfile::{NamedTempFile, TempDir};
 
     #[test]
     fn test_is_tex_file_true() {
@@ -54,12 +304,140 @@ mod tests {
         assert!(is_tex_file(&path));
     }
 
+    #[test]
+    fn test_is_tex_file_rejects_backups_and_extensionless() {
+        assert!(!is_tex_file(Path::new("resume.tex.bak")));
+        assert!(!is_tex_file(Path::new("resume.txt")));
+        assert!(!is_tex_file(Path::new("resume.pdf")));
+        assert!(!is_tex_file(Path::new("Makefile")));
+        assert!(!is_tex_file(Path::new(".tex")));
+    }
+
+    #[test]
+    fn test_is_tex_file_accepts_every_latex_extension() {
+        for name in ["resume.tex", "resume.ltx", "resume.latex", "RESUME.LTX", "cv.LaTeX"] {
+            assert!(is_tex_file(Path::new(name)), "{}", name);
+        }
+        assert_eq!(DEFAULT_TEX_EXTENSION, "tex");
+    }
+
+    #[test]
+    fn test_check_path_allowed_rejects_traversal() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir_all(workspace.join("projects")).unwrap();
+        fs::write(temp_dir.path().join("secret.txt"), "x").unwrap();
+        let allowed = [workspace.clone()];
+
+        for path in [
+            workspace.join("projects/../../secret.txt"),
+            workspace.join("../../../../../../etc/passwd"),
+            workspace.join("projects/../.."),
+            temp_dir.path().join("secret.txt"),
+        ] {
+            assert_eq!(
+                check_path_allowed(&path, &allowed).unwrap_err(),
+                PATH_NOT_ALLOWED,
+                "{}",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_path_allowed_accepts_workspace_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir_all(workspace.join("projects")).unwrap();
+        fs::write(workspace.join("projects/resume.tex"), "x").unwrap();
+        let allowed = [temp_dir.path().join("elsewhere"), workspace.clone()];
+
+        let existing =
+            check_path_allowed(&workspace.join("projects/resume.tex"), &allowed).unwrap();
+        assert!(existing.ends_with("projects/resume.tex"));
+        // A save target that doesn't exist yet, reached through `..`
+        let new_file = workspace.join("projects/../cover.tex");
+        let resolved = check_path_allowed(&new_file, &allowed).unwrap();
+        assert_eq!(
+            resolved,
+            fs::canonicalize(&workspace).unwrap().join("cover.tex")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_check_path_allowed_follows_symlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir_all(&workspace).unwrap();
+        std::os::unix::fs::symlink("/etc", workspace.join("link")).unwrap();
+
+        let err = check_path_allowed(&workspace.join("link/passwd"), &[workspace]).unwrap_err();
+        assert_eq!(err, PATH_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn test_normalized_path_makes_relative_paths_absolute() {
+        let path = normalized_path(Path::new("no-such-folder/resume.tex")).unwrap();
+        assert!(path.is_absolute());
+        assert!(path.ends_with("no-such-folder/resume.tex"));
+    }
+
+    #[test]
+    fn test_normalized_path_collapses_parent_segments() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("a")).unwrap();
+        fs::write(temp_dir.path().join("b.tex"), "").unwrap();
+        let expected = normalized_path(&temp_dir.path().join("b.tex")).unwrap();
+
+        let path = normalized_path(&temp_dir.path().join("a/../b.tex")).unwrap();
+        assert_eq!(path, expected);
+        // Same when the folders don't exist yet
+        assert_eq!(
+            lexically_normalized(Path::new("/resumes/./a/../b.tex")),
+            PathBuf::from("/resumes/b.tex")
+        );
+    }
+
+    #[test]
+    fn test_strip_verbatim_prefix() {
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\C:\Users\jane\resume.tex").as_deref(),
+            Some(r"C:\Users\jane\resume.tex")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\server\share\resume.tex").as_deref(),
+            Some(r"\\server\share\resume.tex")
+        );
+        assert_eq!(strip_verbatim_prefix(r"\\?\Volume{abc}\resume.tex"), None);
+        assert_eq!(strip_verbatim_prefix(r"C:\resume.tex"), None);
+    }
+
     #[test]
     fn test_get_file_name() {
         let path = PathBuf::from("/some/path/resume.tex");
         assert_eq!(get_file_name(&path), "resume.tex");
     }
 
+    #[test]
+    fn test_file_name_parts() {
+        let parts = |path: &str| file_name_parts(Path::new(path));
+        assert_eq!(
+            parts("/some/path/resume.tex"),
+            ("resume".into(), "tex".into())
+        );
+        assert_eq!(
+            parts("/some/path/.hidden"),
+            (".hidden".into(), String::new())
+        );
+        assert_eq!(parts("my.resume.tex"), ("my.resume".into(), "tex".into()));
+        assert_eq!(
+            parts("resume.final.tex"),
+            ("resume.final".into(), "tex".into())
+        );
+        assert_eq!(parts("Makefile"), ("Makefile".into(), String::new()));
+    }
+
     #[test]
     fn test_read_write_file() {
         let mut temp = NamedTempFile::new().unwrap();
@@ -70,4 +448,138 @@ mod tests {
         let read_content = read_file(&path).unwrap();
         assert!(read_content.contains("\\documentclass"));
     }
+
+    #[test]
+    fn test_write_file_replaces_content_without_leftovers() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        fs::write(&path, "old content").unwrap();
+
+        write_file(&path, "new content").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new content");
+        assert!(!temp_dir.path().join("resume.tex.tmp").exists());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_failed_write_keeps_old_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        fs::write(&path, "old content").unwrap();
+        // A directory in the way of the temp file makes the write fail
+        fs::create_dir(temp_dir.path().join("resume.tex.tmp")).unwrap();
+
+        assert!(write_file(&path, "new content").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old content");
+    }
+
+    #[test]
+    fn test_write_read_only_file_fails_with_friendly_message() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        fs::write(&path, "old content").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+
+        assert_eq!(check_writable(&path), Err("File is read-only".to_string()));
+        assert_eq!(
+            write_file(&path, "new content"),
+            Err("File is read-only".to_string())
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old content");
+    }
+
+    #[test]
+    fn test_check_writable_missing_folder() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("missing").join("resume.tex");
+        assert_eq!(
+            check_writable(&path),
+            Err("Folder does not exist".to_string())
+        );
+        assert_eq!(check_writable(&temp_dir.path().join("new.tex")), Ok(()));
+    }
+
+    #[test]
+    fn test_permission_denied_is_not_a_lock() {
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(!is_locked_error(&denied));
+        assert_eq!(
+            describe_write_error(denied),
+            "Permission denied: you can't write to this location"
+        );
+    }
+
+    #[test]
+    fn test_line_ending_for_preserves_file_on_disk() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        fs::write(&path, "a\r\nb\r\n").unwrap();
+
+        assert_eq!(
+            line_ending_for(&path, LineEnding::Preserve),
+            LineEnding::CrLf
+        );
+        assert_eq!(line_ending_for(&path, LineEnding::Lf), LineEnding::Lf);
+        assert_eq!(
+            line_ending_for(&temp_dir.path().join("new.tex"), LineEnding::Preserve),
+            LineEnding::Preserve
+        );
+    }
+
+    #[test]
+    fn test_temp_path_for_appends_tmp() {
+        let path = PathBuf::from("/some/path/resume.tex");
+        assert_eq!(
+            temp_path_for(&path),
+            PathBuf::from("/some/path/resume.tex.tmp")
+        );
+    }
+
+    #[test]
+    fn test_read_file_strips_bom() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        fs::write(&path, b"\xEF\xBB\xBF\\section{Education}").unwrap();
+
+        let (content, encoding) = read_file_with_encoding(&path).unwrap();
+        assert_eq!(content, "\\section{Education}");
+        assert_eq!(encoding, TextEncoding::Utf8Bom);
+    }
+
+    #[test]
+    fn test_read_file_falls_back_to_latin1() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        fs::write(&path, b"Universit\xE9 de Montr\xE9al").unwrap();
+
+        let (content, encoding) = read_file_with_encoding(&path).unwrap();
+        assert_eq!(content, "Université de Montréal");
+        assert_eq!(encoding, TextEncoding::Latin1);
+    }
+
+    #[test]
+    fn test_write_file_keeps_existing_encoding() {
+        let temp_dir = TempDir::new().unwrap();
+        let bom_path = temp_dir.path().join("bom.tex");
+        let latin1_path = temp_dir.path().join("latin1.tex");
+        fs::write(&bom_path, b"\xEF\xBB\xBFold").unwrap();
+        fs::write(&latin1_path, b"Caf\xE9").unwrap();
+
+        write_file(&bom_path, "new é").unwrap();
+        write_file(&latin1_path, "Café crème").unwrap();
+
+        assert_eq!(fs::read(&bom_path).unwrap(), b"\xEF\xBB\xBFnew \xC3\xA9");
+        assert_eq!(fs::read(&latin1_path).unwrap(), b"Caf\xE9 cr\xE8me");
+    }
+
+    #[test]
+    fn test_write_new_file_uses_utf8() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("new.tex");
+        write_file(&path, "é").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), "é".as_bytes());
+    }
 }
@@ -0,0 +1,81 @@
+//! The document class a source declares with `\documentclass`
+
+use super::{read_command_name, read_group, read_optional, strip_comments};
+
+/// A `\documentclass[options]{name}` declaration
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ClassInfo {
+    pub name: String,
+    /// Class options in order, e.g. `["11pt", "a4paper"]`
+    pub options: Vec<String>,
+}
+
+/// The class `source` declares, if any
+///
+/// Comments are ignored, and the options and name may be spread over
+/// several lines.
+pub fn document_class(source: &str) -> Option<ClassInfo> {
+    let source = strip_comments(source);
+    let mut search = 0;
+    while let Some(found) = source[search..].find('\\') {
+        let (command, end) = read_command_name(&source, search + found + 1);
+        search = end;
+        if command != "documentclass" {
+            continue;
+        }
+
+        let (options, end) = match read_optional(&source, end) {
+            Some((options, end)) => (split_options(options), end),
+            None => (Vec::new(), end),
+        };
+        let (name, _) = read_group(&source, end)?;
+        let name = name.trim();
+        return (!name.is_empty()).then(|| ClassInfo {
+            name: name.to_string(),
+            options,
+        });
+    }
+    None
+}
+
+fn split_options(options: &str) -> Vec<String> {
+    options
+        .split(',')
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_with_options() {
+        let class = document_class("\\documentclass[11pt,a4paper]{article}\n").unwrap();
+        assert_eq!(class.name, "article");
+        assert_eq!(class.options, ["11pt", "a4paper"]);
+    }
+
+    #[test]
+    fn test_class_after_comments() {
+        let source = concat!(
+            "% Resume template\n",
+            "% \\documentclass{report}\n",
+            "\n",
+            "\\documentclass[ 11pt , sans,\n",
+            "  ] { moderncv } % banking style below\n",
+            "\\moderncvstyle{banking}\n",
+        );
+        let class = document_class(source).unwrap();
+        assert_eq!(class.name, "moderncv");
+        assert_eq!(class.options, ["11pt", "sans"]);
+    }
+
+    #[test]
+    fn test_no_class() {
+        assert_eq!(document_class("\\section{Education}\n"), None);
+        assert_eq!(document_class("\\documentclass\n"), None);
+    }
+}
@@ -0,0 +1,110 @@
+//! Escaping user-entered text for LaTeX
+//!
+//! Company names with `&` or figures like `40%` break a build when pasted
+//! into a document as is. Escaping is not idempotent: `\&` escapes to
+//! `\textbackslash{}\&`, since the user may really mean a backslash. Use
+//! `latex_unescape` to get the original text back instead.
+
+/// Escapes for LaTeX's special characters
+const ESCAPES: [(char, &str); 10] = [
+    ('&', "\\&"),
+    ('%', "\\%"),
+    ('$', "\\$"),
+    ('#', "\\#"),
+    ('_', "\\_"),
+    ('{', "\\{"),
+    ('}', "\\}"),
+    ('~', "\\textasciitilde{}"),
+    ('^', "\\textasciicircum{}"),
+    ('\\', "\\textbackslash{}"),
+];
+
+/// Escape the characters LaTeX treats specially so `text` prints as typed
+pub fn latex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match ESCAPES.iter().find(|(special, _)| *special == c) {
+            Some((_, escape)) => escaped.push_str(escape),
+            None => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Undo `latex_escape`
+///
+/// The text-mode commands are also recognized without their trailing `{}`.
+/// Other commands are left as they are.
+pub fn latex_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('\\') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match unescape_at(rest) {
+            Some((c, len)) => {
+                unescaped.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// The character escaped at the start of `text` and the escape's length
+fn unescape_at(text: &str) -> Option<(char, usize)> {
+    ESCAPES.iter().find_map(|(c, escape)| {
+        if text.starts_with(escape) {
+            return Some((*c, escape.len()));
+        }
+        // `\textasciitilde` without the `{}`, unless it's part of a longer name
+        let bare = escape.strip_suffix("{}")?;
+        let after = text.strip_prefix(bare)?;
+        (!after.starts_with(|c: char| c.is_ascii_alphabetic())).then_some((*c, bare.len()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_each_special_character() {
+        for (c, escape) in ESCAPES {
+            assert_eq!(latex_escape(&c.to_string()), escape);
+            assert_eq!(latex_unescape(escape), c.to_string());
+        }
+    }
+
+    #[test]
+    fn test_escape_mixed_string() {
+        let text = "R&D: grew 40% to $2M (#1), see ~/notes_v2 {draft} x^2 C:\\cv";
+        let escaped = latex_escape(text);
+        assert_eq!(
+            escaped,
+            concat!(
+                "R\\&D: grew 40\\% to \\$2M (\\#1), see \\textasciitilde{}/notes\\_v2 ",
+                "\\{draft\\} x\\textasciicircum{}2 C:\\textbackslash{}cv"
+            )
+        );
+        assert_eq!(latex_unescape(&escaped), text);
+    }
+
+    #[test]
+    fn test_escape_is_not_idempotent() {
+        assert_eq!(latex_escape("\\&"), "\\textbackslash{}\\&");
+        assert_eq!(latex_unescape(&latex_escape(&latex_escape("&"))), "\\&");
+    }
+
+    #[test]
+    fn test_unescape_leaves_other_commands() {
+        assert_eq!(latex_unescape("\\textbf{Bold} \\\\"), "\\textbf{Bold} \\\\");
+        assert_eq!(latex_unescape("\\textasciitilde x"), "~ x");
+        assert_eq!(latex_unescape("\\textbackslashes"), "\\textbackslashes");
+    }
+}
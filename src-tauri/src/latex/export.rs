@@ -0,0 +1,440 @@
+//! Exporting a resume's text for job sites that only take plain text
+//!
+//! The document body is converted command by command: formatting commands
+//! keep their text, layout commands are dropped and unknown macros keep
+//! their arguments, so custom resume macros still produce readable text.
+
+use super::{
+    document_body, read_command_name, read_group, read_optional, skip_optional, strip_comments,
+};
+
+/// Output format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    PlainText,
+    Markdown,
+}
+
+/// Environments whose arguments are layout rather than text, and how many there are
+const ENVIRONMENT_ARGS: [(&str, usize); 6] = [
+    ("tabular", 1),
+    ("tabular*", 2),
+    ("tabularx", 2),
+    ("minipage", 1),
+    ("multicols", 1),
+    ("wrapfigure", 2),
+];
+
+/// Commands dropped together with their arguments, and how many there are
+const DROPPED_COMMANDS: [(&str, usize); 17] = [
+    ("vspace", 1),
+    ("label", 1),
+    ("includegraphics", 1),
+    ("setlength", 2),
+    ("addtolength", 2),
+    ("pagestyle", 1),
+    ("thispagestyle", 1),
+    ("color", 1),
+    ("definecolor", 3),
+    ("usepackage", 1),
+    ("fontsize", 2),
+    ("rule", 2),
+    ("phantom", 1),
+    ("vphantom", 1),
+    ("hypersetup", 1),
+    ("setcounter", 2),
+    ("titlespacing", 4),
+];
+
+/// Convert a LaTeX resume to plain text
+pub fn export_plaintext(source: &str) -> String {
+    export(source, ExportFormat::PlainText)
+}
+
+/// Convert a LaTeX resume to Markdown
+pub fn export_markdown(source: &str) -> String {
+    export(source, ExportFormat::Markdown)
+}
+
+/// Convert the document body of `source`, dropping comments and the preamble
+pub fn export(source: &str, format: ExportFormat) -> String {
+    let source = strip_comments(source);
+    let text = Converter { format }.convert(document_body(&source));
+    tidy(&text)
+}
+
+struct Converter {
+    format: ExportFormat,
+}
+
+impl Converter {
+    fn markdown(&self) -> bool {
+        self.format == ExportFormat::Markdown
+    }
+
+    fn convert(&self, source: &str) -> String {
+        let mut out = String::new();
+        let mut i = 0;
+        while let Some(c) = source[i..].chars().next() {
+            let rest = &source[i..];
+            i += match c {
+                '\\' => self.command(source, i + 1, &mut out) - i,
+                '{' => match read_group(source, i) {
+                    Some((inner, end)) => {
+                        out.push_str(&self.convert(inner));
+                        end - i
+                    }
+                    None => 1,
+                },
+                '}' | '$' => 1,
+                '~' => {
+                    out.push(' ');
+                    1
+                }
+                '&' => {
+                    out.push_str(" | ");
+                    1
+                }
+                '\n' => {
+                    // A blank line ends a paragraph; a single line break is a space
+                    let blank = rest[1..]
+                        .find(|c: char| !c.is_whitespace())
+                        .map_or(&rest[1..], |end| &rest[1..1 + end]);
+                    out.push_str(if blank.contains('\n') { "\n\n" } else { " " });
+                    1 + blank.len()
+                }
+                _ if rest.starts_with("---") => {
+                    out.push('—');
+                    3
+                }
+                _ if rest.starts_with("--") => {
+                    out.push('–');
+                    2
+                }
+                _ if rest.starts_with("``") || rest.starts_with("''") => {
+                    out.push('"');
+                    2
+                }
+                _ => {
+                    out.push(c);
+                    c.len_utf8()
+                }
+            };
+        }
+        out
+    }
+
+    /// Convert the command whose name starts at `start`, returning the index after it
+    fn command(&self, source: &str, start: usize, out: &mut String) -> usize {
+        let (name, mut end) = read_command_name(source, start);
+        if source[end..].starts_with('*') {
+            end += 1;
+        }
+
+        match name {
+            "\\" => {
+                line_break(out);
+                skip_optional(source, end)
+            }
+            "newline" | "linebreak" | "par" => {
+                line_break(out);
+                end
+            }
+            "&" | "%" | "$" | "#" | "_" | "{" | "}" => {
+                out.push_str(name);
+                end
+            }
+            " " | "," | ";" | ":" | "quad" | "qquad" | "enspace" => {
+                out.push(' ');
+                end
+            }
+            "hfill" => {
+                out.push_str(" | ");
+                end
+            }
+            "hspace" => {
+                out.push(' ');
+                self.skip_args(source, end, 1)
+            }
+            "ldots" | "dots" | "textellipsis" => {
+                out.push_str("...");
+                end
+            }
+            "textbar" => {
+                out.push('|');
+                end
+            }
+            "textbullet" | "cdot" => {
+                out.push('•');
+                end
+            }
+            "textendash" => {
+                out.push('–');
+                end
+            }
+            "textemdash" => {
+                out.push('—');
+                end
+            }
+            "textasciitilde" => {
+                out.push('~');
+                end
+            }
+            "textbackslash" => {
+                out.push('\\');
+                end
+            }
+            "LaTeX" | "TeX" => {
+                out.push_str(name);
+                end
+            }
+            "textbf" => self.wrap(source, end, "**", out),
+            "textit" | "emph" | "textsl" => self.wrap(source, end, "*", out),
+            "MakeUppercase" => match read_group(source, end) {
+                Some((inner, after)) => {
+                    out.push_str(&self.convert(inner).to_uppercase());
+                    after
+                }
+                None => end,
+            },
+            "section" => self.heading(source, end, 1, out),
+            "subsection" => self.heading(source, end, 2, out),
+            "subsubsection" => self.heading(source, end, 3, out),
+            "item" => self.item(source, end, out),
+            "href" => self.href(source, end, out),
+            "url" => match read_group(source, end) {
+                Some((url, after)) => {
+                    out.push_str(url);
+                    after
+                }
+                None => end,
+            },
+            "textcolor" => self.skip_args(source, end, 1),
+            "begin" | "end" => self.environment(source, end, name == "begin", out),
+            "newcommand" | "renewcommand" | "providecommand" => {
+                let after_name = read_group(source, end).map_or(end, |(_, after)| after);
+                let after_opts = skip_optional(source, skip_optional(source, after_name));
+                read_group(source, after_opts).map_or(after_opts, |(_, after)| after)
+            }
+            _ => match DROPPED_COMMANDS
+                .iter()
+                .find(|(dropped, _)| *dropped == name)
+            {
+                Some(&(_, count)) => self.skip_args(source, end, count),
+                None => self.unknown(source, end, out),
+            },
+        }
+    }
+
+    /// Skip an optional argument and `count` required ones
+    fn skip_args(&self, source: &str, start: usize, count: usize) -> usize {
+        (0..count).fold(skip_optional(source, start), |pos, _| {
+            read_group(source, pos).map_or(pos, |(_, end)| end)
+        })
+    }
+
+    /// Emit the argument, wrapped in `marker` when exporting Markdown
+    fn wrap(&self, source: &str, start: usize, marker: &str, out: &mut String) -> usize {
+        let Some((inner, end)) = read_group(source, start) else {
+            return start;
+        };
+        let text = self.convert(inner);
+        let text = text.trim();
+        if self.markdown() && !text.is_empty() {
+            out.push_str(&format!("{}{}{}", marker, text, marker));
+        } else {
+            out.push_str(text);
+        }
+        end
+    }
+
+    fn heading(&self, source: &str, start: usize, level: usize, out: &mut String) -> usize {
+        let start = skip_optional(source, start);
+        let Some((inner, end)) = read_group(source, start) else {
+            return start;
+        };
+        let title = self.convert(inner);
+        let title = title.trim();
+        out.push_str("\n\n");
+        if self.markdown() {
+            out.push_str(&format!("{} {}", "#".repeat(level), title));
+        } else if level == 1 {
+            out.push_str(&title.to_uppercase());
+        } else {
+            out.push_str(title);
+        }
+        out.push_str("\n\n");
+        end
+    }
+
+    fn item(&self, source: &str, start: usize, out: &mut String) -> usize {
+        line_break(out);
+        out.push_str(if self.markdown() { "- " } else { "• " });
+        match read_optional(source, start) {
+            Some((label, end)) => {
+                let label = self.convert(label);
+                let label = label.trim();
+                if self.markdown() && !label.is_empty() {
+                    out.push_str(&format!("**{}** ", label));
+                } else {
+                    out.push_str(&format!("{} ", label));
+                }
+                end
+            }
+            None => start,
+        }
+    }
+
+    fn href(&self, source: &str, start: usize, out: &mut String) -> usize {
+        let Some((url, after_url)) = read_group(source, start) else {
+            return start;
+        };
+        let Some((text, end)) = read_group(source, after_url) else {
+            out.push_str(url);
+            return after_url;
+        };
+        let text = self.convert(text);
+        let text = text.trim();
+        if self.markdown() {
+            out.push_str(&format!("[{}]({})", text, url));
+        } else if text == url || url.strip_prefix("mailto:") == Some(text) {
+            out.push_str(text);
+        } else {
+            out.push_str(&format!("{} ({})", text, url));
+        }
+        end
+    }
+
+    fn environment(&self, source: &str, start: usize, begin: bool, out: &mut String) -> usize {
+        let Some((env, end)) = read_group(source, start) else {
+            return start;
+        };
+        line_break(out);
+        if !begin {
+            return end;
+        }
+        let count = ENVIRONMENT_ARGS
+            .iter()
+            .find(|(name, _)| *name == env)
+            .map_or(0, |&(_, count)| count);
+        self.skip_args(source, end, count)
+    }
+
+    /// Keep the text of an unknown command's arguments, separated by ` | `
+    fn unknown(&self, source: &str, start: usize, out: &mut String) -> usize {
+        let mut end = skip_optional(source, start);
+        let mut args = Vec::new();
+        while let Some((inner, after)) = read_group(source, end) {
+            let text = self.convert(inner);
+            let text = text.trim().to_string();
+            if !text.is_empty() {
+                args.push(text);
+            }
+            end = after;
+        }
+        out.push_str(&args.join(" | "));
+        end
+    }
+}
+
+/// Start a new line unless the output is already at the start of one
+fn line_break(out: &mut String) {
+    if !out.trim_end_matches([' ', '\t']).ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Collapse spaces, trim lines and allow at most one blank line in a row
+fn tidy(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() && lines.last().map_or(true, |last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESUME: &str = r"\documentclass{article}
+\usepackage{hyperref}
+\newcommand{\resumeItem}[1]{\item #1}
+\begin{document}
+% Header
+\begin{center}
+  \textbf{\Large Jane Doe} \\
+  \href{mailto:jane@example.com}{jane@example.com} $|$ \href{https://github.com/jane}{GitHub}
+\end{center}
+
+\section{Experience}
+\textbf{Acme Corp} \hfill Jan 2020 -- Present \\
+\textit{Software Engineer}
+\begin{itemize}
+  \item Cut build times by 40\% using caching % internal metric
+  \item Led R\&D on the {\bfseries \emph{new}} parser
+\end{itemize}
+
+\subsection*{Skills}
+Rust, C++, \LaTeX
+\end{document}
+";
+
+    #[test]
+    fn test_export_plaintext() {
+        let expected = "\
+Jane Doe
+jane@example.com | GitHub (https://github.com/jane)
+
+EXPERIENCE
+
+Acme Corp | Jan 2020 – Present
+Software Engineer
+• Cut build times by 40% using caching
+• Led R&D on the new parser
+
+Skills
+
+Rust, C++, LaTeX";
+        assert_eq!(export_plaintext(RESUME), expected);
+    }
+
+    #[test]
+    fn test_export_markdown() {
+        let expected = "\
+**Jane Doe**
+[jane@example.com](mailto:jane@example.com) | [GitHub](https://github.com/jane)
+
+# Experience
+
+**Acme Corp** | Jan 2020 – Present
+*Software Engineer*
+- Cut build times by 40% using caching
+- Led R&D on the *new* parser
+
+## Skills
+
+Rust, C++, LaTeX";
+        assert_eq!(export_markdown(RESUME), expected);
+    }
+
+    #[test]
+    fn test_unknown_macros_keep_arguments() {
+        let source = r"\resumeSubheading{Acme}{2020}{Engineer}{Remote}";
+        assert_eq!(export_plaintext(source), "Acme | 2020 | Engineer | Remote");
+    }
+
+    #[test]
+    fn test_description_item_labels() {
+        let source = r"\begin{description}\item[Languages] Rust, Go\end{description}";
+        assert_eq!(export_plaintext(source), "• Languages Rust, Go");
+        assert_eq!(export_markdown(source), "- **Languages** Rust, Go");
+    }
+}
@@ -0,0 +1,70 @@
+//! Whitespace clean-up applied when saving
+
+use super::verbatim_lines;
+
+/// Strip trailing spaces and tabs from each line and end with exactly one newline
+///
+/// Verbatim environments are left as they are, since their whitespace is
+/// printed. Empty content stays empty.
+pub fn format_whitespace(source: &str) -> String {
+    let verbatim = verbatim_lines(source);
+    let mut out = String::with_capacity(source.len());
+    let mut last_ending = "\n";
+
+    for (index, line) in source.split_inclusive('\n').enumerate() {
+        if verbatim.get(index).copied().unwrap_or(false) {
+            out.push_str(line);
+            continue;
+        }
+        let (text, ending) = if let Some(text) = line.strip_suffix("\r\n") {
+            (text, "\r\n")
+        } else if let Some(text) = line.strip_suffix('\n') {
+            (text, "\n")
+        } else {
+            (line, "")
+        };
+        out.push_str(text.trim_end_matches([' ', '\t']));
+        out.push_str(ending);
+        if !ending.is_empty() {
+            last_ending = ending;
+        }
+    }
+
+    let content_end = out.trim_end_matches(['\r', '\n']).len();
+    if content_end == 0 {
+        return String::new();
+    }
+    out.truncate(content_end);
+    out.push_str(last_ending);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trailing_whitespace_removed() {
+        assert_eq!(
+            format_whitespace("\\section{Skills}  \nRust,\tGo\t \n"),
+            "\\section{Skills}\nRust,\tGo\n"
+        );
+    }
+
+    #[test]
+    fn test_single_final_newline() {
+        assert_eq!(format_whitespace("a"), "a\n");
+        assert_eq!(format_whitespace("a\n\n  \n\n"), "a\n");
+        assert_eq!(format_whitespace("a \r\nb\r\n\r\n"), "a\r\nb\r\n");
+        assert_eq!(format_whitespace(""), "");
+    }
+
+    #[test]
+    fn test_verbatim_whitespace_kept() {
+        let source = "\\begin{lstlisting}  \n  code  \n\t\n\\end{lstlisting}  \n";
+        assert_eq!(
+            format_whitespace(source),
+            "\\begin{lstlisting}\n  code  \n\t\n\\end{lstlisting}\n"
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! The files a document pulls in with `\input` and `\include`
+//!
+//! TeX runs from the main file's folder, so every include is resolved
+//! relative to it, whichever file the command is in.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{read_command_name, read_group, strip_comments};
+
+/// Commands whose argument names a source file
+const INCLUDE_COMMANDS: [&str; 2] = ["input", "include"];
+
+/// Inputs that come from the TeX distribution rather than the project
+const DISTRIBUTION_INPUTS: [&str; 1] = ["glyphtounicode"];
+
+/// An include found in a source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Include {
+    /// The name as written, e.g. `sections/experience`
+    pub name: String,
+    /// 1-based line of the command
+    pub line: u32,
+}
+
+/// The `\input{...}` and `\include{...}` targets of `source`, in order
+///
+/// Names built from macro parameters (`\input{#1}`) are skipped.
+pub fn find_includes(source: &str) -> Vec<Include> {
+    let source = strip_comments(source);
+    let mut includes = Vec::new();
+    for (index, _) in source.match_indices('\\') {
+        let (name, after) = read_command_name(&source, index + 1);
+        if !INCLUDE_COMMANDS.contains(&name) {
+            continue;
+        }
+        let Some((target, _)) = read_group(&source, after) else {
+            continue;
+        };
+        let target = target.trim();
+        if target.is_empty() || target.contains('#') {
+            continue;
+        }
+        includes.push(Include {
+            name: target.to_string(),
+            line: source[..index].matches('\n').count() as u32 + 1,
+        });
+    }
+    includes
+}
+
+/// Why the include tree couldn't be followed
+#[derive(Debug, Clone, PartialEq)]
+pub enum IncludeError {
+    /// Files that include each other, starting and ending with the same file
+    Cycle(Vec<PathBuf>),
+    /// An include that doesn't resolve to a file
+    Missing {
+        name: String,
+        from: PathBuf,
+        line: u32,
+    },
+    Read(String),
+}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IncludeError::Cycle(files) => {
+                let names: Vec<String> = files.iter().map(|p| display_name(p)).collect();
+                write!(f, "Circular include: {}", names.join(" -> "))
+            }
+            IncludeError::Missing { name, from, line } => write!(
+                f,
+                "Included file not found: {} (from {}, line {})",
+                name,
+                display_name(from),
+                line
+            ),
+            IncludeError::Read(message) => f.write_str(message),
+        }
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+/// Follow the includes of the main file at `path`
+///
+/// Returns every file in the tree once, in the order TeX first reads
+/// them, starting with `path`.
+pub fn include_tree(path: &Path) -> Result<Vec<PathBuf>, IncludeError> {
+    let root = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let mut files = Vec::new();
+    let mut stack = Vec::new();
+    visit(path, &root, &mut stack, &mut files)?;
+    Ok(files)
+}
+
+fn visit(
+    path: &Path,
+    root: &Path,
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), IncludeError> {
+    let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if let Some(start) = stack.iter().position(|p| *p == key) {
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(key);
+        return Err(IncludeError::Cycle(cycle));
+    }
+    if files.contains(&path.to_path_buf()) {
+        // Included twice without a cycle, e.g. a shared header
+        return Ok(());
+    }
+
+    let source = fs::read_to_string(path)
+        .map_err(|e| IncludeError::Read(format!("Failed to read {}: {}", path.display(), e)))?;
+    files.push(path.to_path_buf());
+    stack.push(key);
+    for include in find_includes(&source) {
+        if DISTRIBUTION_INPUTS.contains(&include.name.as_str()) {
+            continue;
+        }
+        let Some(target) = resolve(root, &include.name) else {
+            return Err(IncludeError::Missing {
+                name: include.name,
+                from: path.to_path_buf(),
+                line: include.line,
+            });
+        };
+        visit(&target, root, stack, files)?;
+    }
+    stack.pop();
+    Ok(())
+}
+
+/// The file an include names, trying `name.tex` before `name` as TeX does
+fn resolve(root: &Path, name: &str) -> Option<PathBuf> {
+    [root.join(format!("{}.tex", name)), root.join(name)]
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+}
+
+/// Check the include tree of the main file at `path` for cycles and
+/// missing files, returning the files it reads
+pub fn check_includes(path: &Path) -> Result<Vec<PathBuf>, String> {
+    include_tree(path).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_find_includes() {
+        let source = "\\input{header}\n% \\input{old}\n\\include{sections/work}\n\
+                      \\newcommand{\\part}[1]{\\input{#1}}\n\\inputencoding{utf8}";
+        let includes = find_includes(source);
+        assert_eq!(
+            includes,
+            [
+                Include {
+                    name: "header".to_string(),
+                    line: 1
+                },
+                Include {
+                    name: "sections/work".to_string(),
+                    line: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_include_tree_in_reading_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let main = write(
+            dir,
+            "resume.tex",
+            "\\input{glyphtounicode}\n\\input{header}\n\\input{sections/work.tex}\n\\input{header}",
+        );
+        write(dir, "header.tex", "\\name{Jane}");
+        // Includes resolve from the main file's folder, not the includer's
+        write(dir, "sections/work.tex", "\\input{sections/jobs}");
+        write(dir, "sections/jobs.tex", "Acme");
+
+        let files = check_includes(&main).unwrap();
+        let names: Vec<String> = files
+            .iter()
+            .map(|p| {
+                p.strip_prefix(dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "resume.tex",
+                "header.tex",
+                "sections/work.tex",
+                "sections/jobs.tex"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_two_file_cycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let main = write(temp_dir.path(), "resume.tex", "\\input{a}");
+        write(temp_dir.path(), "a.tex", "\\input{b}");
+        write(temp_dir.path(), "b.tex", "\\input{a}");
+
+        let err = include_tree(&main).unwrap_err();
+        assert!(matches!(&err, IncludeError::Cycle(files) if files.len() == 3));
+        assert_eq!(err.to_string(), "Circular include: a.tex -> b.tex -> a.tex");
+    }
+
+    #[test]
+    fn test_self_include() {
+        let temp_dir = TempDir::new().unwrap();
+        let main = write(temp_dir.path(), "resume.tex", "Hi\n\\input{resume}");
+        let err = check_includes(&main).unwrap_err();
+        assert_eq!(err, "Circular include: resume.tex -> resume.tex");
+    }
+
+    #[test]
+    fn test_missing_include() {
+        let temp_dir = TempDir::new().unwrap();
+        let main = write(
+            temp_dir.path(),
+            "resume.tex",
+            "\\input{header}\n\n\\include{skills}",
+        );
+        write(temp_dir.path(), "header.tex", "");
+
+        let err = check_includes(&main).unwrap_err();
+        assert_eq!(
+            err,
+            "Included file not found: skills (from resume.tex, line 3)"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Matching a resume against a job description's keywords
+//!
+//! Applicant tracking systems rank resumes by how many of a posting's terms
+//! they contain. The resume is compared as the plain text a job site would
+//! see, so terms only in commands or comments don't count.
+
+use std::collections::HashSet;
+
+use super::export::export_plaintext;
+
+/// Words too common in job postings to be worth matching
+const STOPWORDS: [&str; 96] = [
+    "a",
+    "about",
+    "across",
+    "all",
+    "also",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "at",
+    "be",
+    "been",
+    "both",
+    "but",
+    "by",
+    "can",
+    "candidate",
+    "do",
+    "each",
+    "etc",
+    "experience",
+    "for",
+    "from",
+    "good",
+    "has",
+    "have",
+    "help",
+    "how",
+    "if",
+    "in",
+    "including",
+    "into",
+    "is",
+    "it",
+    "its",
+    "join",
+    "just",
+    "like",
+    "looking",
+    "may",
+    "more",
+    "most",
+    "must",
+    "new",
+    "not",
+    "of",
+    "on",
+    "or",
+    "other",
+    "our",
+    "out",
+    "over",
+    "plus",
+    "preferred",
+    "required",
+    "role",
+    "should",
+    "so",
+    "some",
+    "strong",
+    "such",
+    "team",
+    "than",
+    "that",
+    "the",
+    "their",
+    "them",
+    "then",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "to",
+    "up",
+    "us",
+    "use",
+    "using",
+    "very",
+    "want",
+    "we",
+    "well",
+    "what",
+    "when",
+    "where",
+    "which",
+    "who",
+    "will",
+    "with",
+    "within",
+    "work",
+    "years",
+    "you",
+    "your",
+];
+
+/// Which of a job description's terms a resume contains
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct MatchReport {
+    /// Terms found in the resume, in the order the posting uses them
+    pub present: Vec<String>,
+    /// Terms the resume lacks, in the order the posting uses them
+    pub missing: Vec<String>,
+    /// Share of the terms present, from 0 to 1; 0 if the posting has none
+    pub score: f32,
+}
+
+/// Compare the LaTeX source of a resume with a job description
+pub fn keyword_match(resume_source: &str, job_description: &str) -> MatchReport {
+    let resume: HashSet<String> = terms(&export_plaintext(resume_source)).collect();
+
+    let mut seen = HashSet::new();
+    let (present, missing): (Vec<String>, Vec<String>) = terms(job_description)
+        .filter(|term| seen.insert(term.clone()))
+        .partition(|term| resume.contains(term));
+
+    let total = present.len() + missing.len();
+    let score = if total == 0 {
+        0.0
+    } else {
+        present.len() as f32 / total as f32
+    };
+    MatchReport {
+        present,
+        missing,
+        score,
+    }
+}
+
+/// The significant lowercase terms in `text`
+///
+/// `+`, `#` and inner dots are part of a term, so `C++`, `C#` and `Node.js`
+/// survive.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || matches!(c, '+' | '#' | '.')))
+        .map(|word| word.trim_matches('.').to_lowercase())
+        .filter(|word| word.chars().count() > 1)
+        .filter(|word| word.chars().any(char::is_alphabetic))
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESUME: &str = r"\documentclass{article}
+\begin{document}
+\section{Experience}
+\textbf{Backend Engineer}, Acme Corp. \\
+Built REST APIs in \textit{Rust} and Python; deployed on AWS.
+% Kubernetes would go here once I learn it
+\section{Skills}
+C++, SQL, Node.js
+\end{document}
+";
+
+    const JOB: &str = "We are looking for a backend engineer with 3+ years of experience \
+        in Rust or C++. You will design REST APIs, run services on AWS and Kubernetes, \
+        and write SQL. Node.js a plus.";
+
+    #[test]
+    fn test_keyword_match() {
+        let report = keyword_match(RESUME, JOB);
+        assert_eq!(
+            report.present,
+            ["backend", "engineer", "rust", "c++", "rest", "apis", "aws", "sql", "node.js"]
+        );
+        // Comments don't count
+        assert_eq!(
+            report.missing,
+            ["design", "run", "services", "kubernetes", "write"]
+        );
+        assert!((report.score - 9.0 / 14.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_terms_are_normalized() {
+        let terms: Vec<String> = terms("The C# and TypeScript (typescript) roles, 2024.").collect();
+        assert_eq!(terms, ["c#", "typescript", "typescript", "roles"]);
+    }
+
+    #[test]
+    fn test_empty_job_description_scores_zero() {
+        let report = keyword_match(RESUME, "and the with");
+        assert!(report.present.is_empty() && report.missing.is_empty());
+        assert_eq!(report.score, 0.0);
+    }
+}
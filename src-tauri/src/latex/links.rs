@@ -0,0 +1,202 @@
+//! Links in a resume: `\href{url}{text}` and `\url{url}`
+//!
+//! Every link is checked for a well-formed URL. Checking that it actually
+//! loads needs the network, so it only happens when asked for, using the
+//! system's `curl`.
+
+use std::process::{Command, Stdio};
+
+use super::{read_command_name, read_group, strip_comments};
+
+/// Commands whose first argument is a URL
+const LINK_COMMANDS: [&str; 2] = ["href", "url"];
+
+/// Schemes a resume link is expected to use
+const LINK_SCHEMES: [&str; 4] = ["http", "https", "mailto", "tel"];
+
+/// Seconds to wait for a server before calling a link unreachable
+const REQUEST_TIMEOUT_SECS: u32 = 5;
+
+/// A link found in the source and what is known about it
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct LinkStatus {
+    pub url: String,
+    /// 1-based line of the command
+    pub line: u32,
+    /// Whether the URL is well formed, with a scheme and (for web links) a host
+    pub valid: bool,
+    /// Whether the link loaded; `None` when it wasn't checked
+    pub reachable: Option<bool>,
+}
+
+/// The URLs of the `\href` and `\url` commands in `source`, in order
+///
+/// TeX escapes such as `\#` and `\%` are undone, and URLs built from macro
+/// parameters (`\href{#1}`) are skipped.
+pub fn find_links(source: &str) -> Vec<LinkStatus> {
+    let source = strip_comments(source);
+    let mut links = Vec::new();
+    for (index, _) in source.match_indices('\\') {
+        let (name, after) = read_command_name(&source, index + 1);
+        if !LINK_COMMANDS.contains(&name) {
+            continue;
+        }
+        let Some((target, _)) = read_group(&source, after) else {
+            continue;
+        };
+        let url = unescape_url(target.trim());
+        if url.is_empty() || target.contains("#1") {
+            continue;
+        }
+        links.push(LinkStatus {
+            valid: is_valid_url(&url),
+            url,
+            line: source[..index].matches('\n').count() as u32 + 1,
+            reachable: None,
+        });
+    }
+    links
+}
+
+/// Undo the escapes `\href` needs for URL characters special to TeX
+fn unescape_url(url: &str) -> String {
+    let mut unescaped = String::with_capacity(url.len());
+    let mut chars = url.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next)) if "#%&_~$".contains(next) => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Whether `url` is well formed and uses one of the [`LINK_SCHEMES`]
+pub fn is_valid_url(url: &str) -> bool {
+    if url.chars().any(char::is_whitespace) {
+        return false;
+    }
+    let Ok(parsed) = tauri::Url::parse(url) else {
+        return false;
+    };
+    if !LINK_SCHEMES.contains(&parsed.scheme()) {
+        return false;
+    }
+    match parsed.scheme() {
+        "http" | "https" => parsed.host_str().is_some_and(|host| host.contains('.')),
+        _ => !parsed.path().is_empty(),
+    }
+}
+
+/// Whether a web link loads, following redirects
+///
+/// Servers that refuse `HEAD` requests (405) are up, so they count as
+/// reachable. Links other than http(s) aren't checked and return `None`.
+pub fn check_reachable(url: &str) -> Option<bool> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return None;
+    }
+    let null_device = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    let output = Command::new("curl")
+        .args(["--silent", "--head", "--location"])
+        .args(["--max-time", &REQUEST_TIMEOUT_SECS.to_string()])
+        .args(["--output", null_device, "--write-out", "%{http_code}"])
+        .arg(url)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    let status: u16 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    Some((200..400).contains(&status) || status == 405)
+}
+
+/// The links in `source`, with reachability filled in when `online` is set
+///
+/// Malformed links aren't requested and stay unreachable.
+pub fn check_links(source: &str, online: bool) -> Vec<LinkStatus> {
+    let mut links = find_links(source);
+    if online {
+        for link in &mut links {
+            link.reachable = if link.valid {
+                check_reachable(&link.url)
+            } else {
+                Some(false)
+            };
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_links_with_lines() {
+        let source = "\\documentclass{article}\n\
+                      \\begin{document}\n\
+                      \\href{https://github.com/jane}{GitHub} |\n\
+                      \\href{mailto:jane@example.com}{jane@example.com}\n\
+                      % \\url{https://old.example.com}\n\
+                      Portfolio: \\url{https://jane.dev/work\\#projects}\n\
+                      \\end{document}\n";
+
+        let links = find_links(source);
+        let found: Vec<(&str, u32)> = links.iter().map(|l| (l.url.as_str(), l.line)).collect();
+        assert_eq!(
+            found,
+            [
+                ("https://github.com/jane", 3),
+                ("mailto:jane@example.com", 4),
+                ("https://jane.dev/work#projects", 6),
+            ]
+        );
+        assert!(links.iter().all(|l| l.valid && l.reachable.is_none()));
+    }
+
+    #[test]
+    fn test_find_links_skips_macro_parameters() {
+        let source = "\\newcommand{\\site}[1]{\\url{#1}}\n\\href{}{empty}";
+        assert!(find_links(source).is_empty());
+    }
+
+    #[test]
+    fn test_is_valid_url() {
+        for url in [
+            "https://www.linkedin.com/in/jane-doe",
+            "http://example.com/a?b=1&c=2",
+            "mailto:jane@example.com",
+            "tel:+15551234567",
+        ] {
+            assert!(is_valid_url(url), "{}", url);
+        }
+        for url in [
+            "www.example.com",
+            "https://",
+            "https://localhost",
+            "ftp://example.com/file",
+            "https://exa mple.com",
+            "github.com/jane",
+        ] {
+            assert!(!is_valid_url(url), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_offline_check_marks_invalid_links() {
+        let links = check_links("\\href{linkedin.com/in/jane}{LinkedIn}", false);
+        assert_eq!(links.len(), 1);
+        assert!(!links[0].valid);
+        assert_eq!(links[0].reachable, None);
+    }
+
+    #[test]
+    fn test_only_web_links_are_requested() {
+        assert_eq!(check_reachable("mailto:jane@example.com"), None);
+    }
+}
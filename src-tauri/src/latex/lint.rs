@@ -0,0 +1,236 @@
+//! Brace and environment balance checks that run without compiling
+//!
+//! Unbalanced `{}` and mismatched `\begin`/`\end` make the engine fail
+//! far from the actual mistake, so they are reported at the offending line.
+
+use super::{read_command_name, read_group, strip_line_comment, VERBATIM_ENVIRONMENTS};
+use crate::compiler::Diagnostic;
+
+/// Where something was opened, 1-based
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    line: u32,
+    column: u32,
+}
+
+#[derive(Default)]
+struct Linter {
+    braces: Vec<Position>,
+    environments: Vec<(String, Position)>,
+    /// Verbatim environment whose end is being looked for
+    verbatim: Option<(String, Position)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Report unbalanced braces and unmatched `\begin`/`\end` in `source`
+///
+/// Braces in comments and verbatim environments are ignored.
+pub fn lint_structure(source: &str) -> Vec<Diagnostic> {
+    let mut linter = Linter::default();
+    for (index, line) in source.lines().enumerate() {
+        linter.line(line, index as u32 + 1);
+    }
+    linter.finish()
+}
+
+/// Index after `\verb|...|` whose delimiter is at (or, starred, after) `start`
+fn skip_inline_verbatim(code: &str, start: usize) -> usize {
+    let start = if code[start..].starts_with('*') {
+        start + 1
+    } else {
+        start
+    };
+    let Some(delimiter) = code[start..].chars().next() else {
+        return start;
+    };
+    let content = start + delimiter.len_utf8();
+    code[content..]
+        .find(delimiter)
+        .map_or(code.len(), |i| content + i + delimiter.len_utf8())
+}
+
+fn error_at(message: String, position: Position) -> Diagnostic {
+    Diagnostic::error(message)
+        .with_line(position.line)
+        .with_column(position.column)
+}
+
+impl Linter {
+    fn line(&mut self, line: &str, number: u32) {
+        let mut pos = 0;
+        if let Some((env, _)) = &self.verbatim {
+            let end = format!("\\end{{{}}}", env);
+            match line.find(&end) {
+                Some(index) => {
+                    pos = index + end.len();
+                    self.verbatim = None;
+                }
+                None => return,
+            }
+        }
+
+        let code = strip_line_comment(line);
+        while let Some(c) = code[pos..].chars().next() {
+            let position = Position {
+                line: number,
+                column: code[..pos].chars().count() as u32 + 1,
+            };
+            pos += c.len_utf8();
+            match c {
+                '{' => self.braces.push(position),
+                '}' => self.close_brace(position),
+                '\\' => {
+                    let (name, end) = read_command_name(code, pos);
+                    pos = end;
+                    if name == "verb" {
+                        pos = skip_inline_verbatim(code, pos);
+                        continue;
+                    }
+                    if name != "begin" && name != "end" {
+                        continue;
+                    }
+                    let Some((env, after)) = read_group(code, pos) else {
+                        continue;
+                    };
+                    pos = after;
+                    if name == "begin" {
+                        self.begin(env, position);
+                        if self.verbatim.is_some() {
+                            // The rest of the line is verbatim too
+                            return self.verbatim_rest(&line[pos..], number);
+                        }
+                    } else {
+                        self.end(env, position);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Look for the end of a verbatim environment later on the line it starts on
+    fn verbatim_rest(&mut self, rest: &str, number: u32) {
+        let Some((env, _)) = &self.verbatim else {
+            return;
+        };
+        let end = format!("\\end{{{}}}", env);
+        if let Some(index) = rest.find(&end) {
+            self.verbatim = None;
+            self.line(&rest[index + end.len()..], number);
+        }
+    }
+
+    fn close_brace(&mut self, position: Position) {
+        if self.braces.pop().is_none() {
+            let message = "Unmatched closing brace '}'".to_string();
+            self.diagnostics.push(error_at(message, position));
+        }
+    }
+
+    fn begin(&mut self, env: &str, position: Position) {
+        if VERBATIM_ENVIRONMENTS.contains(&env) {
+            self.verbatim = Some((env.to_string(), position));
+        } else {
+            self.environments.push((env.to_string(), position));
+        }
+    }
+
+    fn end(&mut self, env: &str, position: Position) {
+        match self.environments.iter().rposition(|(open, _)| open == env) {
+            Some(index) => {
+                // Anything opened after the matching \begin was never closed
+                for (open, opened_at) in self.environments.drain(index..).skip(1) {
+                    self.diagnostics.push(error_at(
+                        format!("\\begin{{{}}} ended by \\end{{{}}}", open, env),
+                        opened_at,
+                    ));
+                }
+            }
+            None => self.diagnostics.push(error_at(
+                format!("\\end{{{}}} without matching \\begin{{{}}}", env, env),
+                position,
+            )),
+        }
+    }
+
+    fn finish(mut self) -> Vec<Diagnostic> {
+        for position in self.braces.drain(..) {
+            self.diagnostics
+                .push(error_at("Unclosed brace '{'".to_string(), position));
+        }
+        let verbatim = self.verbatim.take();
+        for (env, position) in self.environments.drain(..).chain(verbatim) {
+            self.diagnostics.push(error_at(
+                format!("\\begin{{{}}} without matching \\end{{{}}}", env, env),
+                position,
+            ));
+        }
+        self.diagnostics.sort_by_key(|d| (d.line, d.column));
+        self.diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced_document_has_no_diagnostics() {
+        let source = r"\documentclass{article}
+\begin{document}
+\section{Skills} % unbalanced in a comment: {
+Rust \& C\{\} \verb|{| % escaped braces are text
+\begin{verbatim}
+fn main() { println!()
+\end{verbatim}
+\begin{itemize}
+  \item {\bfseries Bold}
+\end{itemize}
+\end{document}
+";
+        assert!(lint_structure(source).is_empty());
+    }
+
+    #[test]
+    fn test_unclosed_brace() {
+        let source = "\\begin{document}\n\\textbf{Name\n\\end{document}\n";
+        let diagnostics = lint_structure(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unclosed brace '{'");
+        assert_eq!(diagnostics[0].line, Some(2));
+        assert_eq!(diagnostics[0].column, Some(8));
+    }
+
+    #[test]
+    fn test_unmatched_closing_brace() {
+        let diagnostics = lint_structure("Name}\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unmatched closing brace '}'");
+        assert_eq!(diagnostics[0].line, Some(1));
+    }
+
+    #[test]
+    fn test_mismatched_environment() {
+        let source = "\\begin{document}\n\\begin{itemize}\n\\item A\n\\end{document}\n";
+        let diagnostics = lint_structure(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "\\begin{itemize} ended by \\end{document}"
+        );
+        assert_eq!(diagnostics[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_unmatched_begin_and_end() {
+        let diagnostics = lint_structure("\\end{center}\n\\begin{itemize}\n");
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "\\end{center} without matching \\begin{center}",
+                "\\begin{itemize} without matching \\end{itemize}",
+            ]
+        );
+    }
+}
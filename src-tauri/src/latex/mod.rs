@@ -0,0 +1,246 @@
+//! LaTeX source analysis that works without a TeX installation
+
+pub mod class;
+pub mod escape;
+pub mod export;
+pub mod format;
+pub mod includes;
+pub mod keywords;
+pub mod links;
+pub mod lint;
+pub mod outline;
+
+/// Environments whose content is taken literally rather than as LaTeX
+pub const VERBATIM_ENVIRONMENTS: [&str; 6] = [
+    "verbatim",
+    "verbatim*",
+    "Verbatim",
+    "lstlisting",
+    "minted",
+    "comment",
+];
+
+/// The part of a source line before any `%` comment
+pub fn strip_line_comment(line: &str) -> &str {
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        if c == '%' && !escaped {
+            return &line[..index];
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    line
+}
+
+/// Remove `%` comments, keeping line breaks so line numbers don't change
+///
+/// `\%` is a literal percent sign, and the content of verbatim
+/// environments is kept as written.
+pub fn strip_comments(source: &str) -> String {
+    let verbatim = verbatim_lines(source);
+    source
+        .lines()
+        .zip(verbatim)
+        .map(|(line, verbatim)| {
+            if verbatim {
+                line
+            } else {
+                strip_line_comment(line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// For each line of `source`, whether it is the content of a verbatim environment
+///
+/// The `\begin` and `\end` lines themselves are not content.
+pub fn verbatim_lines(source: &str) -> Vec<bool> {
+    let mut open: Option<&str> = None;
+    source
+        .lines()
+        .map(|line| {
+            if let Some(env) = open {
+                if line.contains(&format!("\\end{{{}}}", env)) {
+                    open = None;
+                    return false;
+                }
+                return true;
+            }
+            let code = strip_line_comment(line);
+            open = VERBATIM_ENVIRONMENTS.iter().copied().find(|env| {
+                code.find(&format!("\\begin{{{}}}", env)).is_some_and(|start| {
+                    !code[start..].contains(&format!("\\end{{{}}}", env))
+                })
+            });
+            false
+        })
+        .collect()
+}
+
+/// The text between `\begin{document}` and `\end{document}`
+///
+/// Sources without a document environment (e.g. `\input` fragments) are
+/// returned whole.
+pub fn document_body(source: &str) -> &str {
+    let start = source
+        .find("\\begin{document}")
+        .map_or(0, |i| i + "\\begin{document}".len());
+    let end = source[start..]
+        .find("\\end{document}")
+        .map_or(source.len(), |i| start + i);
+    &source[start..end]
+}
+
+/// Index of the first non-blank character at or after `start`
+///
+/// Like TeX looking for an argument, this crosses at most one line break,
+/// never a paragraph break.
+fn skip_spaces(source: &str, start: usize) -> usize {
+    let mut newline = false;
+    for (offset, c) in source[start..].char_indices() {
+        match c {
+            ' ' | '\t' | '\r' => {}
+            '\n' if !newline => newline = true,
+            _ => return start + offset,
+        }
+    }
+    source.len()
+}
+
+/// Read a delimited argument starting at `start`, allowing leading spaces
+///
+/// Returns the text between the delimiters and the index just past the
+/// closing one. Nested and escaped delimiters are handled.
+fn read_delimited(source: &str, start: usize, open: char, close: char) -> Option<(&str, usize)> {
+    let begin = skip_spaces(source, start);
+    if !source[begin..].starts_with(open) {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut escaped = false;
+    for (offset, c) in source[begin..].char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        if c == '\\' {
+            escaped = true;
+        } else if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                let end = begin + offset;
+                return Some((&source[begin + 1..end], end + 1));
+            }
+        }
+    }
+    None
+}
+
+/// Read a `{...}` group starting at `start`
+pub fn read_group(source: &str, start: usize) -> Option<(&str, usize)> {
+    read_delimited(source, start, '{', '}')
+}
+
+/// Read an optional `[...]` argument starting at `start`
+pub fn read_optional(source: &str, start: usize) -> Option<(&str, usize)> {
+    read_delimited(source, start, '[', ']')
+}
+
+/// Skip an optional `[...]` argument if there is one
+pub fn skip_optional(source: &str, start: usize) -> usize {
+    read_optional(source, start).map_or(start, |(_, end)| end)
+}
+
+/// Read the name of the command whose backslash is just before `start`
+///
+/// Names are a run of letters or a single other character (`\%`, `\\`).
+pub fn read_command_name(source: &str, start: usize) -> (&str, usize) {
+    let rest = &source[start..];
+    let len = match rest.find(|c: char| !c.is_ascii_alphabetic()) {
+        Some(0) => rest.chars().next().map_or(0, char::len_utf8),
+        Some(len) => len,
+        None => rest.len(),
+    };
+    (&rest[..len], start + len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_line_comment() {
+        assert_eq!(strip_line_comment("Hello % world"), "Hello ");
+        assert_eq!(strip_line_comment("50\\% done"), "50\\% done");
+        assert_eq!(strip_line_comment("line\\\\% comment"), "line\\\\");
+    }
+
+    #[test]
+    fn test_strip_comments_keeps_lines() {
+        assert_eq!(strip_comments("a % x\n% y\nb"), "a \n\nb");
+    }
+
+    #[test]
+    fn test_strip_comments_keeps_literal_percent() {
+        assert_eq!(strip_comments("Grew revenue 40\\% literal"), "Grew revenue 40\\% literal");
+        assert_eq!(strip_comments("text % comment"), "text ");
+        assert_eq!(strip_comments("40\\% up % was 30\\%"), "40\\% up ");
+    }
+
+    #[test]
+    fn test_strip_comments_keeps_verbatim() {
+        let source = concat!(
+            "\\begin{verbatim} % opened here\n",
+            "printf(\"100%\\n\"); % not a comment\n",
+            "\\end{verbatim}\n",
+            "after % comment",
+        );
+        assert_eq!(
+            strip_comments(source),
+            concat!(
+                "\\begin{verbatim} \n",
+                "printf(\"100%\\n\"); % not a comment\n",
+                "\\end{verbatim}\n",
+                "after ",
+            )
+        );
+    }
+
+    #[test]
+    fn test_document_body() {
+        let source = "\\documentclass{article}\n\\begin{document}\nHi\n\\end{document}\n";
+        assert_eq!(document_body(source), "\nHi\n");
+        assert_eq!(document_body("\\section{A}"), "\\section{A}");
+    }
+
+    #[test]
+    fn test_read_group_nested_and_escaped() {
+        let source = "\\textbf {a {b} \\} c} rest";
+        assert_eq!(read_group(source, 7), Some(("a {b} \\} c", 20)));
+        assert_eq!(read_group("{unclosed", 0), None);
+        assert_eq!(read_group("text", 0), None);
+        assert_eq!(read_group("\n  {next line}", 0), Some(("next line", 14)));
+        assert_eq!(read_group("\n\n{new paragraph}", 0), None);
+    }
+
+    #[test]
+    fn test_read_command_name() {
+        assert_eq!(read_command_name("\\section*{A}", 1), ("section", 8));
+        assert_eq!(read_command_name("\\%", 1), ("%", 2));
+        assert_eq!(read_command_name("\\", 1), ("", 1));
+    }
+
+    #[test]
+    fn test_verbatim_lines() {
+        let source = "a\n\\begin{verbatim}\n  x  \n\\end{verbatim}\n\\verb|y| \\begin{lstlisting}\nz\n";
+        assert_eq!(
+            verbatim_lines(source),
+            [false, false, true, false, false, true]
+        );
+        assert_eq!(verbatim_lines("% \\begin{verbatim}\nx\n"), [false, false]);
+    }
+}
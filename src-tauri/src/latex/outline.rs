@@ -0,0 +1,148 @@
+//! Section outline of a resume for the document navigator
+
+use super::export::{export, ExportFormat};
+use super::{read_command_name, read_group, skip_optional, strip_comments};
+
+/// A heading in the outline
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct OutlineEntry {
+    pub title: String,
+    /// 1 for sections, 2 for subsections and resume entries, 3 for subsubsections
+    pub level: u32,
+    /// 1-based source line of the command
+    pub line: u32,
+}
+
+/// A command that starts an outline entry
+struct Heading {
+    command: &'static str,
+    level: u32,
+    /// Which required argument holds the title
+    title_arg: usize,
+}
+
+const HEADINGS: [Heading; 6] = [
+    Heading {
+        command: "section",
+        level: 1,
+        title_arg: 0,
+    },
+    Heading {
+        command: "subsection",
+        level: 2,
+        title_arg: 0,
+    },
+    Heading {
+        command: "subsubsection",
+        level: 3,
+        title_arg: 0,
+    },
+    Heading {
+        command: "resumeSubheading",
+        level: 2,
+        title_arg: 0,
+    },
+    Heading {
+        command: "resumeProjectHeading",
+        level: 2,
+        title_arg: 0,
+    },
+    // moderncv: \cventry{years}{degree/job title}{institution}...
+    Heading {
+        command: "cventry",
+        level: 2,
+        title_arg: 1,
+    },
+];
+
+/// Headings of `source` in document order
+pub fn document_outline(source: &str) -> Vec<OutlineEntry> {
+    let source = strip_comments(source);
+    let mut entries = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while let Some(offset) = source[i..].find('\\') {
+        line += source[i..i + offset].matches('\n').count() as u32;
+        let (name, mut end) = read_command_name(&source, i + offset + 1);
+        if source[end..].starts_with('*') {
+            end += 1;
+        }
+        if let Some(heading) = HEADINGS.iter().find(|h| h.command == name) {
+            if let Some(title) = heading_title(&source, end, heading.title_arg) {
+                entries.push(OutlineEntry {
+                    title,
+                    level: heading.level,
+                    line,
+                });
+            }
+        }
+        // Continue after the name so commands inside arguments are found too
+        i = end;
+    }
+    entries
+}
+
+/// The plain-text title in required argument `index` after `start`
+fn heading_title(source: &str, start: usize, index: usize) -> Option<String> {
+    let mut pos = skip_optional(source, start);
+    for _ in 0..index {
+        pos = read_group(source, pos)?.1;
+    }
+    let (arg, _) = read_group(source, pos)?;
+    let title = export(arg, ExportFormat::PlainText);
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str, level: u32, line: u32) -> OutlineEntry {
+        OutlineEntry {
+            title: title.to_string(),
+            level,
+            line,
+        }
+    }
+
+    #[test]
+    fn test_outline_of_resume() {
+        let source = r"\documentclass{article}
+\begin{document}
+\section*{Education}
+\resumeSubheading
+  {State University}{2016 -- 2020}
+  {B.S. Computer Science}{Springfield}
+% \section{Hidden}
+\section[Work]{Work \textbf{Experience}}
+\subsection{Acme Corp}
+\cventry{2020--2023}{Software Engineer}{Acme}{}{}{}
+\end{document}
+";
+        assert_eq!(
+            document_outline(source),
+            [
+                entry("Education", 1, 3),
+                entry("State University", 2, 4),
+                entry("Work Experience", 1, 8),
+                entry("Acme Corp", 2, 9),
+                entry("Software Engineer", 2, 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outline_skips_commands_without_title() {
+        assert!(document_outline(r"\section{} \section \newcommand{\section}").is_empty());
+    }
+
+    #[test]
+    fn test_subsubsection_not_matched_as_section() {
+        assert_eq!(
+            document_outline("\\subsubsection{Details}"),
+            [entry("Details", 3, 1)]
+        );
+    }
+}
@@ -1,18 +1,36 @@
+pub mod backups;
 pub mod commands;
 pub mod compiler;
+pub mod diff;
+pub mod disk;
+pub mod encoding;
 pub mod file_ops;
+pub mod latex;
+pub mod line_endings;
 pub mod pdf;
+pub mod projects;
+pub mod reveal;
+pub mod session;
+pub mod settings;
 pub mod state;
+pub mod templates;
 pub mod types;
+pub mod watcher;
 pub mod workspace;
 
 use state::AppState;
+use tauri::Manager;
 
 // Re-export commonly used types
 pub use types::FileInfo;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Apply the saved tool paths before anything looks for TeX
+    if let Ok(settings) = settings::load_settings() {
+        compiler::pdflatex::set_tool_overrides(settings.tool_overrides());
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
@@ -20,15 +38,79 @@ pub fn run() {
         .manage(AppState::default())
         .invoke_handler(tauri::generate_handler![
             commands::workspace_init,
+            commands::workspace_repair,
+            commands::workspace_disk_status,
             commands::file_open,
             commands::file_save,
+            commands::doc_update,
+            commands::file_has_unsaved_changes,
+            commands::file_diff,
             commands::file_save_as,
             commands::file_get_current,
+            commands::reveal_in_file_manager,
+            commands::session_restore,
+            commands::file_reload,
+            commands::file_is_writable,
+            commands::file_allow_directory,
+            commands::file_new,
+            commands::project_create,
+            commands::projects_list,
+            commands::project_open,
+            commands::project_detect_main,
+            commands::project_set_output_name,
+            commands::templates_list,
+            commands::template_instantiate,
+            commands::template_placeholders,
+            commands::latex_escape,
+            commands::latex_unescape,
+            commands::tab_switch,
+            commands::tab_close,
+            commands::backups_list,
+            commands::backup_restore,
             commands::build_compile,
+            commands::build_cancel,
+            commands::build_compile_content,
+            commands::build_compile_snippet,
+            commands::build_selftest,
+            commands::build_probe_engines,
+            commands::build_artifacts,
+            commands::build_open_log,
+            commands::build_compile_debounced,
+            commands::build_history,
+            commands::diagnostics_last,
+            commands::settings_get,
+            commands::settings_update,
+            commands::build_set_auto_build,
+            commands::build_set_output_dir,
             commands::check_system_requirements,
             commands::debug_pdflatex,
-            commands::read_pdf_base64
+            commands::pdflatex_set_path,
+            commands::pdflatex_refresh_cache,
+            commands::read_pdf_base64,
+            commands::pdf_get_bytes,
+            commands::pdf_page_count,
+            commands::pdf_extract_text,
+            commands::pdf_word_count,
+            commands::pdf_extract_pages,
+            commands::pdf_render_page_png,
+            commands::pdf_diff,
+            commands::synctex_resolve,
+            commands::synctex_forward,
+            commands::export_plaintext,
+            commands::keyword_match,
+            commands::export_markdown,
+            commands::document_outline,
+            commands::document_class,
+            commands::lint_structure,
+            commands::check_includes,
+            commands::check_links
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Don't leave a running pdflatex behind when the app quits mid-build
+            if let tauri::RunEvent::Exit = event {
+                app.state::<AppState>().compilation.shutdown();
+            }
+        });
 }
@@ -0,0 +1,115 @@
+//! Line ending handling when saving files
+//!
+//! Files edited on different systems end up with a mix of `\n` and
+//! `\r\n`; saving converts every line to one ending so diffs stay clean.
+
+use crate::latex::verbatim_lines;
+
+/// Line ending used when saving
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+    /// Whichever ending the file mostly uses already
+    #[default]
+    Preserve,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Lf | LineEnding::Preserve => "\n",
+        }
+    }
+}
+
+/// The ending most lines of `text` use; ties and text without line breaks are `Lf`
+pub fn detect_line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    if crlf > lf {
+        LineEnding::CrLf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// Convert every line ending in `content` to `ending`
+///
+/// `Preserve` uses the dominant ending of `content`. Lines inside verbatim
+/// environments keep their ending, since it is part of their content.
+pub fn normalize_line_endings(content: &str, ending: LineEnding) -> String {
+    let ending = match ending {
+        LineEnding::Preserve => detect_line_ending(content),
+        ending => ending,
+    };
+    let verbatim = verbatim_lines(content);
+
+    let mut out = String::with_capacity(content.len());
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let Some(text) = line.strip_suffix('\n') else {
+            // Last line without a line break
+            out.push_str(line);
+            continue;
+        };
+        if verbatim.get(index).copied().unwrap_or(false) {
+            out.push_str(line);
+            continue;
+        }
+        out.push_str(text.strip_suffix('\r').unwrap_or(text));
+        out.push_str(ending.as_str());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIXED: &str = "a\r\nb\nc\r\nd";
+
+    #[test]
+    fn test_detect_line_ending() {
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), LineEnding::CrLf);
+        assert_eq!(detect_line_ending("a\nb\nc\r\n"), LineEnding::Lf);
+        assert_eq!(detect_line_ending("no breaks"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn test_normalize_to_lf() {
+        assert_eq!(normalize_line_endings(MIXED, LineEnding::Lf), "a\nb\nc\nd");
+    }
+
+    #[test]
+    fn test_normalize_to_crlf() {
+        assert_eq!(
+            normalize_line_endings(MIXED, LineEnding::CrLf),
+            "a\r\nb\r\nc\r\nd"
+        );
+    }
+
+    #[test]
+    fn test_preserve_uses_dominant_ending() {
+        assert_eq!(
+            normalize_line_endings(MIXED, LineEnding::Preserve),
+            "a\r\nb\r\nc\r\nd"
+        );
+        assert_eq!(
+            normalize_line_endings("a\nb\nc\r\n", LineEnding::Preserve),
+            "a\nb\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_verbatim_content_is_untouched() {
+        let content = "\\begin{verbatim}\r\nx\r\n\\end{verbatim}\r\ny\r\n";
+        assert_eq!(
+            normalize_line_endings(content, LineEnding::Lf),
+            "\\begin{verbatim}\nx\r\n\\end{verbatim}\ny\n"
+        );
+    }
+}
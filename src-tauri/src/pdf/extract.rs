@@ -0,0 +1,390 @@
+//! Copy a range of pages into a new PDF
+//!
+//! The selected page objects are copied along with everything they
+//! reference (content streams, fonts, images), renumbered from 3 under a
+//! fresh catalog (1) and page tree (2). Streams are copied still encoded.
+//! The outline and named destinations belong to the old catalog and are
+//! left behind, along with links that would lead nowhere without them.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::parser::{dict_type, Dictionary, Document, Object};
+
+/// Page attributes a page may inherit from its ancestors in the page tree
+const INHERITABLE: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// A new PDF holding pages `start..=end` (1-based) of `bytes`
+pub fn extract_pages(bytes: &[u8], start: u32, end: u32) -> Result<Vec<u8>, String> {
+    let document = Document::parse(bytes)?;
+    let pages = document.pages();
+    if pages.is_empty() {
+        return Err("Could not find any pages in PDF".to_string());
+    }
+    if start == 0 || start > end || end as usize > pages.len() {
+        return Err(format!(
+            "Page range {}-{} is out of bounds: the PDF has {} pages",
+            start,
+            end,
+            pages.len()
+        ));
+    }
+    let selected: Vec<u32> = pages[start as usize - 1..end as usize]
+        .iter()
+        .map(|page| page.id)
+        .collect();
+
+    // Pages left out and the old page tree would drag the whole document in
+    let excluded: HashSet<u32> = document
+        .objects
+        .iter()
+        .filter(|(id, object)| {
+            let kind = object.as_dict().and_then(dict_type);
+            kind == Some(b"Pages") || (kind == Some(b"Page") && !selected.contains(id))
+        })
+        .map(|(id, _)| *id)
+        .collect();
+
+    let page_dicts: HashMap<u32, Object> = selected
+        .iter()
+        .map(|&id| {
+            (
+                id,
+                Object::Dictionary(page_dictionary(&document, id, &selected)),
+            )
+        })
+        .collect();
+
+    let mut numbers: HashMap<u32, u32> = HashMap::new();
+    let mut order: Vec<u32> = Vec::new();
+    let mut queue: VecDeque<u32> = VecDeque::new();
+    for &id in &selected {
+        numbers.insert(id, 3 + order.len() as u32);
+        order.push(id);
+        queue.push_back(id);
+    }
+    while let Some(id) = queue.pop_front() {
+        let Some(object) = page_dicts.get(&id).or_else(|| document.get(id)) else {
+            continue;
+        };
+        let mut references = Vec::new();
+        collect_references(object, &mut references);
+        for reference in references {
+            if excluded.contains(&reference)
+                || numbers.contains_key(&reference)
+                || document.get(reference).is_none()
+            {
+                continue;
+            }
+            numbers.insert(reference, 3 + order.len() as u32);
+            order.push(reference);
+            queue.push_back(reference);
+        }
+    }
+
+    let kids: Vec<String> = selected
+        .iter()
+        .map(|id| format!("{} 0 R", numbers[id]))
+        .collect();
+    let mut writer = Writer::new();
+    writer.add(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    writer.add(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            kids.len()
+        )
+        .into_bytes(),
+    );
+    for id in order {
+        let object = page_dicts
+            .get(&id)
+            .or_else(|| document.get(id))
+            .unwrap_or(&Object::Null);
+        let mut out = Vec::new();
+        serialize(object, &numbers, &mut out);
+        writer.add(out);
+    }
+    Ok(writer.finish())
+}
+
+/// A page's dictionary with inherited attributes filled in, parented to object 2
+///
+/// Links whose destination won't exist among the `selected` pages are dropped.
+fn page_dictionary(document: &Document, id: u32, selected: &[u32]) -> Dictionary {
+    let mut dict = document
+        .get(id)
+        .and_then(Object::as_dict)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut ancestor = dict.get(b"Parent".as_slice()).cloned();
+    for _ in 0..64 {
+        let Some(parent) = ancestor
+            .as_ref()
+            .and_then(|parent| document.resolve(parent).as_dict())
+        else {
+            break;
+        };
+        for key in INHERITABLE {
+            if !dict.contains_key(key) {
+                if let Some(value) = parent.get(key) {
+                    dict.insert(key.to_vec(), value.clone());
+                }
+            }
+        }
+        ancestor = parent.get(b"Parent".as_slice()).cloned();
+    }
+
+    let annotations =
+        dict.get(b"Annots".as_slice())
+            .and_then(|annots| document.resolve(annots).as_array())
+            .map(|annots| {
+                annots
+                    .iter()
+                    .filter(|annot| {
+                        !document.resolve(annot).as_dict().is_some_and(|annot| {
+                            has_dangling_destination(document, annot, selected)
+                        })
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>()
+            });
+    if let Some(annotations) = annotations {
+        dict.insert(b"Annots".to_vec(), Object::Array(annotations));
+    }
+
+    dict.insert(b"Parent".to_vec(), Object::Reference(2, 0));
+    dict
+}
+
+/// Whether a link annotation goes to a destination the new PDF won't have
+///
+/// Explicit destinations may point at a page that was left out, and named
+/// ones are looked up in the old catalog, which isn't copied.
+fn has_dangling_destination(document: &Document, annot: &Dictionary, selected: &[u32]) -> bool {
+    let destination = match annot.get(b"Dest".as_slice()) {
+        Some(destination) => Some(destination),
+        None => annot
+            .get(b"A".as_slice())
+            .and_then(|action| document.resolve(action).as_dict())
+            .filter(|action| action.get(b"S".as_slice()).and_then(Object::as_name) == Some(b"GoTo"))
+            .and_then(|action| action.get(b"D".as_slice())),
+    };
+    match destination.map(|destination| document.resolve(destination)) {
+        None => false,
+        Some(Object::Array(items)) => {
+            !matches!(items.first(), Some(Object::Reference(id, _)) if selected.contains(id))
+        }
+        Some(_) => true,
+    }
+}
+
+/// Object numbers referenced anywhere inside `object`
+fn collect_references(object: &Object, references: &mut Vec<u32>) {
+    match object {
+        Object::Reference(id, _) => references.push(*id),
+        Object::Array(items) => {
+            for item in items {
+                collect_references(item, references);
+            }
+        }
+        Object::Dictionary(dict) => {
+            for value in dict.values() {
+                collect_references(value, references);
+            }
+        }
+        Object::Stream(stream) => {
+            for value in stream.dict.values() {
+                collect_references(value, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Write `object` in PDF syntax, renumbering references through `numbers`
+///
+/// References to objects that weren't copied become `null`.
+fn serialize(object: &Object, numbers: &HashMap<u32, u32>, out: &mut Vec<u8>) {
+    match object {
+        Object::Null => out.extend_from_slice(b"null"),
+        Object::Boolean(value) => out.extend_from_slice(value.to_string().as_bytes()),
+        Object::Integer(value) => out.extend_from_slice(value.to_string().as_bytes()),
+        Object::Real(value) => out.extend_from_slice(value.to_string().as_bytes()),
+        Object::Name(name) => write_name(name, out),
+        Object::String(bytes) => {
+            out.push(b'<');
+            for byte in bytes {
+                out.extend_from_slice(format!("{:02X}", byte).as_bytes());
+            }
+            out.push(b'>');
+        }
+        Object::Array(items) => {
+            out.push(b'[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(b' ');
+                }
+                serialize(item, numbers, out);
+            }
+            out.push(b']');
+        }
+        Object::Dictionary(dict) => write_dictionary(dict, numbers, out),
+        Object::Stream(stream) => {
+            let mut dict = stream.dict.clone();
+            // The original length may be an indirect object we didn't copy
+            dict.insert(
+                b"Length".to_vec(),
+                Object::Integer(stream.data.len() as i64),
+            );
+            write_dictionary(&dict, numbers, out);
+            out.extend_from_slice(b"\nstream\n");
+            out.extend_from_slice(&stream.data);
+            out.extend_from_slice(b"\nendstream");
+        }
+        Object::Reference(id, _) => match numbers.get(id) {
+            Some(number) => out.extend_from_slice(format!("{} 0 R", number).as_bytes()),
+            None => out.extend_from_slice(b"null"),
+        },
+    }
+}
+
+fn write_dictionary(dict: &Dictionary, numbers: &HashMap<u32, u32>, out: &mut Vec<u8>) {
+    out.extend_from_slice(b"<<");
+    for (key, value) in dict {
+        write_name(key, out);
+        out.push(b' ');
+        serialize(value, numbers, out);
+        out.push(b' ');
+    }
+    out.extend_from_slice(b">>");
+}
+
+/// A name with delimiters and non-printable bytes written as `#xx`
+fn write_name(name: &[u8], out: &mut Vec<u8>) {
+    out.push(b'/');
+    for &byte in name {
+        let plain = (b'!'..=b'~').contains(&byte) && !b"()<>[]{}/%#".contains(&byte);
+        if plain {
+            out.push(byte);
+        } else {
+            out.extend_from_slice(format!("#{:02X}", byte).as_bytes());
+        }
+    }
+}
+
+/// Lays out numbered objects with a cross-reference table
+struct Writer {
+    pdf: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Self {
+            pdf: b"%PDF-1.5\n%\xE2\xE3\xCF\xD3\n".to_vec(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Append the next object, numbered from 1
+    fn add(&mut self, body: Vec<u8>) {
+        self.offsets.push(self.pdf.len());
+        self.pdf
+            .extend_from_slice(format!("{} 0 obj\n", self.offsets.len()).as_bytes());
+        self.pdf.extend_from_slice(&body);
+        self.pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let xref = self.pdf.len();
+        let size = self.offsets.len() + 1;
+        self.pdf
+            .extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", size).as_bytes());
+        for offset in &self.offsets {
+            self.pdf
+                .extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        self.pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                size, xref
+            )
+            .as_bytes(),
+        );
+        self.pdf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::count_pages;
+    use super::super::fixtures::{object_stream_pdf, pdf_from_objects, text_pdf};
+    use super::super::text::extract_pages_text;
+    use super::*;
+
+    #[test]
+    fn test_extract_first_page() {
+        let pdf = extract_pages(&text_pdf(&["One", "Two", "Three"]), 1, 1).unwrap();
+        assert_eq!(count_pages(&pdf).unwrap(), 1);
+
+        // The font is inherited from the old page tree and must come along
+        let document = Document::parse(&pdf).unwrap();
+        assert_eq!(extract_pages_text(&document), ["One"]);
+    }
+
+    #[test]
+    fn test_extract_range_keeps_order() {
+        let pdf = extract_pages(&text_pdf(&["One", "Two", "Three"]), 2, 3).unwrap();
+        let document = Document::parse(&pdf).unwrap();
+        assert_eq!(extract_pages_text(&document), ["Two", "Three"]);
+    }
+
+    #[test]
+    fn test_extract_from_object_stream() {
+        let pdf = extract_pages(&object_stream_pdf(3), 3, 3).unwrap();
+        assert_eq!(count_pages(&pdf).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_links_to_dropped_pages_are_removed() {
+        let pdf = pdf_from_objects(&[
+            b"<< /Type /Catalog /Pages 2 0 R /Outlines 9 0 R >>".to_vec(),
+            b"<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>".to_vec(),
+            b"<< /Type /Page /Parent 2 0 R /Annots [5 0 R 6 0 R 7 0 R 8 0 R] >>".to_vec(),
+            b"<< /Type /Page /Parent 2 0 R >>".to_vec(),
+            b"<< /Type /Annot /Subtype /Link /Dest [4 0 R /Fit] >>".to_vec(),
+            b"<< /Type /Annot /Subtype /Link /A << /S /GoTo /D [3 0 R /Fit] >> >>".to_vec(),
+            b"<< /Type /Annot /Subtype /Link /A << /S /URI /URI (https://example.com) >> >>"
+                .to_vec(),
+            b"<< /Type /Annot /Subtype /Link /Dest (section.2) >>".to_vec(),
+            b"<< /Type /Outlines /First 10 0 R /Count 1 >>".to_vec(),
+            b"<< /Title (Work) /Parent 9 0 R /Dest [4 0 R /Fit] >>".to_vec(),
+        ]);
+        let annotations = |pdf: &[u8]| {
+            let document = Document::parse(pdf).unwrap();
+            let pages = document.pages();
+            let annots = document.get_entry(pages[0].dict, b"Annots").unwrap();
+            let annots = document.resolve(annots).as_array().unwrap();
+            (annots.len(), document.objects.len())
+        };
+
+        // The link to page 2 and the named destination go; the outline isn't copied
+        let (links, objects) = annotations(&extract_pages(&pdf, 1, 1).unwrap());
+        assert_eq!(links, 2);
+        assert_eq!(objects, 5);
+
+        let (links, _) = annotations(&extract_pages(&pdf, 1, 2).unwrap());
+        assert_eq!(links, 3);
+    }
+
+    #[test]
+    fn test_out_of_bounds_range() {
+        let pdf = text_pdf(&["One", "Two", "Three"]);
+        for (start, end) in [(0, 1), (2, 4), (3, 2)] {
+            let err = extract_pages(&pdf, start, end).unwrap_err();
+            assert!(err.contains("out of bounds"), "{}", err);
+            assert!(err.contains("3 pages"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Small generated PDFs for tests
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+/// Assemble numbered objects (starting at 1, catalog first) into a PDF
+pub fn pdf_from_objects(objects: &[Vec<u8>]) -> Vec<u8> {
+    let mut pdf = b"%PDF-1.5\n".to_vec();
+    for (index, object) in objects.iter().enumerate() {
+        pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    pdf.extend_from_slice(b"trailer\n<< /Root 1 0 R >>\n%%EOF\n");
+    pdf
+}
+
+/// A FlateDecode stream object
+pub fn compressed_stream(extra_dict: &str, data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    let data = encoder.finish().unwrap();
+
+    let mut object = format!(
+        "<< {} /Length {} /Filter /FlateDecode >>\nstream\n",
+        extra_dict,
+        data.len()
+    )
+    .into_bytes();
+    object.extend_from_slice(&data);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
+
+/// A PDF with one page per entry, each showing that text in Helvetica
+///
+/// Lines within an entry are separated by `\n` and laid out with `Td`.
+pub fn text_pdf(pages: &[&str]) -> Vec<u8> {
+    // 1: catalog, 2: page tree, 3: font, then a page + content pair per page
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 4 + 2 * i))
+        .collect();
+    let mut objects = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} /Resources << /Font << /F1 3 0 R >> >> >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_vec(),
+    ];
+    for (i, text) in pages.iter().enumerate() {
+        let mut content = String::from("BT /F1 11 Tf 72 720 Td\n");
+        for (line_number, line) in text.lines().enumerate() {
+            if line_number > 0 {
+                content.push_str("0 -14 Td\n");
+            }
+            // Words are separated by kerning, as pdfTeX does
+            let words: Vec<String> = line.split(' ').map(|w| format!("({})", w)).collect();
+            content.push_str(&format!("[{}] TJ\n", words.join(" -333 ")));
+        }
+        content.push_str("ET");
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents {} 0 R >>",
+                5 + 2 * i
+            )
+            .into_bytes(),
+        );
+        objects.push(compressed_stream("", content.as_bytes()));
+    }
+    pdf_from_objects(&objects)
+}
+
+/// Like pdfTeX output: the page tree lives in a compressed object stream
+/// and the catalog is found through a cross-reference stream
+pub fn object_stream_pdf(pages: usize) -> Vec<u8> {
+    // Objects 2 (page tree) and 3.. (pages) go in the object stream
+    let mut bodies = vec![format!(
+        "<</Type/Pages/Count {}/Kids[{}]>>",
+        pages,
+        (0..pages)
+            .map(|i| format!("{} 0 R", 3 + i))
+            .collect::<Vec<_>>()
+            .join(" ")
+    )];
+    bodies.extend((0..pages).map(|_| "<</Type/Page/Parent 2 0 R>>".to_string()));
+
+    let mut header = String::new();
+    let mut body = String::new();
+    for (index, object) in bodies.iter().enumerate() {
+        header.push_str(&format!("{} {} ", index + 2, body.len()));
+        body.push_str(object);
+        body.push(' ');
+    }
+    let data = format!("{}{}", header, body);
+
+    let mut pdf = b"%PDF-1.5\n1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj\n".to_vec();
+    pdf.extend_from_slice(format!("{} 0 obj\n", pages + 3).as_bytes());
+    pdf.extend_from_slice(&compressed_stream(
+        &format!("/Type /ObjStm /N {} /First {}", bodies.len(), header.len()),
+        data.as_bytes(),
+    ));
+    pdf.extend_from_slice(b"\nendobj\n");
+    pdf.extend_from_slice(
+        format!(
+            "{} 0 obj\n<</Type/XRef/Root 1 0 R/Size {}/Length 0>>stream\n\nendstream\nendobj\n%%EOF\n",
+            pages + 4,
+            pages + 5
+        )
+        .as_bytes(),
+    );
+    pdf
+}
@@ -0,0 +1,288 @@
+//! PDF file operations
+
+pub mod extract;
+#[cfg(test)]
+mod fixtures;
+pub mod parser;
+pub mod render;
+pub mod text;
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use parser::Document;
+
+/// Read a PDF file and return it as base64
+#[deprecated(note = "base64 inflates the PDF by a third; use `read_pdf_bytes`")]
+pub fn read_pdf_base64(path: &str) -> Result<String, String> {
+    let buffer = read_pdf_bytes(path)?;
+
+    use base64::Engine;
+    Ok(base64::engine::general_purpose::STANDARD.encode(&buffer))
+}
+
+/// Read a PDF file's raw bytes
+pub fn read_pdf_bytes(path: &str) -> Result<Vec<u8>, String> {
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open PDF: {}", e))?;
+    
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)
+        .map_err(|e| format!("Failed to read PDF: {}", e))?;
+    Ok(buffer)
+}
+
+
+/// Count the pages of a PDF file without rendering it
+pub fn pdf_page_count(path: &str) -> Result<u32, String> {
+    count_pages(&read_pdf_bytes(path)?)
+}
+
+/// Count the pages of a PDF from its page tree
+///
+/// Reads `/Count` from the root of the page tree, falling back to walking
+/// it (or to counting `/Type /Page` objects if there is no usable tree).
+pub fn count_pages(bytes: &[u8]) -> Result<u32, String> {
+    let pages = Document::parse(bytes)?.page_count();
+    if pages == 0 {
+        return Err("Could not find any pages in PDF".to_string());
+    }
+    Ok(pages as u32)
+}
+
+/// Extract the text layer of a PDF file, pages separated by blank lines
+pub fn pdf_extract_text(path: &str) -> Result<String, String> {
+    let document = Document::parse(&read_pdf_bytes(path)?)?;
+    Ok(text::extract_pages_text(&document).join("\n\n"))
+}
+
+/// Write pages `start..=end` (1-based) of the PDF at `src` to a new PDF at `dest`
+pub fn pdf_extract_pages(src: &str, dest: &str, start: u32, end: u32) -> Result<(), String> {
+    let pdf = extract::extract_pages(&read_pdf_bytes(src)?, start, end)?;
+    crate::file_ops::write_bytes(Path::new(dest), &pdf)
+}
+
+/// Word, character and page counts of a compiled document
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct WordCount {
+    pub words: usize,
+    /// Non-whitespace characters
+    pub characters: usize,
+    pub pages: u32,
+}
+
+/// Count the words in a PDF's text layer
+///
+/// This reads the rendered output, so LaTeX markup never counts.
+pub fn pdf_word_count(path: &str) -> Result<WordCount, String> {
+    let document = Document::parse(&read_pdf_bytes(path)?)?;
+    let text = text::extract_pages_text(&document).join("\n");
+    Ok(WordCount {
+        words: text.split_whitespace().count(),
+        characters: text.chars().filter(|c| !c.is_whitespace()).count(),
+        pages: document.page_count() as u32,
+    })
+}
+
+/// Whether one page differs between two PDFs
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PageDiff {
+    /// 1-based page number
+    pub page: u32,
+    /// The page's text differs, or it only exists in one of the PDFs
+    pub changed: bool,
+}
+
+/// Page-level comparison of two PDFs
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DiffResult {
+    pub pages_a: u32,
+    pub pages_b: u32,
+    /// One entry per page of the longer PDF
+    pub pages: Vec<PageDiff>,
+}
+
+impl DiffResult {
+    /// No page changed and the page counts match
+    pub fn is_identical(&self) -> bool {
+        self.pages.iter().all(|page| !page.changed)
+    }
+}
+
+/// Compare two PDF files page by page
+pub fn pdf_diff(a: &str, b: &str) -> Result<DiffResult, String> {
+    diff_pdfs(&read_pdf_bytes(a)?, &read_pdf_bytes(b)?)
+}
+
+/// Compare two PDFs page by page using their text layers
+///
+/// Coarse on purpose: a page is either changed or not. Comparing text
+/// rather than content streams keeps font renumbering between builds from
+/// marking every page as changed.
+pub fn diff_pdfs(a: &[u8], b: &[u8]) -> Result<DiffResult, String> {
+    let text_a = text::extract_pages_text(&Document::parse(a)?);
+    let text_b = text::extract_pages_text(&Document::parse(b)?);
+    let pages = (0..text_a.len().max(text_b.len()))
+        .map(|i| PageDiff {
+            page: i as u32 + 1,
+            changed: text_a.get(i) != text_b.get(i),
+        })
+        .collect();
+    Ok(DiffResult {
+        pages_a: text_a.len() as u32,
+        pages_b: text_b.len() as u32,
+        pages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::{object_stream_pdf, pdf_from_objects, text_pdf};
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_fixture(temp_dir: &TempDir, bytes: &[u8]) -> String {
+        let path = temp_dir.path().join("resume.pdf");
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_count_pages_reads_root_count() {
+        assert_eq!(count_pages(&text_pdf(&["One"])).unwrap(), 1);
+        assert_eq!(count_pages(&text_pdf(&["One", "Two", "Three"])).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_count_pages_in_object_stream() {
+        assert_eq!(count_pages(&object_stream_pdf(2)).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_count_pages_falls_back_to_page_objects() {
+        // No catalog, so no page tree to walk
+        let pdf = pdf_from_objects(&[
+            b"<< /Type /Page >>".to_vec(),
+            b"<< /Type/Page >>".to_vec(),
+        ]);
+        assert_eq!(count_pages(&pdf).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_count_pages_rejects_non_pdf() {
+        assert!(count_pages(b"\\documentclass{article}").is_err());
+        assert!(count_pages(b"%PDF-1.4\n%%EOF").is_err());
+    }
+
+    #[test]
+    fn test_pdf_page_count_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_fixture(&temp_dir, &text_pdf(&["One", "Two"]));
+        assert_eq!(pdf_page_count(&path).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_read_pdf_bytes_matches_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let bytes = object_stream_pdf(1);
+        let path = write_fixture(&temp_dir, &bytes);
+        assert_eq!(read_pdf_bytes(&path).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_read_pdf_bytes_missing_file() {
+        let err = read_pdf_bytes("/nonexistent/resume.pdf").unwrap_err();
+        assert!(err.contains("Failed to open PDF"));
+    }
+
+    #[test]
+    fn test_pdf_extract_text() {
+        let temp_dir = TempDir::new().unwrap();
+        let pdf = text_pdf(&["Jane Doe\nSoftware Engineer", "References available"]);
+        let path = write_fixture(&temp_dir, &pdf);
+
+        let text = pdf_extract_text(&path).unwrap();
+        assert_eq!(text, "Jane Doe\nSoftware Engineer\n\nReferences available");
+    }
+
+    #[test]
+    fn test_pdf_extract_pages_to_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = write_fixture(&temp_dir, &text_pdf(&["One", "Two", "Three"]));
+        let dest = temp_dir.path().join("page-1.pdf");
+        let dest = dest.to_string_lossy();
+
+        pdf_extract_pages(&src, &dest, 1, 1).unwrap();
+        assert_eq!(pdf_page_count(&dest).unwrap(), 1);
+        assert!(pdf_extract_pages(&src, &dest, 1, 4).is_err());
+        // The earlier extract is left whole, with no temporary file beside it
+        assert_eq!(pdf_page_count(&dest).unwrap(), 1);
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_identical_pdfs_have_no_diffs() {
+        let pdf = text_pdf(&["Jane Doe", "References available"]);
+        let diff = diff_pdfs(&pdf, &pdf).unwrap();
+        assert!(diff.is_identical());
+        assert_eq!((diff.pages_a, diff.pages_b), (2, 2));
+        assert_eq!(diff.pages.len(), 2);
+    }
+
+    #[test]
+    fn test_diff_reports_changed_page() {
+        let a = text_pdf(&["Jane Doe", "Rust, Go", "References available"]);
+        let b = text_pdf(&["Jane Doe", "Rust, Go, Python", "References available"]);
+        let changed: Vec<u32> = diff_pdfs(&a, &b)
+            .unwrap()
+            .pages
+            .iter()
+            .filter(|page| page.changed)
+            .map(|page| page.page)
+            .collect();
+        assert_eq!(changed, [2]);
+    }
+
+    #[test]
+    fn test_diff_reports_extra_pages_as_changed() {
+        let temp_dir = TempDir::new().unwrap();
+        let a = temp_dir.path().join("a.pdf");
+        let b = temp_dir.path().join("b.pdf");
+        fs::write(&a, text_pdf(&["Jane Doe"])).unwrap();
+        fs::write(&b, text_pdf(&["Jane Doe", "References available"])).unwrap();
+
+        let diff = pdf_diff(&a.to_string_lossy(), &b.to_string_lossy()).unwrap();
+        assert_eq!((diff.pages_a, diff.pages_b), (1, 2));
+        assert_eq!(
+            diff.pages,
+            [
+                PageDiff {
+                    page: 1,
+                    changed: false
+                },
+                PageDiff {
+                    page: 2,
+                    changed: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pdf_word_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let pdf = text_pdf(&["Jane Doe\nSoftware Engineer", "References available"]);
+        let path = write_fixture(&temp_dir, &pdf);
+
+        let count = pdf_word_count(&path).unwrap();
+        assert_eq!(
+            count,
+            WordCount {
+                words: 6,
+                characters: 42,
+                pages: 2,
+            }
+        );
+    }
+}
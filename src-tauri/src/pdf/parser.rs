@@ -0,0 +1,972 @@
+//! Minimal PDF object parser
+//!
+//! Just enough of the format to walk the page tree and read content
+//! streams: indirect objects (including those packed into compressed
+//! object streams) and FlateDecode streams. Objects are located by
+//! scanning for `N G obj` rather than trusting the cross-reference table,
+//! which also copes with slightly damaged files. Incremental updates are
+//! appended, so when an object is defined twice the copy later in the
+//! file wins, whether it is top-level or packed into an object stream.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+
+/// A PDF dictionary; ordered so that re-serialized output is stable
+pub type Dictionary = BTreeMap<Vec<u8>, Object>;
+
+/// A parsed PDF object
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Real(f64),
+    /// A name, without the leading slash
+    Name(Vec<u8>),
+    String(Vec<u8>),
+    Array(Vec<Object>),
+    Dictionary(Dictionary),
+    Stream(Stream),
+    /// Indirect reference: object number and generation
+    Reference(u32, u16),
+}
+
+impl Object {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Object::Integer(i) => Some(*i),
+            Object::Real(r) => Some(*r as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Object::Integer(i) => Some(*i as f64),
+            Object::Real(r) => Some(*r),
+            _ => None,
+        }
+    }
+
+    pub fn as_name(&self) -> Option<&[u8]> {
+        match self {
+            Object::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The dictionary of a dictionary or stream object
+    pub fn as_dict(&self) -> Option<&Dictionary> {
+        match self {
+            Object::Dictionary(dict) => Some(dict),
+            Object::Stream(stream) => Some(&stream.dict),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Object]> {
+        match self {
+            Object::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// A stream object; `data` is still encoded
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stream {
+    pub dict: Dictionary,
+    pub data: Vec<u8>,
+}
+
+impl Stream {
+    /// Decode the stream data; only FlateDecode (or no filter) is supported
+    pub fn decode(&self) -> Result<Vec<u8>, String> {
+        let filters: Vec<&[u8]> = match self.dict.get(b"Filter".as_slice()) {
+            None => Vec::new(),
+            Some(Object::Name(name)) => vec![name],
+            Some(Object::Array(items)) => items.iter().filter_map(Object::as_name).collect(),
+            Some(_) => return Err("Invalid PDF stream filter".to_string()),
+        };
+
+        let mut data = self.data.clone();
+        for filter in filters {
+            data = match filter {
+                b"FlateDecode" | b"Fl" => {
+                    let mut decoded = Vec::new();
+                    flate2::read::ZlibDecoder::new(data.as_slice())
+                        .read_to_end(&mut decoded)
+                        .map_err(|e| format!("Failed to inflate PDF stream: {}", e))?;
+                    decoded
+                }
+                other => {
+                    return Err(format!(
+                        "Unsupported PDF stream filter: {}",
+                        String::from_utf8_lossy(other)
+                    ))
+                }
+            };
+        }
+        Ok(data)
+    }
+}
+
+fn is_whitespace(c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' | b'\0')
+}
+
+fn is_delimiter(c: u8) -> bool {
+    matches!(
+        c,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+fn is_regular(c: u8) -> bool {
+    !is_whitespace(c) && !is_delimiter(c)
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
+
+/// A token from a PDF file or content stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Object(Object),
+    /// A bare keyword: a content stream operator, or `stream`/`endobj` etc.
+    Operator(Vec<u8>),
+}
+
+/// Tokenizer over PDF syntax
+pub struct Lexer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Whether `N G R` is read as a reference (not in content streams)
+    references: bool,
+}
+
+impl<'a> Lexer<'a> {
+    /// Lexer for file-level objects starting at `pos`
+    pub fn new(bytes: &'a [u8], pos: usize) -> Self {
+        Self {
+            bytes,
+            pos,
+            references: true,
+        }
+    }
+
+    /// Lexer for a content stream
+    pub fn content(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            references: false,
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if is_whitespace(c) {
+                self.pos += 1;
+            } else if c == b'%' {
+                while self.peek().is_some_and(|c| c != b'\n' && c != b'\r') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Skip the binary data of an inline image, after its `ID` operator
+    pub fn skip_inline_image(&mut self) {
+        while self.pos + 2 < self.bytes.len() {
+            if is_whitespace(self.bytes[self.pos])
+                && &self.bytes[self.pos + 1..self.pos + 3] == b"EI"
+                && self
+                    .bytes
+                    .get(self.pos + 3)
+                    .map_or(true, |&c| !is_regular(c))
+            {
+                self.pos += 3;
+                return;
+            }
+            self.pos += 1;
+        }
+        self.pos = self.bytes.len();
+    }
+
+    /// Parse the next object, or `None` at the end or on a keyword
+    pub fn parse_object(&mut self) -> Option<Object> {
+        match self.next_token()? {
+            Token::Object(object) => Some(object),
+            Token::Operator(_) => None,
+        }
+    }
+
+    /// Read the next token
+    pub fn next_token(&mut self) -> Option<Token> {
+        self.skip_whitespace();
+        let c = self.peek()?;
+        let token = match c {
+            b'/' => Token::Object(Object::Name(self.read_name())),
+            b'(' => Token::Object(Object::String(self.read_literal_string())),
+            b'<' if self.bytes.get(self.pos + 1) == Some(&b'<') => {
+                Token::Object(Object::Dictionary(self.read_dictionary()))
+            }
+            b'<' => Token::Object(Object::String(self.read_hex_string())),
+            b'[' => Token::Object(Object::Array(self.read_array())),
+            b'+' | b'-' | b'.' | b'0'..=b'9' => Token::Object(self.read_number()),
+            _ if is_delimiter(c) => {
+                // Stray closing delimiter
+                self.pos += 1;
+                Token::Operator(vec![c])
+            }
+            _ => {
+                let start = self.pos;
+                while self.peek().is_some_and(is_regular) {
+                    self.pos += 1;
+                }
+                match &self.bytes[start..self.pos] {
+                    b"true" => Token::Object(Object::Boolean(true)),
+                    b"false" => Token::Object(Object::Boolean(false)),
+                    b"null" => Token::Object(Object::Null),
+                    keyword => Token::Operator(keyword.to_vec()),
+                }
+            }
+        };
+        Some(token)
+    }
+
+    fn read_name(&mut self) -> Vec<u8> {
+        self.pos += 1;
+        let mut name = Vec::new();
+        while let Some(c) = self.peek().filter(|&c| is_regular(c)) {
+            self.pos += 1;
+            if c == b'#' {
+                let hi = self.bytes.get(self.pos).copied().and_then(hex_value);
+                let lo = self.bytes.get(self.pos + 1).copied().and_then(hex_value);
+                if let (Some(hi), Some(lo)) = (hi, lo) {
+                    name.push(hi << 4 | lo);
+                    self.pos += 2;
+                    continue;
+                }
+            }
+            name.push(c);
+        }
+        name
+    }
+
+    fn read_literal_string(&mut self) -> Vec<u8> {
+        self.pos += 1;
+        let mut out = Vec::new();
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                b'(' => {
+                    depth += 1;
+                    out.push(c);
+                }
+                b')' if depth == 0 => break,
+                b')' => {
+                    depth -= 1;
+                    out.push(c);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else {
+                        break;
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'0'..=b'7' => {
+                            let mut value = u32::from(escaped - b'0');
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(d @ b'0'..=b'7') => {
+                                        value = value * 8 + u32::from(d - b'0');
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value as u8);
+                        }
+                        // Line continuation
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+
+    fn read_hex_string(&mut self) -> Vec<u8> {
+        self.pos += 1;
+        let mut digits = Vec::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == b'>' {
+                break;
+            }
+            if let Some(value) = hex_value(c) {
+                digits.push(value);
+            }
+        }
+        if digits.len() % 2 == 1 {
+            digits.push(0);
+        }
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect()
+    }
+
+    fn read_array(&mut self) -> Vec<Object> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => break,
+                Some(b']') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => match self.next_token() {
+                    Some(Token::Object(object)) => items.push(object),
+                    Some(Token::Operator(_)) => {}
+                    None => break,
+                },
+            }
+        }
+        items
+    }
+
+    fn read_dictionary(&mut self) -> Dictionary {
+        self.pos += 2;
+        let mut dict = Dictionary::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                break;
+            }
+            if self.bytes[self.pos..].starts_with(b">>") {
+                self.pos += 2;
+                break;
+            }
+            match self.next_token() {
+                Some(Token::Object(Object::Name(key))) => {
+                    if let Some(value) = self.parse_object() {
+                        dict.insert(key, value);
+                    }
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+        dict
+    }
+
+    fn read_unsigned(&mut self) -> Option<u64> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    fn read_number(&mut self) -> Object {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| matches!(c, b'+' | b'-' | b'.' | b'0'..=b'9'))
+        {
+            self.pos += 1;
+        }
+        let text = String::from_utf8_lossy(&self.bytes[start..self.pos]).to_string();
+        if text.contains('.') {
+            return Object::Real(text.parse().unwrap_or(0.0));
+        }
+        let Ok(value) = text.parse::<i64>() else {
+            return Object::Integer(0);
+        };
+
+        if self.references && value >= 0 {
+            // Look ahead for `G R`
+            let saved = self.pos;
+            self.skip_whitespace();
+            if let Some(generation) = self.read_unsigned() {
+                self.skip_whitespace();
+                let is_ref = self.peek() == Some(b'R')
+                    && self
+                        .bytes
+                        .get(self.pos + 1)
+                        .map_or(true, |&c| !is_regular(c));
+                if is_ref {
+                    self.pos += 1;
+                    return Object::Reference(value as u32, generation as u16);
+                }
+            }
+            self.pos = saved;
+        }
+        Object::Integer(value)
+    }
+}
+
+/// A page in document order, with its inherited resources
+#[derive(Debug, Clone)]
+pub struct Page<'a> {
+    /// Object number of the page dictionary
+    pub id: u32,
+    pub dict: &'a Dictionary,
+    pub resources: Option<&'a Dictionary>,
+}
+
+/// A parsed PDF file
+#[derive(Debug, Default)]
+pub struct Document {
+    pub objects: HashMap<u32, Object>,
+    /// Object number of the document catalog
+    pub root: Option<u32>,
+}
+
+/// Deepest page tree / reference chain followed before giving up
+const MAX_DEPTH: usize = 64;
+
+impl Document {
+    /// Parse a PDF from its bytes
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if !bytes.starts_with(b"%PDF-") {
+            return Err("Not a PDF file".to_string());
+        }
+
+        let mut document = Document::default();
+        let mut offsets = document.scan_objects(bytes);
+        document.unpack_object_streams(&mut offsets);
+        document.root = find_root(bytes, &document);
+        Ok(document)
+    }
+
+    /// Find every `N G obj ... endobj` in the file
+    ///
+    /// Returns where each kept definition starts.
+    fn scan_objects(&mut self, bytes: &[u8]) -> HashMap<u32, usize> {
+        let mut offsets = HashMap::new();
+        let mut cursor = 0;
+        while let Some(found) = find(&bytes[cursor..], b"obj") {
+            let keyword = cursor + found;
+            cursor = keyword + 3;
+            if bytes.get(cursor).is_some_and(|&c| is_regular(c)) {
+                continue;
+            }
+            let Some(number) = object_number_before(bytes, keyword) else {
+                continue;
+            };
+
+            let mut lexer = Lexer::new(bytes, cursor);
+            let Some(object) = lexer.parse_object() else {
+                continue;
+            };
+            cursor = lexer.position();
+
+            let object = match object {
+                Object::Dictionary(dict) => match stream_bounds(bytes, cursor, &dict) {
+                    Some((start, end)) => {
+                        cursor = end;
+                        Object::Stream(Stream {
+                            dict,
+                            data: bytes[start..end].to_vec(),
+                        })
+                    }
+                    None => Object::Dictionary(dict),
+                },
+                other => other,
+            };
+            // Later definitions (incremental updates) win
+            self.objects.insert(number, object);
+            offsets.insert(number, keyword);
+        }
+        offsets
+    }
+
+    /// Add the objects packed into `/Type /ObjStm` streams
+    ///
+    /// A packed object replaces a top-level one only if its stream comes
+    /// later in the file; `offsets` is updated to match.
+    fn unpack_object_streams(&mut self, offsets: &mut HashMap<u32, usize>) {
+        let mut streams: Vec<(usize, Stream)> = self
+            .objects
+            .iter()
+            .filter_map(|(id, object)| match object {
+                Object::Stream(stream) if dict_type(&stream.dict) == Some(b"ObjStm") => {
+                    Some((offsets.get(id).copied().unwrap_or(0), stream.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        streams.sort_by_key(|(offset, _)| *offset);
+
+        for (stream_offset, stream) in streams {
+            let Ok(data) = stream.decode() else {
+                continue;
+            };
+            let count = stream.dict.get(b"N".as_slice()).and_then(Object::as_i64);
+            let first = stream
+                .dict
+                .get(b"First".as_slice())
+                .and_then(Object::as_i64);
+            let first = first.and_then(|first| usize::try_from(first).ok());
+            let (Some(count), Some(first)) = (count, first) else {
+                continue;
+            };
+
+            let mut header = Lexer::new(&data[..first.min(data.len())], 0);
+            for _ in 0..count {
+                let number = header.parse_object().and_then(|o| o.as_i64());
+                let offset = header.parse_object().and_then(|o| o.as_i64());
+                let (Some(number), Some(offset)) = (number, offset) else {
+                    break;
+                };
+                // Skip entries whose number or offset can't be right
+                let number = u32::try_from(number).ok();
+                let position = usize::try_from(offset)
+                    .ok()
+                    .and_then(|offset| first.checked_add(offset))
+                    .filter(|&position| position < data.len());
+                let (Some(number), Some(position)) = (number, position) else {
+                    continue;
+                };
+                if offsets.get(&number).is_some_and(|&defined| defined > stream_offset) {
+                    continue;
+                }
+                if let Some(object) = Lexer::new(&data, position).parse_object() {
+                    self.objects.insert(number, object);
+                    offsets.insert(number, stream_offset);
+                }
+            }
+        }
+    }
+
+    /// Look up an object by number
+    pub fn get(&self, id: u32) -> Option<&Object> {
+        self.objects.get(&id)
+    }
+
+    /// Follow references until reaching a direct object
+    pub fn resolve<'a>(&'a self, object: &'a Object) -> &'a Object {
+        let mut current = object;
+        for _ in 0..MAX_DEPTH {
+            match current {
+                Object::Reference(id, _) => match self.objects.get(id) {
+                    Some(target) => current = target,
+                    None => return &Object::Null,
+                },
+                _ => return current,
+            }
+        }
+        &Object::Null
+    }
+
+    /// Resolve a dictionary entry, following references
+    pub fn get_entry<'a>(&'a self, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+        dict.get(key).map(|object| self.resolve(object))
+    }
+
+    /// The document catalog
+    pub fn catalog(&self) -> Option<&Dictionary> {
+        self.get(self.root?)?.as_dict()
+    }
+
+    /// All pages, in document order
+    ///
+    /// Falls back to every `/Type /Page` object (by object number) when
+    /// the page tree can't be walked.
+    pub fn pages(&self) -> Vec<Page<'_>> {
+        let mut pages = Vec::new();
+        if let Some(Object::Reference(id, _)) =
+            self.catalog().and_then(|c| c.get(b"Pages".as_slice()))
+        {
+            self.collect_pages(*id, None, &mut pages, 0);
+        }
+        if pages.is_empty() {
+            let mut ids: Vec<u32> = self
+                .objects
+                .iter()
+                .filter(|(_, object)| object.as_dict().and_then(dict_type) == Some(b"Page"))
+                .map(|(id, _)| *id)
+                .collect();
+            ids.sort_unstable();
+            for id in ids {
+                if let Some(dict) = self.get(id).and_then(Object::as_dict) {
+                    let resources = self.get_entry(dict, b"Resources").and_then(Object::as_dict);
+                    pages.push(Page {
+                        id,
+                        dict,
+                        resources,
+                    });
+                }
+            }
+        }
+        pages
+    }
+
+    fn collect_pages<'a>(
+        &'a self,
+        id: u32,
+        inherited: Option<&'a Dictionary>,
+        pages: &mut Vec<Page<'a>>,
+        depth: usize,
+    ) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let Some(dict) = self.get(id).and_then(Object::as_dict) else {
+            return;
+        };
+        let resources = self
+            .get_entry(dict, b"Resources")
+            .and_then(Object::as_dict)
+            .or(inherited);
+
+        match self.get_entry(dict, b"Kids").and_then(Object::as_array) {
+            Some(kids) => {
+                for kid in kids {
+                    if let Object::Reference(kid_id, _) = kid {
+                        self.collect_pages(*kid_id, resources, pages, depth + 1);
+                    }
+                }
+            }
+            None => pages.push(Page {
+                id,
+                dict,
+                resources,
+            }),
+        }
+    }
+
+    /// Page count from the root of the page tree, or by walking it
+    pub fn page_count(&self) -> usize {
+        let root_count = self
+            .catalog()
+            .and_then(|catalog| self.get_entry(catalog, b"Pages"))
+            .and_then(Object::as_dict)
+            .and_then(|pages| self.get_entry(pages, b"Count"))
+            .and_then(Object::as_i64);
+        match root_count {
+            Some(count) if count >= 0 => count as usize,
+            _ => self.pages().len(),
+        }
+    }
+
+    /// Decoded content stream(s) of a page, concatenated
+    pub fn page_content(&self, page: &Page<'_>) -> Vec<u8> {
+        let streams: Vec<&Object> = match page.dict.get(b"Contents".as_slice()) {
+            Some(Object::Array(items)) => items.iter().map(|o| self.resolve(o)).collect(),
+            Some(object) => match self.resolve(object) {
+                Object::Array(items) => items.iter().map(|o| self.resolve(o)).collect(),
+                resolved => vec![resolved],
+            },
+            None => Vec::new(),
+        };
+
+        let mut content = Vec::new();
+        for stream in streams {
+            if let Object::Stream(stream) = stream {
+                if let Ok(data) = stream.decode() {
+                    content.extend_from_slice(&data);
+                    content.push(b'\n');
+                }
+            }
+        }
+        content
+    }
+}
+
+/// The `/Type` of a dictionary
+pub fn dict_type(dict: &Dictionary) -> Option<&[u8]> {
+    dict.get(b"Type".as_slice()).and_then(Object::as_name)
+}
+
+pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+/// Read the `N G` in front of an `obj` keyword
+fn object_number_before(bytes: &[u8], keyword: usize) -> Option<u32> {
+    let mut pos = keyword;
+    let read_number = |pos: &mut usize| -> Option<u64> {
+        let end = *pos;
+        while *pos > 0 && bytes[*pos - 1].is_ascii_digit() {
+            *pos -= 1;
+        }
+        std::str::from_utf8(&bytes[*pos..end]).ok()?.parse().ok()
+    };
+    let skip_whitespace = |pos: &mut usize| -> bool {
+        let end = *pos;
+        while *pos > 0 && is_whitespace(bytes[*pos - 1]) {
+            *pos -= 1;
+        }
+        *pos < end
+    };
+
+    if !skip_whitespace(&mut pos) {
+        return None;
+    }
+    read_number(&mut pos)?;
+    if !skip_whitespace(&mut pos) {
+        return None;
+    }
+    let number = read_number(&mut pos)?;
+    // The object number must start a token
+    if pos > 0 && is_regular(bytes[pos - 1]) {
+        return None;
+    }
+    u32::try_from(number).ok()
+}
+
+/// Start and end of the stream data following a dictionary, if any
+fn stream_bounds(bytes: &[u8], after_dict: usize, dict: &Dictionary) -> Option<(usize, usize)> {
+    let mut pos = after_dict;
+    while bytes.get(pos).is_some_and(|&c| is_whitespace(c)) {
+        pos += 1;
+    }
+    if !bytes[pos..].starts_with(b"stream") {
+        return None;
+    }
+    pos += b"stream".len();
+    // The keyword is followed by CRLF or LF before the data
+    if bytes.get(pos) == Some(&b'\r') {
+        pos += 1;
+    }
+    if bytes.get(pos) == Some(&b'\n') {
+        pos += 1;
+    }
+
+    // Trust a direct /Length if `endstream` is where it says
+    if let Some(length) = dict.get(b"Length".as_slice()).and_then(|o| match o {
+        Object::Integer(length) => usize::try_from(*length).ok(),
+        _ => None,
+    }) {
+        let end = pos + length;
+        if let Some(tail) = bytes.get(end..) {
+            let trimmed = tail
+                .iter()
+                .position(|&c| !is_whitespace(c))
+                .map(|skip| &tail[skip..]);
+            if trimmed.is_some_and(|t| t.starts_with(b"endstream")) {
+                return Some((pos, end));
+            }
+        }
+    }
+
+    let mut end = pos + find(&bytes[pos..], b"endstream")?;
+    if end > pos && bytes[end - 1] == b'\n' {
+        end -= 1;
+    }
+    if end > pos && bytes[end - 1] == b'\r' {
+        end -= 1;
+    }
+    Some((pos, end))
+}
+
+/// Find the catalog: the trailer's (or cross-reference stream's) `/Root`,
+/// falling back to any `/Type /Catalog` object
+fn find_root(bytes: &[u8], document: &Document) -> Option<u32> {
+    let trailer_root = rfind(bytes, b"trailer").and_then(|pos| {
+        match Lexer::new(bytes, pos + b"trailer".len()).parse_object() {
+            Some(Object::Dictionary(dict)) => match dict.get(b"Root".as_slice()) {
+                Some(Object::Reference(id, _)) => Some(*id),
+                _ => None,
+            },
+            _ => None,
+        }
+    });
+    if trailer_root.is_some() {
+        return trailer_root;
+    }
+
+    let xref_root = document.objects.values().find_map(|object| match object {
+        Object::Stream(stream) if dict_type(&stream.dict) == Some(b"XRef") => {
+            match stream.dict.get(b"Root".as_slice()) {
+                Some(Object::Reference(id, _)) => Some(*id),
+                _ => None,
+            }
+        }
+        _ => None,
+    });
+    if xref_root.is_some() {
+        return xref_root;
+    }
+
+    let mut catalogs: Vec<u32> = document
+        .objects
+        .iter()
+        .filter(|(_, object)| object.as_dict().and_then(dict_type) == Some(b"Catalog"))
+        .map(|(id, _)| *id)
+        .collect();
+    catalogs.sort_unstable();
+    catalogs.first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Object {
+        Lexer::new(text.as_bytes(), 0).parse_object().unwrap()
+    }
+
+    #[test]
+    fn test_parse_simple_objects() {
+        assert_eq!(parse("42"), Object::Integer(42));
+        assert_eq!(parse("-1.5"), Object::Real(-1.5));
+        assert_eq!(parse("/Type"), Object::Name(b"Type".to_vec()));
+        assert_eq!(parse("/A#20B"), Object::Name(b"A B".to_vec()));
+        assert_eq!(parse("true"), Object::Boolean(true));
+        assert_eq!(parse("null"), Object::Null);
+        assert_eq!(parse("12 0 R"), Object::Reference(12, 0));
+    }
+
+    #[test]
+    fn test_parse_strings() {
+        assert_eq!(
+            parse("(a (nested) \\) string)"),
+            Object::String(b"a (nested) ) string".to_vec())
+        );
+        assert_eq!(parse("(\\101\\n)"), Object::String(b"A\n".to_vec()));
+        assert_eq!(parse("<48656C6C6F>"), Object::String(b"Hello".to_vec()));
+        assert_eq!(parse("<4 8 6>"), Object::String(vec![0x48, 0x60]));
+    }
+
+    #[test]
+    fn test_parse_dictionary_and_array() {
+        let object = parse("<</Type/Pages/Kids[3 0 R 4 0 R]/Count 2>>");
+        let dict = object.as_dict().unwrap();
+        assert_eq!(dict_type(dict), Some(b"Pages".as_slice()));
+        assert_eq!(
+            dict.get(b"Kids".as_slice()).unwrap().as_array().unwrap(),
+            &[Object::Reference(3, 0), Object::Reference(4, 0)]
+        );
+        assert_eq!(dict.get(b"Count".as_slice()).unwrap().as_i64(), Some(2));
+    }
+
+    #[test]
+    fn test_content_lexer_reads_operators() {
+        let mut lexer = Lexer::content(b"BT /F1 12 Tf 0 0 1 RG (Hi) Tj ET");
+        let mut operators = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            if let Token::Operator(op) = token {
+                operators.push(String::from_utf8(op).unwrap());
+            }
+        }
+        assert_eq!(operators, ["BT", "Tf", "RG", "Tj", "ET"]);
+    }
+
+    #[test]
+    fn test_document_reads_objects_and_streams() {
+        let pdf = b"%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n\
+            2 0 obj\n<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n\
+            3 0 obj\n<< /Type /Page /Parent 2 0 R /Contents 4 0 R >>\nendobj\n\
+            4 0 obj\n<< /Length 8 >>\nstream\nBT ET\nq\nendstream\nendobj\n\
+            trailer\n<< /Root 1 0 R >>\n%%EOF\n";
+        let document = Document::parse(pdf).unwrap();
+
+        assert_eq!(document.root, Some(1));
+        assert_eq!(document.page_count(), 1);
+        let pages = document.pages();
+        assert_eq!(pages[0].id, 3);
+        // Streams are joined with a newline
+        assert_eq!(document.page_content(&pages[0]), b"BT ET\nq\n\n");
+    }
+
+    #[test]
+    fn test_later_object_stream_replaces_stale_object() {
+        let packed = b"5 0 6 16 << /V (new) >> << /V (only) >>";
+        let pdf = [
+            b"%PDF-1.5\n5 0 obj\n<< /V (old) >>\nendobj\n".as_slice(),
+            b"7 0 obj\n<< /Type /ObjStm /N 2 /First 8 /Length 39 >>\nstream\n",
+            packed,
+            b"\nendstream\nendobj\n",
+        ]
+        .concat();
+        let document = Document::parse(&pdf).unwrap();
+        let value = |id| {
+            let dict = document.get(id).and_then(Object::as_dict).unwrap();
+            dict.get(b"V".as_slice()).cloned()
+        };
+        assert_eq!(value(5), Some(Object::String(b"new".to_vec())));
+        assert_eq!(value(6), Some(Object::String(b"only".to_vec())));
+
+        // A top-level update after the object stream wins instead
+        let updated = [pdf.as_slice(), b"5 0 obj\n<< /V (newer) >>\nendobj\n"].concat();
+        let document = Document::parse(&updated).unwrap();
+        let dict = document.get(5).and_then(Object::as_dict).unwrap();
+        assert_eq!(dict.get(b"V".as_slice()), Some(&Object::String(b"newer".to_vec())));
+    }
+
+    #[test]
+    fn test_malformed_object_stream_entries_are_skipped() {
+        let object_stream = |id: u32, first: &str, packed: &str| {
+            format!(
+                "{} 0 obj\n<< /Type /ObjStm /N 4 /First {} /Length {} >>\nstream\n{}\nendstream\nendobj\n",
+                id,
+                first,
+                packed.len(),
+                packed
+            )
+        };
+        let header = "5 -3 -2 0 8 9223372036854775807 6 0 ";
+        let packed = format!("{}<< /V (ok) >>", header);
+        let pdf = [
+            "%PDF-1.5\n".to_string(),
+            object_stream(7, &header.len().to_string(), &packed),
+            object_stream(9, "-1", &packed),
+            object_stream(10, "18446744073709551616", &packed),
+        ]
+        .concat();
+
+        let document = Document::parse(pdf.as_bytes()).unwrap();
+        let dict = document.get(6).and_then(Object::as_dict).unwrap();
+        assert_eq!(dict.get(b"V".as_slice()), Some(&Object::String(b"ok".to_vec())));
+        assert!(document.get(5).is_none());
+        assert!(document.get(8).is_none());
+        assert!(document.get(u32::MAX - 1).is_none());
+    }
+
+    #[test]
+    fn test_stream_decode_rejects_unknown_filter() {
+        let mut dict = Dictionary::new();
+        dict.insert(b"Filter".to_vec(), Object::Name(b"DCTDecode".to_vec()));
+        let stream = Stream { dict, data: vec![] };
+        assert!(stream.decode().unwrap_err().contains("DCTDecode"));
+    }
+}
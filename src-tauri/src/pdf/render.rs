@@ -0,0 +1,262 @@
+//! Rasterize PDF pages to PNG for thumbnails
+//!
+//! Rendering shells out to Ghostscript, which TeX distributions usually
+//! bring along (MiKTeX ships it as `mgs`). Rendered pages are cached by
+//! path, modification time, page and DPI, so an unchanged PDF is only
+//! rendered once. Renders of older versions of a PDF are deleted when a
+//! page of its new version is rendered.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+
+use crate::compiler::pdflatex;
+use crate::workspace::get_cache_dir;
+
+/// Highest resolution a page may be rendered at
+pub const MAX_DPI: u32 = 600;
+
+/// Ghostscript executables, most common first
+const RENDERERS: [&str; 3] = ["gs", "gswin64c", "mgs"];
+
+/// First bytes of every PNG file
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Render `page` (1-based) of the PDF at `path` to PNG bytes
+pub fn render_page_png(path: &str, page: u32, dpi: u32) -> Result<Vec<u8>, String> {
+    let cache_dir = get_cache_dir()
+        .ok_or("Could not determine cache directory")?
+        .join("pages");
+    render_page_png_in(&cache_dir, Path::new(path), page, dpi)
+}
+
+/// Render a page, reusing an earlier render from `cache_dir` when possible
+pub fn render_page_png_in(
+    cache_dir: &Path,
+    path: &Path,
+    page: u32,
+    dpi: u32,
+) -> Result<Vec<u8>, String> {
+    if dpi == 0 || dpi > MAX_DPI {
+        return Err(format!("DPI must be between 1 and {}", MAX_DPI));
+    }
+    let cached = cache_path(cache_dir, path, page, dpi)?;
+    if let Ok(png) = fs::read(&cached) {
+        if png.starts_with(PNG_SIGNATURE) {
+            return Ok(png);
+        }
+    }
+
+    let pages = super::pdf_page_count(&path.to_string_lossy())?;
+    if page == 0 || page > pages {
+        return Err(format!(
+            "Page {} is out of bounds: the PDF has {} pages",
+            page, pages
+        ));
+    }
+
+    fs::create_dir_all(cache_dir)
+        .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    // Render beside the cache entry so a half-written file is never read back
+    let partial = cached.with_extension("png.partial");
+    let output = ghostscript_command(&find_renderer()?, path, &partial, page, dpi)
+        .output()
+        .map_err(|e| format!("Failed to run Ghostscript: {}", e))?;
+    if !output.status.success() {
+        let _ = fs::remove_file(&partial);
+        return Err(format!(
+            "Ghostscript failed to render page {}: {}",
+            page,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let png = fs::read(&partial).map_err(|e| format!("Failed to read rendered page: {}", e))?;
+    if !png.starts_with(PNG_SIGNATURE) {
+        let _ = fs::remove_file(&partial);
+        return Err("Ghostscript did not produce a PNG".to_string());
+    }
+    fs::rename(&partial, &cached).map_err(|e| format!("Failed to cache rendered page: {}", e))?;
+    evict_stale_renders(cache_dir, path);
+    Ok(png)
+}
+
+/// Where a render of `page` at `dpi` is cached
+///
+/// The name includes the file's modification time, so rebuilding the PDF
+/// invalidates its thumbnails.
+fn cache_path(cache_dir: &Path, path: &Path, page: u32, dpi: u32) -> Result<PathBuf, String> {
+    let (_, version) = cache_prefixes(path)?;
+    Ok(cache_dir.join(format!("{}{}-{}.png", version, page, dpi)))
+}
+
+/// Delete cached renders of earlier versions of the PDF at `path`
+fn evict_stale_renders(cache_dir: &Path, path: &Path) {
+    let (Ok((file, version)), Ok(entries)) = (cache_prefixes(path), fs::read_dir(cache_dir)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(&file) && !name.starts_with(&version) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Cache file name prefixes for the PDF at `path`: one shared by all its
+/// renders, and one for renders of its current version
+fn cache_prefixes(path: &Path) -> Result<(String, String), String> {
+    let modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("Failed to read PDF metadata: {}", e))?;
+    let mtime = modified
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    let file = format!("{:016x}-", hasher.finish());
+    let version = format!("{}{:x}-", file, mtime);
+    Ok((file, version))
+}
+
+/// The first Ghostscript executable that can be found
+fn find_renderer() -> Result<String, String> {
+    RENDERERS
+        .iter()
+        .find_map(|binary| pdflatex::find_tool_command(binary))
+        .ok_or_else(|| "Ghostscript was not found; install it to render page previews".to_string())
+}
+
+/// Ghostscript invocation rendering one page of `pdf` to `png`
+fn ghostscript_command(gs: &str, pdf: &Path, png: &Path, page: u32, dpi: u32) -> Command {
+    let mut cmd = Command::new(gs);
+    cmd.args([
+        "-dSAFER",
+        "-dBATCH",
+        "-dNOPAUSE",
+        "-dQUIET",
+        "-sDEVICE=png16m",
+        "-dTextAlphaBits=4",
+        "-dGraphicsAlphaBits=4",
+    ])
+    .arg(format!("-r{}", dpi))
+    .arg(format!("-dFirstPage={}", page))
+    .arg(format!("-dLastPage={}", page))
+    .arg(format!("-sOutputFile={}", png.to_string_lossy()))
+    .arg(pdf);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::text_pdf;
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fixture(temp_dir: &TempDir) -> PathBuf {
+        let path = temp_dir.path().join("resume.pdf");
+        fs::write(&path, text_pdf(&["One", "Two", "Three"])).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_cache_key_depends_on_page_and_dpi() {
+        let temp_dir = TempDir::new().unwrap();
+        let pdf = fixture(&temp_dir);
+        let key = |page, dpi| cache_path(temp_dir.path(), &pdf, page, dpi).unwrap();
+
+        assert_eq!(key(1, 96), key(1, 96));
+        assert_ne!(key(1, 96), key(2, 96));
+        assert_ne!(key(1, 96), key(1, 150));
+    }
+
+    #[test]
+    fn test_cached_render_is_reused() {
+        let temp_dir = TempDir::new().unwrap();
+        let pdf = fixture(&temp_dir);
+        let cache_dir = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache_dir).unwrap();
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(b"cached");
+        fs::write(cache_path(&cache_dir, &pdf, 2, 72).unwrap(), &png).unwrap();
+
+        assert_eq!(render_page_png_in(&cache_dir, &pdf, 2, 72).unwrap(), png);
+    }
+
+    #[test]
+    fn test_cache_is_checked_before_parsing_pdf() {
+        let temp_dir = TempDir::new().unwrap();
+        let pdf = temp_dir.path().join("resume.pdf");
+        fs::write(&pdf, b"not a pdf").unwrap();
+        let png = PNG_SIGNATURE.to_vec();
+        fs::write(cache_path(temp_dir.path(), &pdf, 1, 72).unwrap(), &png).unwrap();
+
+        assert_eq!(
+            render_page_png_in(temp_dir.path(), &pdf, 1, 72).unwrap(),
+            png
+        );
+    }
+
+    #[test]
+    fn test_evict_stale_renders_keeps_current_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let pdf = fixture(&temp_dir);
+        let cache_dir = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache_dir).unwrap();
+        let (file, _) = cache_prefixes(&pdf).unwrap();
+        let stale = cache_dir.join(format!("{}0-1-72.png", file));
+        let current = cache_path(&cache_dir, &pdf, 1, 72).unwrap();
+        let other_pdf = cache_dir.join("0000000000000000-0-1-72.png");
+        for path in [&stale, &current, &other_pdf] {
+            fs::write(path, PNG_SIGNATURE).unwrap();
+        }
+
+        evict_stale_renders(&cache_dir, &pdf);
+        assert!(!stale.exists());
+        assert!(current.exists());
+        assert!(other_pdf.exists());
+    }
+
+    #[test]
+    fn test_rejects_bad_page_and_dpi() {
+        let temp_dir = TempDir::new().unwrap();
+        let pdf = fixture(&temp_dir);
+
+        let err = render_page_png_in(temp_dir.path(), &pdf, 4, 72).unwrap_err();
+        assert!(err.contains("out of bounds"), "{}", err);
+        assert!(render_page_png_in(temp_dir.path(), &pdf, 0, 72).is_err());
+        assert!(render_page_png_in(temp_dir.path(), &pdf, 1, MAX_DPI + 1).is_err());
+    }
+
+    #[test]
+    fn test_ghostscript_command_selects_one_page() {
+        let cmd = ghostscript_command("gs", Path::new("in.pdf"), Path::new("out.png"), 3, 150);
+        let args: Vec<_> = cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect();
+        assert!(args.contains(&"-r150".to_string()));
+        assert!(args.contains(&"-dFirstPage=3".to_string()));
+        assert!(args.contains(&"-dLastPage=3".to_string()));
+        assert_eq!(args.last().map(String::as_str), Some("in.pdf"));
+    }
+
+    // ============ Integration test (only runs if Ghostscript is available) ============
+
+    #[test]
+    fn test_render_first_page_if_ghostscript_available() {
+        if find_renderer().is_err() {
+            return;
+        }
+        let temp_dir = TempDir::new().unwrap();
+        let pdf = fixture(&temp_dir);
+
+        let png = render_page_png_in(&temp_dir.path().join("cache"), &pdf, 1, 72).unwrap();
+        assert!(png.starts_with(PNG_SIGNATURE));
+    }
+}
@@ -0,0 +1,562 @@
+//! Text layer extraction from PDF content streams
+//!
+//! Character codes are mapped through the font's `/ToUnicode` CMap when
+//! present, then its `/Encoding /Differences` glyph names, then a built-in
+//! fallback. Line breaks and spaces are inferred from text positioning.
+
+use std::collections::HashMap;
+
+use super::parser::{Dictionary, Document, Lexer, Object, Page, Token};
+
+/// TJ adjustments (thousandths of an em) wider than this read as a space
+const SPACE_ADJUSTMENT: f64 = -200.0;
+
+/// Vertical movement (text space units) that starts a new line
+const LINE_THRESHOLD: f64 = 1.0;
+
+/// Extract the text of every page, one string per page
+pub fn extract_pages_text(document: &Document) -> Vec<String> {
+    document
+        .pages()
+        .iter()
+        .map(|page| extract_page_text(document, page))
+        .collect()
+}
+
+/// Extract the text of a single page
+pub fn extract_page_text(document: &Document, page: &Page<'_>) -> String {
+    let fonts = load_fonts(document, page.resources);
+    let content = document.page_content(page);
+    let mut writer = TextWriter::default();
+
+    let mut lexer = Lexer::content(&content);
+    let mut operands: Vec<Object> = Vec::new();
+    let mut font: Option<&Font> = None;
+    while let Some(token) = lexer.next_token() {
+        let operator = match token {
+            Token::Object(object) => {
+                operands.push(object);
+                continue;
+            }
+            Token::Operator(operator) => operator,
+        };
+        let number = |index: usize| operands.get(index).and_then(Object::as_f64).unwrap_or(0.0);
+
+        match operator.as_slice() {
+            b"BT" => writer.begin_text(),
+            b"Tf" => {
+                font = operands
+                    .first()
+                    .and_then(Object::as_name)
+                    .and_then(|name| fonts.get(name));
+            }
+            b"TL" => writer.leading = number(0),
+            b"Td" => writer.move_by(number(0), number(1)),
+            b"TD" => {
+                writer.leading = -number(1);
+                writer.move_by(number(0), number(1));
+            }
+            b"Tm" => writer.move_to(number(5)),
+            b"T*" => writer.next_line(),
+            b"Tj" => {
+                if let Some(Object::String(bytes)) = operands.first() {
+                    writer.show(&decode_string(font, bytes));
+                }
+            }
+            b"'" | b"\"" => {
+                writer.next_line();
+                if let Some(Object::String(bytes)) = operands.last() {
+                    writer.show(&decode_string(font, bytes));
+                }
+            }
+            b"TJ" => {
+                for item in operands.first().and_then(Object::as_array).unwrap_or(&[]) {
+                    match item {
+                        Object::String(bytes) => writer.show(&decode_string(font, bytes)),
+                        other => {
+                            if other.as_f64().is_some_and(|n| n < SPACE_ADJUSTMENT) {
+                                writer.space();
+                            }
+                        }
+                    }
+                }
+            }
+            b"ID" => lexer.skip_inline_image(),
+            _ => {}
+        }
+        operands.clear();
+    }
+    writer.finish()
+}
+
+/// Assembles shown text into lines
+#[derive(Default)]
+struct TextWriter {
+    out: String,
+    /// Current text line position
+    y: f64,
+    leading: f64,
+    /// Position of the last shown text
+    shown_y: Option<f64>,
+    /// Whether the position moved since text was last shown
+    moved: bool,
+}
+
+impl TextWriter {
+    fn begin_text(&mut self) {
+        self.y = 0.0;
+        self.moved = true;
+    }
+
+    fn move_by(&mut self, _tx: f64, ty: f64) {
+        self.y += ty;
+        self.moved = true;
+    }
+
+    fn move_to(&mut self, y: f64) {
+        self.y = y;
+        self.moved = true;
+    }
+
+    fn next_line(&mut self) {
+        self.y -= if self.leading == 0.0 {
+            1.0 + LINE_THRESHOLD
+        } else {
+            self.leading
+        };
+        self.moved = true;
+    }
+
+    fn space(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with(char::is_whitespace) {
+            self.out.push(' ');
+        }
+    }
+
+    fn show(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(shown_y) = self.shown_y {
+            if (self.y - shown_y).abs() > LINE_THRESHOLD {
+                let trimmed = self.out.trim_end_matches(' ').len();
+                self.out.truncate(trimmed);
+                self.out.push('\n');
+            } else if self.moved {
+                self.space();
+            }
+        }
+        self.out.push_str(text);
+        self.shown_y = Some(self.y);
+        self.moved = false;
+    }
+
+    fn finish(self) -> String {
+        self.out
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+}
+
+/// How a font's character codes map to text
+#[derive(Debug, Default)]
+struct Font {
+    /// Codes are two bytes wide (Type0/CID fonts)
+    two_byte: bool,
+    to_unicode: HashMap<u32, String>,
+    differences: HashMap<u8, char>,
+    /// Computer Modern fonts use the OT1 layout when no encoding is given
+    ot1: bool,
+}
+
+/// Load the fonts named in a page's resources
+fn load_fonts(document: &Document, resources: Option<&Dictionary>) -> HashMap<Vec<u8>, Font> {
+    let Some(font_dict) = resources
+        .and_then(|r| document.get_entry(r, b"Font"))
+        .and_then(Object::as_dict)
+    else {
+        return HashMap::new();
+    };
+
+    font_dict
+        .iter()
+        .filter_map(|(name, font)| {
+            let font = document.resolve(font).as_dict()?;
+            Some((name.clone(), load_font(document, font)))
+        })
+        .collect()
+}
+
+fn load_font(document: &Document, font: &Dictionary) -> Font {
+    let two_byte = font.get(b"Subtype".as_slice()).and_then(Object::as_name) == Some(b"Type0");
+
+    let to_unicode = match document.get_entry(font, b"ToUnicode") {
+        Some(Object::Stream(stream)) => stream
+            .decode()
+            .map(|cmap| parse_to_unicode(&cmap))
+            .unwrap_or_default(),
+        _ => HashMap::new(),
+    };
+
+    let encoding = document.get_entry(font, b"Encoding");
+    let differences = encoding
+        .and_then(Object::as_dict)
+        .and_then(|e| document.get_entry(e, b"Differences"))
+        .and_then(Object::as_array)
+        .map(parse_differences)
+        .unwrap_or_default();
+
+    let base_font = font
+        .get(b"BaseFont".as_slice())
+        .and_then(Object::as_name)
+        .unwrap_or_default();
+    // Drop the subset tag, e.g. "ABCDEF+CMR10"
+    let base_font = match base_font.iter().position(|&c| c == b'+') {
+        Some(plus) => &base_font[plus + 1..],
+        None => base_font,
+    };
+
+    Font {
+        two_byte,
+        to_unicode,
+        differences,
+        ot1: encoding.is_none() && base_font.starts_with(b"CM"),
+    }
+}
+
+/// Parse the `bfchar`/`bfrange` mappings of a ToUnicode CMap
+fn parse_to_unicode(cmap: &[u8]) -> HashMap<u32, String> {
+    let mut map = HashMap::new();
+    let mut lexer = Lexer::content(cmap);
+    let mut section: &[u8] = b"";
+    let mut pending: Vec<Object> = Vec::new();
+
+    while let Some(token) = lexer.next_token() {
+        match token {
+            Token::Operator(op) => {
+                match op.as_slice() {
+                    b"beginbfchar" => section = b"bfchar",
+                    b"beginbfrange" => section = b"bfrange",
+                    b"endbfchar" | b"endbfrange" => section = b"",
+                    _ => {}
+                }
+                pending.clear();
+            }
+            Token::Object(object) if !section.is_empty() => {
+                pending.push(object);
+                let complete = (section == b"bfchar" && pending.len() == 2)
+                    || (section == b"bfrange" && pending.len() == 3);
+                if !complete {
+                    continue;
+                }
+                let code = |object: &Object| match object {
+                    Object::String(bytes) => Some(code_value(bytes)),
+                    _ => None,
+                };
+                if section == b"bfchar" {
+                    if let (Some(src), Object::String(dst)) = (code(&pending[0]), &pending[1]) {
+                        map.insert(src, utf16_be(dst));
+                    }
+                } else if let (Some(lo), Some(hi)) = (code(&pending[0]), code(&pending[1])) {
+                    match &pending[2] {
+                        Object::String(dst) => {
+                            let base = utf16_be(dst);
+                            for (offset, src) in (lo..=hi.min(lo + 0xFFFF)).enumerate() {
+                                map.insert(src, offset_last_char(&base, offset as u32));
+                            }
+                        }
+                        Object::Array(items) => {
+                            for (src, item) in (lo..=hi).zip(items) {
+                                if let Object::String(dst) = item {
+                                    map.insert(src, utf16_be(dst));
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                pending.clear();
+            }
+            Token::Object(_) => {}
+        }
+    }
+    map
+}
+
+fn code_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |acc, &b| acc << 8 | u32::from(b))
+}
+
+fn utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks(2)
+        .map(|pair| u16::from(pair[0]) << 8 | u16::from(*pair.get(1).unwrap_or(&0)))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// `base` with its last character advanced by `offset` (bfrange semantics)
+fn offset_last_char(base: &str, offset: u32) -> String {
+    let mut chars: Vec<char> = base.chars().collect();
+    if let Some(last) = chars.last_mut() {
+        if let Some(shifted) = char::from_u32(u32::from(*last) + offset) {
+            *last = shifted;
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Parse an `/Encoding /Differences` array into code → character
+fn parse_differences(items: &[Object]) -> HashMap<u8, char> {
+    let mut map = HashMap::new();
+    let mut code: i64 = 0;
+    for item in items {
+        match item {
+            Object::Integer(start) => code = *start,
+            Object::Name(glyph) => {
+                if let (Ok(byte), Some(c)) = (u8::try_from(code), glyph_to_char(glyph)) {
+                    map.insert(byte, c);
+                }
+                code += 1;
+            }
+            _ => {}
+        }
+    }
+    map
+}
+
+/// Decode a shown string to text using the current font
+fn decode_string(font: Option<&Font>, bytes: &[u8]) -> String {
+    let Some(font) = font else {
+        return bytes.iter().map(|&b| fallback_char(b, false)).collect();
+    };
+
+    let width = if font.two_byte { 2 } else { 1 };
+    let mut text = String::new();
+    for chunk in bytes.chunks(width) {
+        let code = code_value(chunk);
+        if let Some(mapped) = font.to_unicode.get(&code) {
+            text.push_str(mapped);
+        } else if font.two_byte {
+            // CID fonts without a ToUnicode map can't be decoded
+            continue;
+        } else if let Some(&c) = font.differences.get(&chunk[0]) {
+            text.push(c);
+        } else {
+            text.push(fallback_char(chunk[0], font.ot1));
+        }
+    }
+    text.replace('\0', "")
+}
+
+/// Map a byte without encoding information, expanding TeX ligatures
+fn fallback_char(byte: u8, ot1: bool) -> char {
+    // OT1 and T1 put the ff/fi/fl/ffi/ffl ligatures at different codes
+    let ligature = match (byte, ot1) {
+        (0x0B, true) | (0x1B, false) => Some('\u{FB00}'),
+        (0x0C, true) | (0x1C, false) => Some('\u{FB01}'),
+        (0x0D, true) | (0x1D, false) => Some('\u{FB02}'),
+        (0x0E, true) | (0x1E, false) => Some('\u{FB03}'),
+        (0x0F, true) | (0x1F, false) => Some('\u{FB04}'),
+        _ => None,
+    };
+    if let Some(c) = ligature {
+        return c;
+    }
+    if ot1 {
+        match byte {
+            0x10 => return 'ı',
+            0x19 => return 'ß',
+            0x1A => return 'æ',
+            0x1B => return 'œ',
+            0x1C => return 'ø',
+            b'"' => return '”',
+            b'\\' => return '“',
+            b'{' => return '–',
+            b'|' => return '—',
+            b'\'' => return '’',
+            b'`' => return '‘',
+            _ => {}
+        }
+    }
+    match byte {
+        0x20..=0x7E | 0xA0..=0xFF => char::from(byte),
+        _ => '\0',
+    }
+}
+
+/// Accented letters by glyph name suffix: (suffix, bases, composed)
+const ACCENTS: [(&str, &str, &str); 8] = [
+    ("acute", "aeiouyAEIOUYcnszCNSZ", "áéíóúýÁÉÍÓÚÝćńśźĆŃŚŹ"),
+    ("grave", "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+    ("circumflex", "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+    ("dieresis", "aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+    ("tilde", "anoANO", "ãñõÃÑÕ"),
+    ("cedilla", "cC", "çÇ"),
+    ("ring", "aAuU", "åÅůŮ"),
+    ("caron", "cdenrstzCDENRSTZ", "čďěňřšťžČĎĚŇŘŠŤŽ"),
+];
+
+/// Named glyphs that aren't a plain letter
+const GLYPHS: [(&str, char); 62] = [
+    ("space", ' '),
+    ("exclam", '!'),
+    ("quotedbl", '"'),
+    ("numbersign", '#'),
+    ("dollar", '$'),
+    ("percent", '%'),
+    ("ampersand", '&'),
+    ("quotesingle", '\''),
+    ("quoteright", '’'),
+    ("quoteleft", '‘'),
+    ("quotedblleft", '“'),
+    ("quotedblright", '”'),
+    ("parenleft", '('),
+    ("parenright", ')'),
+    ("asterisk", '*'),
+    ("plus", '+'),
+    ("comma", ','),
+    ("hyphen", '-'),
+    ("period", '.'),
+    ("slash", '/'),
+    ("zero", '0'),
+    ("one", '1'),
+    ("two", '2'),
+    ("three", '3'),
+    ("four", '4'),
+    ("five", '5'),
+    ("six", '6'),
+    ("seven", '7'),
+    ("eight", '8'),
+    ("nine", '9'),
+    ("colon", ':'),
+    ("semicolon", ';'),
+    ("less", '<'),
+    ("equal", '='),
+    ("greater", '>'),
+    ("question", '?'),
+    ("at", '@'),
+    ("bracketleft", '['),
+    ("backslash", '\\'),
+    ("bracketright", ']'),
+    ("underscore", '_'),
+    ("braceleft", '{'),
+    ("bar", '|'),
+    ("braceright", '}'),
+    ("endash", '–'),
+    ("emdash", '—'),
+    ("bullet", '•'),
+    ("periodcentered", '·'),
+    ("section", '§'),
+    ("copyright", '©'),
+    ("registered", '®'),
+    ("germandbls", 'ß'),
+    ("ae", 'æ'),
+    ("AE", 'Æ'),
+    ("oe", 'œ'),
+    ("OE", 'Œ'),
+    ("oslash", 'ø'),
+    ("Oslash", 'Ø'),
+    ("lslash", 'ł'),
+    ("Lslash", 'Ł'),
+    ("dotlessi", 'ı'),
+    ("visiblespace", '␣'),
+];
+
+/// Map a PostScript glyph name to its character
+fn glyph_to_char(name: &[u8]) -> Option<char> {
+    let name = std::str::from_utf8(name).ok()?;
+    if name.len() == 1 && name.as_bytes()[0].is_ascii_alphabetic() {
+        return name.chars().next();
+    }
+    if let Some(hex) = name.strip_prefix("uni").filter(|hex| hex.len() == 4) {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+    match name {
+        "ff" => return Some('\u{FB00}'),
+        "fi" => return Some('\u{FB01}'),
+        "fl" => return Some('\u{FB02}'),
+        "ffi" => return Some('\u{FB03}'),
+        "ffl" => return Some('\u{FB04}'),
+        _ => {}
+    }
+    if let Some((_, c)) = GLYPHS.iter().find(|(glyph, _)| *glyph == name) {
+        return Some(*c);
+    }
+    ACCENTS.iter().find_map(|(suffix, bases, composed)| {
+        let base = name.strip_suffix(suffix)?;
+        let index = bases
+            .chars()
+            .position(|b| base.len() == 1 && base.starts_with(b))?;
+        composed.chars().nth(index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyph_names() {
+        assert_eq!(glyph_to_char(b"a"), Some('a'));
+        assert_eq!(glyph_to_char(b"endash"), Some('–'));
+        assert_eq!(glyph_to_char(b"eacute"), Some('é'));
+        assert_eq!(glyph_to_char(b"Udieresis"), Some('Ü'));
+        assert_eq!(glyph_to_char(b"uni2022"), Some('•'));
+        assert_eq!(glyph_to_char(b"fi"), Some('\u{FB01}'));
+        assert_eq!(glyph_to_char(b"notaglyph"), None);
+    }
+
+    #[test]
+    fn test_parse_to_unicode_cmap() {
+        let cmap = b"begincmap\n1 begincodespacerange <0000> <FFFF> endcodespacerange\n\
+            2 beginbfchar\n<0003> <0020>\n<0024> <0041>\nendbfchar\n\
+            1 beginbfrange\n<0044> <0046> <0061>\nendbfrange\nendcmap";
+        let map = parse_to_unicode(cmap);
+        assert_eq!(map.get(&0x03).map(String::as_str), Some(" "));
+        assert_eq!(map.get(&0x24).map(String::as_str), Some("A"));
+        assert_eq!(map.get(&0x46).map(String::as_str), Some("c"));
+    }
+
+    #[test]
+    fn test_parse_differences() {
+        let items = [
+            Object::Integer(12),
+            Object::Name(b"fi".to_vec()),
+            Object::Name(b"fl".to_vec()),
+            Object::Integer(39),
+            Object::Name(b"quoteright".to_vec()),
+        ];
+        let map = parse_differences(&items);
+        assert_eq!(map.get(&12), Some(&'\u{FB01}'));
+        assert_eq!(map.get(&13), Some(&'\u{FB02}'));
+        assert_eq!(map.get(&39), Some(&'’'));
+    }
+
+    #[test]
+    fn test_ot1_fallback() {
+        let font = Font {
+            ot1: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            decode_string(Some(&font), b"\\Hi\" 2019{20"),
+            "“Hi” 2019–20"
+        );
+    }
+
+    #[test]
+    fn test_two_byte_font_uses_cmap() {
+        let mut font = Font {
+            two_byte: true,
+            ..Default::default()
+        };
+        font.to_unicode.insert(0x0024, "A".to_string());
+        font.to_unicode.insert(0x0025, "B".to_string());
+        assert_eq!(decode_string(Some(&font), &[0x00, 0x24, 0x00, 0x25]), "AB");
+    }
+}
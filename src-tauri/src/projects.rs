@@ -0,0 +1,406 @@
+//! Resume projects
+//!
+//! Each project is a folder in `<workspace_root>/projects/` holding its
+//! sources and a `project.json` with metadata.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::file_ops::{is_tex_file, read_file};
+use crate::latex::strip_comments;
+use crate::workspace::get_projects_dir;
+
+/// Name of the metadata file in each project folder
+pub const PROJECT_FILE: &str = "project.json";
+
+/// Main file created for a new project
+pub const DEFAULT_MAIN_FILE: &str = "main.tex";
+
+/// Content of the main file of a new, empty project
+const EMPTY_MAIN_TEX: &str = "\\documentclass{article}\n\n\\begin{document}\n\n\\end{document}\n";
+
+/// Metadata stored in `project.json`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProjectMetadata {
+    /// Name shown in the UI
+    pub display_name: String,
+    /// Creation time in milliseconds since the Unix epoch
+    pub created: u64,
+    /// Main .tex file, relative to the project folder
+    pub main_file: String,
+    /// Name of the PDF builds deliver, e.g. `Jane_Doe_Resume`; `None` names
+    /// it after the main file
+    #[serde(default)]
+    pub output_name: Option<String>,
+}
+
+/// A project folder and its metadata
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Project {
+    /// Folder name, which identifies the project
+    pub name: String,
+    pub path: String,
+    #[serde(flatten)]
+    pub metadata: ProjectMetadata,
+}
+
+impl Project {
+    /// Absolute path of the project's main .tex file
+    pub fn main_path(&self) -> PathBuf {
+        Path::new(&self.path).join(&self.metadata.main_file)
+    }
+}
+
+/// Create a new project with an empty main file
+pub fn create_project(name: &str) -> Result<Project, String> {
+    let dir = get_projects_dir().ok_or("Could not determine projects directory")?;
+    create_project_in(&dir, name, now_millis())
+}
+
+/// List the projects in the workspace
+pub fn list_projects() -> Result<Vec<Project>, String> {
+    let dir = get_projects_dir().ok_or("Could not determine projects directory")?;
+    list_projects_in(&dir)
+}
+
+/// Load a project from the workspace by name
+pub fn get_project(name: &str) -> Result<Project, String> {
+    let dir = get_projects_dir().ok_or("Could not determine projects directory")?;
+    validate_project_name(name)?;
+    load_project(&dir.join(name))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Check that a project name is usable as a single folder name
+fn validate_project_name(name: &str) -> Result<(), String> {
+    const RESERVED: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+    if name.trim().is_empty() || name == "." || name == ".." {
+        return Err("Project name cannot be empty".to_string());
+    }
+    if name.contains(&RESERVED[..]) || name.chars().any(|c| c.is_control()) {
+        return Err(format!(
+            "Project name contains invalid characters: {}",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Create a new project whose main file starts out as `main_tex`
+pub fn create_project_with(name: &str, main_tex: &str) -> Result<Project, String> {
+    let dir = get_projects_dir().ok_or("Could not determine projects directory")?;
+    create_project_with_in(&dir, name, now_millis(), main_tex)
+}
+
+/// Create an empty project in `dir`
+pub fn create_project_in(dir: &Path, name: &str, created: u64) -> Result<Project, String> {
+    create_project_with_in(dir, name, created, EMPTY_MAIN_TEX)
+}
+
+/// Create the folder, `project.json` and main file of a project in `dir`
+pub fn create_project_with_in(
+    dir: &Path,
+    name: &str,
+    created: u64,
+    main_tex: &str,
+) -> Result<Project, String> {
+    validate_project_name(name)?;
+    let path = dir.join(name);
+    if path.exists() {
+        return Err(format!("Project already exists: {}", name));
+    }
+    fs::create_dir_all(&path).map_err(|e| format!("Failed to create project: {}", e))?;
+
+    let metadata = ProjectMetadata {
+        display_name: name.to_string(),
+        created,
+        main_file: DEFAULT_MAIN_FILE.to_string(),
+        output_name: None,
+    };
+    save_metadata(&path, &metadata)?;
+    fs::write(path.join(DEFAULT_MAIN_FILE), main_tex)
+        .map_err(|e| format!("Failed to create project: {}", e))?;
+
+    load_project(&path)
+}
+
+/// Write a project's `project.json`
+pub fn save_metadata(path: &Path, metadata: &ProjectMetadata) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(metadata)
+        .map_err(|e| format!("Failed to serialize project: {}", e))?;
+    fs::write(path.join(PROJECT_FILE), json)
+        .map_err(|e| format!("Failed to write project file: {}", e))
+}
+
+/// Load the project in folder `path`
+pub fn load_project(path: &Path) -> Result<Project, String> {
+    let content = fs::read_to_string(path.join(PROJECT_FILE))
+        .map_err(|e| format!("Failed to read project file: {}", e))?;
+    let metadata: ProjectMetadata = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse project file: {}", e))?;
+
+    Ok(Project {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        metadata,
+    })
+}
+
+/// Set the name of the PDF a workspace project's builds deliver
+pub fn set_output_name(name: &str, output_name: Option<String>) -> Result<Project, String> {
+    let mut project = get_project(name)?;
+    project.metadata.output_name = output_name.filter(|n| !n.trim().is_empty());
+    save_metadata(Path::new(&project.path), &project.metadata)?;
+    Ok(project)
+}
+
+/// The project `tex_path` belongs to: one whose folder, or the folder
+/// above (for sources in e.g. `sections/`), has a `project.json`
+pub fn project_for(tex_path: &Path) -> Option<Project> {
+    tex_path
+        .ancestors()
+        .skip(1)
+        .take(2)
+        .find_map(|dir| load_project(dir).ok())
+}
+
+/// List the projects in `dir`, sorted by name
+///
+/// Folders without a readable `project.json` aren't projects and are skipped.
+pub fn list_projects_in(dir: &Path) -> Result<Vec<Project>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read projects: {}", e))?;
+
+    let mut projects: Vec<Project> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| load_project(&path).ok())
+        .collect();
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(projects)
+}
+
+/// Whether `source` is a complete document rather than a fragment
+/// pulled in with `\input` or `\include`
+pub fn is_main_document(source: &str) -> bool {
+    let source = strip_comments(source);
+    source.contains("\\documentclass") && source.contains("\\begin{document}")
+}
+
+/// Find the main file among the `.tex` files directly in `dir`
+///
+/// If several files are complete documents, `main.tex` wins, then the
+/// first by name.
+pub fn detect_main(dir: &Path) -> Option<PathBuf> {
+    let mut candidates: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_tex_file(path))
+        .filter(|path| read_file(path).is_ok_and(|source| is_main_document(&source)))
+        .collect();
+    candidates.sort();
+    let main = candidates
+        .iter()
+        .position(|path| path.file_name().is_some_and(|name| name == DEFAULT_MAIN_FILE))
+        .unwrap_or(0);
+    (!candidates.is_empty()).then(|| candidates.swap_remove(main))
+}
+
+/// The file to build for `tex_path`: itself, or if it is a fragment, the
+/// main file of its folder or the folder above
+///
+/// Fragments are often kept in a subfolder such as `sections/`.
+pub fn build_target(tex_path: &Path) -> PathBuf {
+    let is_fragment = read_file(tex_path).is_ok_and(|source| !is_main_document(&source));
+    if !is_fragment {
+        return tex_path.to_path_buf();
+    }
+    tex_path
+        .ancestors()
+        .skip(1)
+        .take(2)
+        .find_map(detect_main)
+        .unwrap_or_else(|| tex_path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let project =
+            create_project_in(temp_dir.path(), "Software Resume", 1_700_000_000_000).unwrap();
+
+        assert_eq!(project.name, "Software Resume");
+        assert_eq!(project.metadata.display_name, "Software Resume");
+        assert_eq!(project.metadata.created, 1_700_000_000_000);
+        assert_eq!(project.metadata.main_file, "main.tex");
+        assert!(temp_dir
+            .path()
+            .join("Software Resume")
+            .join(PROJECT_FILE)
+            .exists());
+
+        let main = fs::read_to_string(project.main_path()).unwrap();
+        assert!(main.contains("\\begin{document}"));
+    }
+
+    fn split_project() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::create_dir(dir.join("sections")).unwrap();
+        fs::write(
+            dir.join("resume.tex"),
+            concat!(
+                "\\documentclass{article}\n",
+                "\\begin{document}\n",
+                "\\input{experience}\n",
+                "\\input{sections/education}\n",
+                "\\end{document}\n",
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("experience.tex"), "\\section{Experience}\n").unwrap();
+        fs::write(
+            dir.join("sections/education.tex"),
+            "% Not a \\documentclass\n\\section{Education}\n",
+        )
+        .unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_detect_main_among_fragments() {
+        let temp_dir = split_project();
+        let main = temp_dir.path().join("resume.tex");
+        assert_eq!(detect_main(temp_dir.path()), Some(main));
+        assert_eq!(detect_main(&temp_dir.path().join("sections")), None);
+    }
+
+    #[test]
+    fn test_detect_main_prefers_main_tex() {
+        let temp_dir = split_project();
+        let main = temp_dir.path().join(DEFAULT_MAIN_FILE);
+        fs::write(&main, "\\documentclass{article}\\begin{document}\\end{document}").unwrap();
+        assert_eq!(detect_main(temp_dir.path()), Some(main));
+    }
+
+    #[test]
+    fn test_build_target_redirects_fragments() {
+        let temp_dir = split_project();
+        let dir = temp_dir.path();
+        let main = dir.join("resume.tex");
+
+        assert_eq!(build_target(&main), main);
+        assert_eq!(build_target(&dir.join("experience.tex")), main);
+        assert_eq!(build_target(&dir.join("sections/education.tex")), main);
+        // A fragment with no main file nearby is built as is
+        let lone = TempDir::new().unwrap();
+        let fragment = lone.path().join("notes.tex");
+        fs::write(&fragment, "Notes").unwrap();
+        assert_eq!(build_target(&fragment), fragment);
+    }
+
+    #[test]
+    fn test_create_existing_project_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        create_project_in(temp_dir.path(), "resume", 0).unwrap();
+        assert!(create_project_in(temp_dir.path(), "resume", 0).is_err());
+    }
+
+    #[test]
+    fn test_invalid_project_names_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["", "  ", "..", "a/b", "a\\b", "what?"] {
+            assert!(
+                create_project_in(temp_dir.path(), name, 0).is_err(),
+                "{:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_list_projects_sorted() {
+        let temp_dir = TempDir::new().unwrap();
+        create_project_in(temp_dir.path(), "b-resume", 2).unwrap();
+        create_project_in(temp_dir.path(), "a-resume", 1).unwrap();
+
+        let names: Vec<String> = list_projects_in(temp_dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, ["a-resume", "b-resume"]);
+    }
+
+    #[test]
+    fn test_list_projects_skips_stray_folders_and_files() {
+        let temp_dir = TempDir::new().unwrap();
+        create_project_in(temp_dir.path(), "resume", 0).unwrap();
+        fs::create_dir(temp_dir.path().join("stray")).unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "hello").unwrap();
+        let broken = temp_dir.path().join("broken");
+        fs::create_dir(&broken).unwrap();
+        fs::write(broken.join(PROJECT_FILE), "{").unwrap();
+
+        let projects = list_projects_in(temp_dir.path()).unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].name, "resume");
+    }
+
+    #[test]
+    fn test_list_projects_missing_dir_is_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(list_projects_in(&temp_dir.path().join("projects"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_project_for_finds_output_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = create_project_in(temp_dir.path(), "resume", 5).unwrap();
+        let dir = Path::new(&project.path);
+        assert_eq!(project.metadata.output_name, None);
+
+        let metadata = ProjectMetadata {
+            output_name: Some("Jane_Doe_Resume".to_string()),
+            ..project.metadata.clone()
+        };
+        save_metadata(dir, &metadata).unwrap();
+        fs::create_dir(dir.join("sections")).unwrap();
+        for tex_path in [project.main_path(), dir.join("sections").join("work.tex")] {
+            let found = project_for(&tex_path).unwrap();
+            assert_eq!(found.metadata.output_name.as_deref(), Some("Jane_Doe_Resume"));
+        }
+        assert_eq!(project_for(&temp_dir.path().join("loose.tex")), None);
+    }
+
+    #[test]
+    fn test_project_serializes_flat() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = create_project_in(temp_dir.path(), "resume", 5).unwrap();
+        let json = serde_json::to_string(&project).unwrap();
+        assert!(json.contains("\"name\":\"resume\""));
+        assert!(json.contains("\"display_name\":\"resume\""));
+        assert!(json.contains("\"main_file\":\"main.tex\""));
+    }
+}
@@ -0,0 +1,122 @@
+//! Showing a file in the system file manager
+
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Command;
+
+/// The file manager a file is revealed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileManager {
+    Explorer,
+    Finder,
+    /// Whatever `xdg-open` picks for folders; it can't select the file
+    XdgOpen,
+}
+
+impl FileManager {
+    /// The file manager of the platform the app runs on
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            FileManager::Explorer
+        } else if cfg!(target_os = "macos") {
+            FileManager::Finder
+        } else {
+            FileManager::XdgOpen
+        }
+    }
+
+    /// Program and arguments that show `path` in its folder
+    pub fn reveal_command(&self, path: &Path) -> (&'static str, Vec<OsString>) {
+        match self {
+            // Explorer wants the path as its own argument after `/select,`
+            FileManager::Explorer => ("explorer", vec!["/select,".into(), path.into()]),
+            FileManager::Finder => ("open", vec!["-R".into(), path.into()]),
+            FileManager::XdgOpen => {
+                let dir = if path.is_dir() {
+                    path
+                } else {
+                    path.parent().unwrap_or(path)
+                };
+                ("xdg-open", vec![dir.into()])
+            }
+        }
+    }
+}
+
+/// Open the folder holding `path` with the file selected where supported
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    let (program, args) = FileManager::current().reveal_command(path);
+    let mut child = Command::new(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| format!("Failed to open the file manager: {}", e))?;
+    // Reap it without waiting; Explorer exits with 1 even when it worked,
+    // so the status isn't checked
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: (&str, Vec<OsString>)) -> (String, Vec<String>) {
+        let (program, args) = command;
+        let args = args
+            .iter()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect();
+        (program.to_string(), args)
+    }
+
+    #[test]
+    fn test_explorer_selects_file() {
+        let path = Path::new("C:\\Users\\Jane Doe\\resume.pdf");
+        assert_eq!(
+            args(FileManager::Explorer.reveal_command(path)),
+            (
+                "explorer".to_string(),
+                vec![
+                    "/select,".to_string(),
+                    "C:\\Users\\Jane Doe\\resume.pdf".to_string()
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_finder_reveals_file() {
+        let path = Path::new("/Users/jane/resume.pdf");
+        assert_eq!(
+            args(FileManager::Finder.reveal_command(path)),
+            (
+                "open".to_string(),
+                vec!["-R".to_string(), "/Users/jane/resume.pdf".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_xdg_open_opens_containing_folder() {
+        let path = Path::new("/home/jane/resume.pdf");
+        assert_eq!(
+            args(FileManager::XdgOpen.reveal_command(path)),
+            ("xdg-open".to_string(), vec!["/home/jane".to_string()])
+        );
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_, dir_args) = args(FileManager::XdgOpen.reveal_command(temp_dir.path()));
+        assert_eq!(dir_args, [temp_dir.path().to_string_lossy().to_string()]);
+    }
+
+    #[test]
+    fn test_reveal_missing_file_fails() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let missing = temp_dir.path().join("gone.pdf");
+        let err = reveal_in_file_manager(&missing).unwrap_err();
+        assert!(err.starts_with("File not found"), "{}", err);
+    }
+}
@@ -0,0 +1,105 @@
+//! The file that was open when the app last closed
+//!
+//! Stored in `<workspace_root>/session.json` so the next launch can pick
+//! up where the user left off.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::workspace::get_session_path;
+
+/// What is restored on the next launch
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    /// The active file
+    pub path: String,
+}
+
+/// Remember `file` as the active file
+pub fn record_active_file(file: &Path) -> Result<(), String> {
+    let path = get_session_path().ok_or("Could not determine session path")?;
+    save_session_to(
+        &path,
+        &Session {
+            path: file.to_string_lossy().to_string(),
+        },
+    )
+}
+
+/// The file to reopen on launch, if it still exists
+pub fn restorable_file() -> Option<PathBuf> {
+    restorable_file_from(&get_session_path()?)
+}
+
+/// The file recorded in the session file at `path`, if it still exists
+///
+/// A missing or unreadable session file restores nothing.
+pub fn restorable_file_from(path: &Path) -> Option<PathBuf> {
+    let session = load_session_from(path)?;
+    let file = PathBuf::from(session.path);
+    file.is_file().then_some(file)
+}
+
+/// Load the session file at `path`
+pub fn load_session_from(path: &Path) -> Option<Session> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Write the session file at `path`
+pub fn save_session_to(path: &Path, session: &Session) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create session directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(session)
+        .map_err(|e| format!("Failed to serialize session: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write session: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_session_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let session_path = temp_dir.path().join("session.json");
+        let file = temp_dir.path().join("resume.tex");
+        fs::write(&file, "\\documentclass{article}").unwrap();
+
+        let session = Session {
+            path: file.to_string_lossy().to_string(),
+        };
+        save_session_to(&session_path, &session).unwrap();
+        assert_eq!(load_session_from(&session_path), Some(session));
+        assert_eq!(restorable_file_from(&session_path), Some(file));
+    }
+
+    #[test]
+    fn test_moved_file_is_not_restored() {
+        let temp_dir = TempDir::new().unwrap();
+        let session_path = temp_dir.path().join("session.json");
+        let session = Session {
+            path: temp_dir
+                .path()
+                .join("gone.tex")
+                .to_string_lossy()
+                .to_string(),
+        };
+        save_session_to(&session_path, &session).unwrap();
+
+        assert_eq!(restorable_file_from(&session_path), None);
+    }
+
+    #[test]
+    fn test_missing_or_corrupt_session_restores_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let session_path = temp_dir.path().join("session.json");
+        assert_eq!(restorable_file_from(&session_path), None);
+
+        fs::write(&session_path, "{not json").unwrap();
+        assert_eq!(restorable_file_from(&session_path), None);
+    }
+}
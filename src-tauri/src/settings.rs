@@ -0,0 +1,252 @@
+//! User settings persisted in `<workspace_root>/settings.json`
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backups::DEFAULT_MAX_BACKUPS;
+use crate::compiler::{LatexEngine, DEFAULT_INSTALL_RETRIES};
+use crate::line_endings::LineEnding;
+use crate::workspace::get_settings_path;
+
+/// Editor color theme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follow the operating system
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// Settings that persist across launches
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Engine used when a build doesn't name one
+    pub engine: LatexEngine,
+    /// Rebuild automatically shortly after each save
+    pub auto_build: bool,
+    /// Back up a file before each save overwrites it
+    pub keep_backups: bool,
+    /// Number of backups kept per file
+    pub max_backups: usize,
+    /// pdflatex executable to use instead of searching PATH
+    pub pdflatex_path: Option<String>,
+    pub theme: Theme,
+    /// Line ending files are saved with
+    pub line_ending: LineEnding,
+    /// Strip trailing whitespace and end with one newline when saving
+    pub format_on_save: bool,
+    /// Only open and save files inside the workspace and `allowed_directories`
+    pub restrict_file_access: bool,
+    /// Directories the user granted access to outside the workspace
+    pub allowed_directories: Vec<String>,
+    /// Note in the build diagnostics when the PDF is longer than this
+    pub page_limit: Option<u32>,
+    /// Let documents run external programs, as `minted` and `svg` need
+    pub allow_shell_escape: bool,
+    /// Flags passed to the engine on every build, e.g. `-halt-on-error`
+    pub extra_args: Vec<String>,
+    /// Rebuilds after MiKTeX fails while installing a missing package
+    pub install_retries: u32,
+    /// Executables to use instead of searching PATH, keyed by tool name,
+    /// e.g. `"biber": "/opt/biber/biber"`
+    pub tool_paths: BTreeMap<String, String>,
+    /// Build byte-identical PDFs from identical sources, for committing them
+    pub reproducible_builds: bool,
+    /// Leave the PDF in the build directory instead of copying it next to
+    /// the source, so the project folder only holds what the user put there
+    pub keep_project_clean: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            engine: LatexEngine::default(),
+            auto_build: false,
+            keep_backups: true,
+            max_backups: DEFAULT_MAX_BACKUPS,
+            pdflatex_path: None,
+            theme: Theme::default(),
+            line_ending: LineEnding::default(),
+            format_on_save: false,
+            restrict_file_access: false,
+            allowed_directories: Vec::new(),
+            page_limit: Some(1),
+            allow_shell_escape: false,
+            extra_args: Vec::new(),
+            install_retries: DEFAULT_INSTALL_RETRIES,
+            tool_paths: BTreeMap::new(),
+            reproducible_builds: false,
+            keep_project_clean: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Every configured tool path, with `pdflatex_path` winning for pdflatex
+    pub fn tool_overrides(&self) -> HashMap<String, String> {
+        let mut overrides: HashMap<String, String> = self
+            .tool_paths
+            .iter()
+            .map(|(tool, path)| (tool.clone(), path.clone()))
+            .collect();
+        if let Some(path) = &self.pdflatex_path {
+            overrides.insert(LatexEngine::PdfLatex.binary_name().to_string(), path.clone());
+        }
+        overrides
+    }
+}
+
+/// Load settings from the workspace, falling back to defaults
+pub fn load_settings() -> Result<Settings, String> {
+    let path = get_settings_path().ok_or("Could not determine settings path")?;
+    load_settings_from(&path)
+}
+
+/// Save settings to the workspace
+pub fn save_settings(settings: &Settings) -> Result<(), String> {
+    let path = get_settings_path().ok_or("Could not determine settings path")?;
+    save_settings_to(&path, settings)
+}
+
+/// Load settings from a specific file
+///
+/// A missing file is created with defaults. A file that can't be parsed
+/// is moved aside to `settings.json.corrupt` and replaced with defaults.
+pub fn load_settings_from(path: &Path) -> Result<Settings, String> {
+    if !path.exists() {
+        let settings = Settings::default();
+        // Defaults work without a file, so failing to write one isn't fatal
+        let _ = save_settings_to(path, &settings);
+        return Ok(settings);
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    match serde_json::from_str(&content) {
+        Ok(settings) => Ok(settings),
+        Err(_) => {
+            fs::rename(path, corrupt_backup_path(path))
+                .map_err(|e| format!("Failed to back up corrupt settings: {}", e))?;
+            let settings = Settings::default();
+            save_settings_to(path, &settings)?;
+            Ok(settings)
+        }
+    }
+}
+
+/// Where an unreadable settings file is kept, e.g. `settings.json.corrupt`
+pub(crate) fn corrupt_backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".corrupt");
+    path.with_file_name(name)
+}
+
+/// Save settings to a specific file
+pub fn save_settings_to(path: &Path, settings: &Settings) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_settings_file_yields_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let settings = load_settings_from(&temp_dir.path().join("settings.json")).unwrap();
+        assert_eq!(settings, Settings::default());
+        assert!(!settings.auto_build);
+        assert!(settings.keep_backups);
+        assert_eq!(settings.max_backups, DEFAULT_MAX_BACKUPS);
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("settings.json");
+
+        let settings = Settings {
+            engine: LatexEngine::XeLatex,
+            auto_build: true,
+            keep_backups: false,
+            max_backups: 5,
+            pdflatex_path: Some("/opt/texlive/bin/pdflatex".to_string()),
+            theme: Theme::Dark,
+            line_ending: LineEnding::CrLf,
+            format_on_save: true,
+            restrict_file_access: true,
+            allowed_directories: vec!["/home/jane/Documents".to_string()],
+            page_limit: Some(2),
+            allow_shell_escape: true,
+            extra_args: vec!["-halt-on-error".to_string()],
+            install_retries: 3,
+            tool_paths: BTreeMap::from([("biber".to_string(), "/opt/biber/biber".to_string())]),
+            reproducible_builds: true,
+            keep_project_clean: true,
+        };
+        save_settings_to(&path, &settings).unwrap();
+
+        assert_eq!(load_settings_from(&path).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_pdflatex_path_wins_over_tool_paths() {
+        let settings = Settings {
+            pdflatex_path: Some("/opt/tex/pdflatex".to_string()),
+            tool_paths: BTreeMap::from([
+                ("pdflatex".to_string(), "/usr/bin/pdflatex".to_string()),
+                ("makeindex".to_string(), "/usr/bin/makeindex".to_string()),
+            ]),
+            ..Settings::default()
+        };
+
+        let overrides = settings.tool_overrides();
+        assert_eq!(overrides["pdflatex"], "/opt/tex/pdflatex");
+        assert_eq!(overrides["makeindex"], "/usr/bin/makeindex");
+    }
+
+    #[test]
+    fn test_unknown_and_missing_fields_use_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("settings.json");
+        fs::write(&path, r#"{"some_future_option": 1}"#).unwrap();
+
+        assert_eq!(load_settings_from(&path).unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn test_missing_settings_file_is_created() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("workspace").join("settings.json");
+
+        load_settings_from(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("\"engine\": \"pdflatex\""));
+        assert!(content.contains("\"theme\": \"system\""));
+    }
+
+    #[test]
+    fn test_corrupt_settings_are_backed_up_and_reset() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("settings.json");
+        fs::write(&path, "{\"auto_build\": tru").unwrap();
+
+        assert_eq!(load_settings_from(&path).unwrap(), Settings::default());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("settings.json.corrupt")).unwrap(),
+            "{\"auto_build\": tru"
+        );
+        // The reset file parses on the next load
+        assert_eq!(load_settings_from(&path).unwrap(), Settings::default());
+    }
+}
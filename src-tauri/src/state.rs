@@ -1,20 +1,260 @@
 //! Application state management
 
-use std::path::PathBuf;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-/// Application state for tracking the current file
+use crate::compiler::debounce::Debouncer;
+use crate::compiler::CompilationHandle;
+use crate::watcher::{DirWatcher, FileWatcher};
+
+/// A document open in an editor tab
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenDoc {
+    pub path: PathBuf,
+    /// Bumped every time the editor content changes
+    pub revision: u64,
+    /// Hash of the editor content at `revision`
+    pub content_hash: Option<u64>,
+    /// The revision last written to (or read from) disk
+    pub saved_revision: u64,
+    /// Hash of the content last written to (or read from) disk
+    pub saved_hash: Option<u64>,
+}
+
+impl OpenDoc {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            revision: 0,
+            content_hash: None,
+            saved_revision: 0,
+            saved_hash: None,
+        }
+    }
+
+    /// Record new editor content, returning the current revision
+    ///
+    /// Content identical to the current revision doesn't bump it.
+    pub fn update(&mut self, content: &str) -> u64 {
+        let hash = hash_content(content);
+        if self.content_hash != Some(hash) {
+            self.revision += 1;
+            self.content_hash = Some(hash);
+        }
+        self.revision
+    }
+
+    /// Record `content` as what is now on disk
+    pub fn mark_saved(&mut self, content: &str) -> u64 {
+        let revision = self.update(content);
+        self.saved_revision = revision;
+        self.saved_hash = self.content_hash;
+        revision
+    }
+
+    /// Whether the editor content differs from what was last saved
+    ///
+    /// Undoing back to the saved content counts as no change.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.revision != self.saved_revision && self.content_hash != self.saved_hash
+    }
+}
+
+fn hash_content(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Application state for tracking open files
 pub struct AppState {
-    pub current_file: Mutex<Option<PathBuf>>,
+    /// Open documents in tab order
+    pub open_files: Mutex<Vec<OpenDoc>>,
+    /// Index of the active tab in `open_files`
+    pub active: Mutex<usize>,
+    /// The compilation currently in progress, if any
+    pub compilation: CompilationHandle,
+    /// Coalesces rapid auto-build requests
+    pub build_debounce: Debouncer,
+    /// Build artifact directory chosen by the user; `None` uses the cache dir
+    pub build_dir: Mutex<Option<PathBuf>>,
+    /// Watches the active file for changes made by other programs
+    pub watcher: Mutex<Option<FileWatcher>>,
+    /// Watches the templates folder so the template list stays current
+    pub templates_watcher: Mutex<Option<DirWatcher>>,
 }
 
 impl AppState {
     /// Create a new AppState instance
     pub fn new() -> Self {
         Self {
-            current_file: Mutex::new(None),
+            open_files: Mutex::new(Vec::new()),
+            active: Mutex::new(0),
+            compilation: CompilationHandle::default(),
+            build_debounce: Debouncer::default(),
+            build_dir: Mutex::new(None),
+            watcher: Mutex::new(None),
+            templates_watcher: Mutex::new(None),
+        }
+    }
+
+    /// Open a document in a new tab, or switch to it if it is already open
+    ///
+    /// Returns the index of the now active tab.
+    pub fn open_document(&self, path: &Path) -> Result<usize, String> {
+        let mut files = self.open_files.lock().map_err(|e| e.to_string())?;
+        let mut active = self.active.lock().map_err(|e| e.to_string())?;
+
+        *active = match files.iter().position(|doc| doc.path == path) {
+            Some(index) => index,
+            None => {
+                files.push(OpenDoc::new(path));
+                files.len() - 1
+            }
+        };
+        Ok(*active)
+    }
+
+    /// Open a document whose `content` was just read from disk
+    ///
+    /// A new tab records `content` as saved. A tab that is already open
+    /// keeps its state, so reopening a modified document keeps its edits
+    /// unsaved. Returns the index of the now active tab.
+    pub fn open_loaded_document(&self, path: &Path, content: &str) -> Result<usize, String> {
+        let mut files = self.open_files.lock().map_err(|e| e.to_string())?;
+        let mut active = self.active.lock().map_err(|e| e.to_string())?;
+
+        *active = match files.iter().position(|doc| doc.path == path) {
+            Some(index) => index,
+            None => {
+                let mut doc = OpenDoc::new(path);
+                doc.mark_saved(content);
+                files.push(doc);
+                files.len() - 1
+            }
+        };
+        Ok(*active)
+    }
+
+    /// Fail if saving the active tab as `path` would discard edits made in
+    /// another tab that has `path` open
+    pub fn check_replace_active(&self, path: &Path) -> Result<(), String> {
+        let files = self.open_files.lock().map_err(|e| e.to_string())?;
+        let active = self.active.lock().map_err(|e| e.to_string())?;
+        match other_tab_with(&files, *active, path) {
+            Some(index) if files[index].has_unsaved_changes() => {
+                Err(SAVE_AS_TARGET_MODIFIED.to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Point the active tab at a new path (save as), opening a tab if none is open
+    ///
+    /// Another tab with `path` open is closed so the file has one tab; it
+    /// fails instead if that tab has unsaved changes.
+    pub fn replace_active(&self, path: &Path) -> Result<(), String> {
+        let mut files = self.open_files.lock().map_err(|e| e.to_string())?;
+        let mut active = self.active.lock().map_err(|e| e.to_string())?;
+
+        if let Some(index) = other_tab_with(&files, *active, path) {
+            if files[index].has_unsaved_changes() {
+                return Err(SAVE_AS_TARGET_MODIFIED.to_string());
+            }
+            files.remove(index);
+            if index < *active {
+                *active -= 1;
+            }
+        }
+
+        let doc = OpenDoc::new(path);
+        match files.get_mut(*active) {
+            Some(current) => *current = doc,
+            None => {
+                files.push(doc);
+                *active = files.len() - 1;
+            }
         }
+        Ok(())
     }
+
+    /// Make the tab at `index` active and return its path
+    pub fn switch_tab(&self, index: usize) -> Result<PathBuf, String> {
+        let files = self.open_files.lock().map_err(|e| e.to_string())?;
+        let mut active = self.active.lock().map_err(|e| e.to_string())?;
+
+        let doc = files
+            .get(index)
+            .ok_or_else(|| format!("No tab at index {}", index))?;
+        *active = index;
+        Ok(doc.path.clone())
+    }
+
+    /// Close the tab at `index`
+    ///
+    /// Closing the active tab activates the tab that takes its place, or
+    /// the new last tab. Returns the path of the active tab afterwards.
+    pub fn close_tab(&self, index: usize) -> Result<Option<PathBuf>, String> {
+        let mut files = self.open_files.lock().map_err(|e| e.to_string())?;
+        let mut active = self.active.lock().map_err(|e| e.to_string())?;
+
+        if index >= files.len() {
+            return Err(format!("No tab at index {}", index));
+        }
+        files.remove(index);
+
+        if index < *active || *active >= files.len() {
+            *active = active.saturating_sub(1);
+        }
+        Ok(files.get(*active).map(|doc| doc.path.clone()))
+    }
+
+    /// Record the editor content of the active document
+    ///
+    /// Returns the document's revision after the update.
+    pub fn update_active(&self, content: &str) -> Result<u64, String> {
+        self.with_active(|doc| doc.update(content))
+    }
+
+    /// Record that the active document's content was saved (or loaded)
+    ///
+    /// Returns the saved revision.
+    pub fn mark_active_saved(&self, content: &str) -> Result<u64, String> {
+        self.with_active(|doc| doc.mark_saved(content))
+    }
+
+    /// Whether the active document has changes that haven't been saved
+    pub fn active_has_unsaved_changes(&self) -> Result<bool, String> {
+        self.with_active(|doc| doc.has_unsaved_changes())
+    }
+
+    fn with_active<T>(&self, f: impl FnOnce(&mut OpenDoc) -> T) -> Result<T, String> {
+        let mut files = self.open_files.lock().map_err(|e| e.to_string())?;
+        let active = self.active.lock().map_err(|e| e.to_string())?;
+        let doc = files.get_mut(*active).ok_or("No file is currently open")?;
+        Ok(f(doc))
+    }
+
+    /// Path of the active document, if any
+    pub fn active_path(&self) -> Result<Option<PathBuf>, String> {
+        let files = self.open_files.lock().map_err(|e| e.to_string())?;
+        let active = self.active.lock().map_err(|e| e.to_string())?;
+        Ok(files.get(*active).map(|doc| doc.path.clone()))
+    }
+}
+
+/// Error for a save as onto a file whose tab has unsaved changes
+pub const SAVE_AS_TARGET_MODIFIED: &str =
+    "That file is open in another tab with unsaved changes; save or close it first";
+
+/// Index of a tab other than `active` that has `path` open
+fn other_tab_with(files: &[OpenDoc], active: usize, path: &Path) -> Option<usize> {
+    files
+        .iter()
+        .enumerate()
+        .position(|(index, doc)| index != active && doc.path == path)
 }
 
 impl Default for AppState {
@@ -23,3 +263,170 @@ impl Default for AppState {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(paths: &[&str]) -> AppState {
+        let state = AppState::new();
+        for path in paths {
+            state.open_document(Path::new(path)).unwrap();
+        }
+        state
+    }
+
+    fn active(state: &AppState) -> Option<PathBuf> {
+        state.active_path().unwrap()
+    }
+
+    #[test]
+    fn test_no_active_document_initially() {
+        assert_eq!(active(&AppState::new()), None);
+    }
+
+    #[test]
+    fn test_open_appends_and_activates() {
+        let state = state_with(&["resume.tex"]);
+        assert_eq!(state.open_document(Path::new("letter.tex")).unwrap(), 1);
+        assert_eq!(active(&state), Some(PathBuf::from("letter.tex")));
+    }
+
+    #[test]
+    fn test_open_existing_document_switches_to_it() {
+        let state = state_with(&["resume.tex", "letter.tex"]);
+        assert_eq!(state.open_document(Path::new("resume.tex")).unwrap(), 0);
+        assert_eq!(state.open_files.lock().unwrap().len(), 2);
+        assert_eq!(active(&state), Some(PathBuf::from("resume.tex")));
+    }
+
+    #[test]
+    fn test_reopening_modified_document_keeps_it_unsaved() {
+        let state = AppState::new();
+        state.open_loaded_document(Path::new("resume.tex"), "on disk").unwrap();
+        assert!(!state.active_has_unsaved_changes().unwrap());
+        state.update_active("edited").unwrap();
+        state.open_loaded_document(Path::new("letter.tex"), "letter").unwrap();
+
+        assert_eq!(state.open_loaded_document(Path::new("resume.tex"), "on disk").unwrap(), 0);
+        assert!(state.active_has_unsaved_changes().unwrap());
+    }
+
+    #[test]
+    fn test_save_as_onto_open_tab_closes_it() {
+        let state = state_with(&["a.tex", "b.tex", "c.tex"]);
+        state.replace_active(Path::new("a.tex")).unwrap();
+
+        let files = state.open_files.lock().unwrap();
+        let paths: Vec<&Path> = files.iter().map(|doc| doc.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("b.tex"), Path::new("a.tex")]);
+        assert_eq!(*state.active.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_save_as_onto_modified_tab_fails() {
+        let state = state_with(&["a.tex", "b.tex"]);
+        state.switch_tab(0).unwrap();
+        state.update_active("edited").unwrap();
+        state.switch_tab(1).unwrap();
+
+        let expected = Err(SAVE_AS_TARGET_MODIFIED.to_string());
+        assert_eq!(state.check_replace_active(Path::new("a.tex")), expected);
+        assert_eq!(state.replace_active(Path::new("a.tex")), expected);
+        assert_eq!(state.open_files.lock().unwrap().len(), 2);
+        assert_eq!(state.check_replace_active(Path::new("new.tex")), Ok(()));
+    }
+
+    #[test]
+    fn test_switch_tab() {
+        let state = state_with(&["resume.tex", "letter.tex"]);
+        assert_eq!(state.switch_tab(0).unwrap(), PathBuf::from("resume.tex"));
+        assert_eq!(active(&state), Some(PathBuf::from("resume.tex")));
+        assert!(state.switch_tab(2).is_err());
+        assert_eq!(*state.active.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_close_tab_before_active_shifts_index() {
+        let state = state_with(&["a.tex", "b.tex", "c.tex"]);
+        assert_eq!(state.close_tab(0).unwrap(), Some(PathBuf::from("c.tex")));
+        assert_eq!(*state.active.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_close_tab_after_active_keeps_index() {
+        let state = state_with(&["a.tex", "b.tex", "c.tex"]);
+        state.switch_tab(0).unwrap();
+        assert_eq!(state.close_tab(2).unwrap(), Some(PathBuf::from("a.tex")));
+        assert_eq!(*state.active.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_close_active_tab_activates_next() {
+        let state = state_with(&["a.tex", "b.tex", "c.tex"]);
+        state.switch_tab(1).unwrap();
+        assert_eq!(state.close_tab(1).unwrap(), Some(PathBuf::from("c.tex")));
+    }
+
+    #[test]
+    fn test_close_active_last_tab_activates_previous() {
+        let state = state_with(&["a.tex", "b.tex", "c.tex"]);
+        assert_eq!(state.close_tab(2).unwrap(), Some(PathBuf::from("b.tex")));
+        assert_eq!(*state.active.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_close_only_tab() {
+        let state = state_with(&["a.tex"]);
+        assert_eq!(state.close_tab(0).unwrap(), None);
+        assert_eq!(active(&state), None);
+        assert!(state.close_tab(0).is_err());
+    }
+
+    #[test]
+    fn test_update_bumps_revision_on_change() {
+        let mut doc = OpenDoc::new(Path::new("resume.tex"));
+        assert_eq!(doc.update("a"), 1);
+        assert_eq!(doc.update("ab"), 2);
+        // Same content again is not a new revision
+        assert_eq!(doc.update("ab"), 2);
+    }
+
+    #[test]
+    fn test_unsaved_changes_compare_revisions() {
+        let state = state_with(&["resume.tex"]);
+        assert_eq!(state.mark_active_saved("Hello").unwrap(), 1);
+        assert!(!state.active_has_unsaved_changes().unwrap());
+
+        assert_eq!(state.update_active("Hello, world").unwrap(), 2);
+        assert!(state.active_has_unsaved_changes().unwrap());
+
+        // Undoing back to the saved text leaves nothing to save
+        assert_eq!(state.update_active("Hello").unwrap(), 3);
+        assert!(!state.active_has_unsaved_changes().unwrap());
+
+        state.update_active("Hello!").unwrap();
+        assert_eq!(state.mark_active_saved("Hello!").unwrap(), 4);
+        assert!(!state.active_has_unsaved_changes().unwrap());
+    }
+
+    #[test]
+    fn test_revisions_are_per_document() {
+        let state = state_with(&["a.tex", "b.tex"]);
+        state.update_active("b").unwrap();
+        state.switch_tab(0).unwrap();
+        assert!(!state.active_has_unsaved_changes().unwrap());
+        assert_eq!(state.update_active("a").unwrap(), 1);
+        assert!(AppState::new().update_active("x").is_err());
+    }
+
+    #[test]
+    fn test_replace_active_for_save_as() {
+        let state = AppState::new();
+        state.replace_active(Path::new("untitled.tex")).unwrap();
+        assert_eq!(active(&state), Some(PathBuf::from("untitled.tex")));
+
+        state.replace_active(Path::new("resume.tex")).unwrap();
+        assert_eq!(state.open_files.lock().unwrap().len(), 1);
+        assert_eq!(active(&state), Some(PathBuf::from("resume.tex")));
+    }
+}
@@ -0,0 +1,323 @@
+//! Resume templates
+//!
+//! Templates are `.tex` files in `<workspace_root>/templates/`. A few
+//! starter templates are bundled and copied there on first launch.
+//!
+//! Templates may contain `{{name}}` style placeholders, filled in with the
+//! user's details when a project is started from them.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::file_ops::{is_tex_file, read_file};
+use crate::latex::escape::latex_escape;
+use crate::projects::{create_project_with, Project};
+use crate::workspace::get_templates_dir;
+
+const CLASSIC_TEMPLATE: &str = include_str!("../templates/classic.tex");
+
+/// Starter templates as (file name, content)
+const BUNDLED_TEMPLATES: [(&str, &str); 2] = [
+    ("classic.tex", CLASSIC_TEMPLATE),
+    ("modern.tex", include_str!("../templates/modern.tex")),
+];
+
+/// Kind of document a new, unsaved file starts as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NewFileKind {
+    /// An empty article, ready to type into
+    Blank,
+    Resume,
+    CoverLetter,
+}
+
+const BLANK_STARTER: &str = include_str!("../templates/starters/blank.tex");
+const COVER_LETTER_STARTER: &str = include_str!("../templates/starters/cover-letter.tex");
+
+/// Starting content for a new file of `kind`
+///
+/// Unlike workspace templates this never touches disk, so it works before
+/// the workspace exists. New resumes start from the classic template.
+pub fn starter_document(kind: NewFileKind) -> &'static str {
+    match kind {
+        NewFileKind::Blank => BLANK_STARTER,
+        NewFileKind::Resume => CLASSIC_TEMPLATE,
+        NewFileKind::CoverLetter => COVER_LETTER_STARTER,
+    }
+}
+
+/// Number of lines shown as a template preview
+const PREVIEW_LINES: usize = 5;
+
+/// A template available to start a project from
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub path: String,
+    /// The first few non-empty lines of the template
+    pub preview: String,
+}
+
+/// Write the bundled templates into `dir` if it has no templates yet
+///
+/// Returns whether templates were written. Templates the user edited or
+/// added are never overwritten.
+pub fn seed_templates_in(dir: &Path) -> std::io::Result<bool> {
+    fs::create_dir_all(dir)?;
+    if fs::read_dir(dir)?.next().is_some() {
+        return Ok(false);
+    }
+    for (name, content) in BUNDLED_TEMPLATES {
+        fs::write(dir.join(name), content)?;
+    }
+    Ok(true)
+}
+
+/// List the templates in the workspace
+pub fn list_templates() -> Result<Vec<TemplateInfo>, String> {
+    let dir = get_templates_dir().ok_or("Could not determine templates directory")?;
+    list_templates_in(&dir)
+}
+
+/// List the `.tex` templates in `dir`, sorted by name
+pub fn list_templates_in(dir: &Path) -> Result<Vec<TemplateInfo>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read templates: {}", e))?;
+
+    let mut templates: Vec<TemplateInfo> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_tex_file(path))
+        .filter_map(|path| {
+            let content = read_file(&path).ok()?;
+            Some(TemplateInfo {
+                name: path.file_stem()?.to_string_lossy().to_string(),
+                path: path.to_string_lossy().to_string(),
+                preview: preview(&content),
+            })
+        })
+        .collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// The first few non-empty lines of a template
+fn preview(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(PREVIEW_LINES)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Start a new project named `dest` from the template named `template`
+///
+/// Placeholders named in `vars` are replaced with their values; others are
+/// left as they are.
+pub fn instantiate_template(
+    template: &str,
+    dest: &str,
+    vars: &HashMap<String, String>,
+) -> Result<Project, String> {
+    let dir = get_templates_dir().ok_or("Could not determine templates directory")?;
+    let content = read_template_in(&dir, template)?;
+    create_project_with(dest, &fill_placeholders(&content, vars))
+}
+
+/// The placeholders the template named `template` declares
+pub fn template_placeholders(template: &str) -> Result<Vec<String>, String> {
+    let dir = get_templates_dir().ok_or("Could not determine templates directory")?;
+    Ok(placeholders_in(&read_template_in(&dir, template)?))
+}
+
+/// Names of the `{{name}}` placeholders in `content`, in order of first use
+pub fn placeholders_in(content: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = content;
+    while let Some((_, name, after)) = next_placeholder(rest) {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = after;
+    }
+    names
+}
+
+/// Replace the placeholders named in `vars` with their LaTeX-escaped values
+pub fn fill_placeholders(content: &str, vars: &HashMap<String, String>) -> String {
+    let mut filled = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some((before, name, after)) = next_placeholder(rest) {
+        filled.push_str(before);
+        match vars.get(name) {
+            Some(value) => filled.push_str(&latex_escape(value)),
+            None => {
+                filled.push_str(&rest[before.len()..rest.len() - after.len()]);
+            }
+        }
+        rest = after;
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Split `text` around its first placeholder: (text before, name, text after)
+///
+/// Names are letters, digits and underscores, so TeX groups like `{{\bf x}}`
+/// are not mistaken for placeholders.
+fn next_placeholder(text: &str) -> Option<(&str, &str, &str)> {
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("{{").map(|i| offset + i) {
+        let inner = &text[start + 2..];
+        let len = inner
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(inner.len());
+        if len > 0 && inner[len..].starts_with("}}") {
+            return Some((&text[..start], &inner[..len], &inner[len + 2..]));
+        }
+        offset = start + 1;
+    }
+    None
+}
+
+/// Read the content of the template named `name` in `dir`
+fn read_template_in(dir: &Path, name: &str) -> Result<String, String> {
+    let template = list_templates_in(dir)?
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| format!("Template not found: {}", name))?;
+    read_file(Path::new(&template.path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_seed_templates_into_empty_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("templates");
+
+        assert!(seed_templates_in(&dir).unwrap());
+        let names: Vec<String> = list_templates_in(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, ["classic", "modern"]);
+    }
+
+    #[test]
+    fn test_seed_templates_only_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        assert!(seed_templates_in(dir).unwrap());
+
+        // A user's edits survive later launches
+        fs::write(dir.join("classic.tex"), "% my edits").unwrap();
+        assert!(!seed_templates_in(dir).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join("classic.tex")).unwrap(),
+            "% my edits"
+        );
+    }
+
+    #[test]
+    fn test_seed_skips_dir_with_user_templates() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("mine.tex"), "% mine").unwrap();
+
+        assert!(!seed_templates_in(temp_dir.path()).unwrap());
+        assert!(!temp_dir.path().join("classic.tex").exists());
+    }
+
+    #[test]
+    fn test_bundled_templates_are_complete_documents() {
+        for (name, content) in BUNDLED_TEMPLATES {
+            assert!(content.contains("\\begin{document}"), "{}", name);
+            assert!(content.contains("\\end{document}"), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_starter_documents_are_complete() {
+        let resume = starter_document(NewFileKind::Resume);
+        assert!(resume.contains("\\begin{document}"));
+        assert!(resume.contains("\\end{document}"));
+
+        for kind in [NewFileKind::Blank, NewFileKind::CoverLetter] {
+            let content = starter_document(kind);
+            assert!(content.contains("\\documentclass"), "{:?}", kind);
+            assert!(content.contains("\\begin{document}"), "{:?}", kind);
+            assert!(content.contains("\\end{document}"), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_template_preview_skips_blank_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("short.tex"),
+            "% Short\n\n\\documentclass{article}\n",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "not a template").unwrap();
+
+        let templates = list_templates_in(temp_dir.path()).unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].preview, "% Short\n\\documentclass{article}");
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_placeholders_in_order_of_first_use() {
+        let content = "{{name}} <{{email}}>\\\\ {{name}} {{ phone }} {{\\bf x}} {{}}";
+        assert_eq!(placeholders_in(content), ["name", "email"]);
+    }
+
+    #[test]
+    fn test_fill_placeholders_leaves_unknown_untouched() {
+        let content = "\\textbf{{{name}}} \\\\ {{email}} \\\\ {{phone}}";
+        let filled = fill_placeholders(
+            content,
+            &vars(&[("name", "Jane Doe"), ("email", "jane@example.com")]),
+        );
+        assert_eq!(
+            filled,
+            "\\textbf{Jane Doe} \\\\ jane@example.com \\\\ {{phone}}"
+        );
+    }
+
+    #[test]
+    fn test_fill_placeholders_escapes_special_characters() {
+        let filled = fill_placeholders(
+            "{{company}}: {{growth}} in {{team}}",
+            &vars(&[
+                ("company", "Smith & Sons"),
+                ("growth", "40%"),
+                ("team", "data_eng"),
+            ]),
+        );
+        assert_eq!(filled, "Smith \\& Sons: 40\\% in data\\_eng");
+    }
+
+    #[test]
+    fn test_read_missing_template_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        seed_templates_in(temp_dir.path()).unwrap();
+        assert!(read_template_in(temp_dir.path(), "classic").is_ok());
+        assert!(read_template_in(temp_dir.path(), "fancy").is_err());
+    }
+}
@@ -1,10 +1,82 @@
 //! Shared types used across the application
 
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::encoding::TextEncoding;
+use crate::file_ops::{file_name_parts, get_file_name};
+
 /// File information returned from file operations
 #[derive(serde::Serialize)]
 pub struct FileInfo {
     pub path: String,
     pub name: String,
+    /// File name without its extension, e.g. `my.resume` for `my.resume.tex`
+    pub stem: String,
+    /// Extension without the dot; empty if there is none
+    pub extension: String,
     pub content: String,
+    /// Encoding the file is stored in; saves keep it
+    pub encoding: TextEncoding,
+    /// Last modification on disk, in seconds since the Unix epoch
+    pub modified: Option<u64>,
+    /// Size on disk in bytes, which differs from `content` for non-UTF-8 files
+    pub size: u64,
+}
+
+
+impl FileInfo {
+    /// Info for the file at `path`, with its name split into parts
+    ///
+    /// Call it after the file is written, so `modified` and `size` describe
+    /// what is now on disk.
+    pub fn new(path: &Path, content: String, encoding: TextEncoding) -> Self {
+        let (stem, extension) = file_name_parts(path);
+        let metadata = fs::metadata(path).ok();
+        let modified = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        Self {
+            path: path.to_string_lossy().to_string(),
+            name: get_file_name(path),
+            stem,
+            extension,
+            content,
+            encoding,
+            modified,
+            size: metadata.map_or(0, |m| m.len()),
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_info_reports_size_and_modified() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        let content = "\\documentclass{article}\n";
+        fs::write(&path, content).unwrap();
+
+        let info = FileInfo::new(&path, content.to_string(), TextEncoding::Utf8);
+        assert_eq!(info.size, content.len() as u64);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let modified = info.modified.unwrap();
+        assert!(modified <= now + 1 && now.saturating_sub(modified) < 60, "{} vs {}", modified, now);
+    }
+
+    #[test]
+    fn test_file_info_for_missing_file() {
+        let path = Path::new("/no/such/resume.tex");
+        let info = FileInfo::new(path, String::new(), TextEncoding::Utf8);
+        assert_eq!(info.modified, None);
+        assert_eq!(info.size, 0);
+    }
+}
@@ -0,0 +1,352 @@
+//! Watching the open file and the templates folder for changes made by
+//! other programs
+//!
+//! The file is polled: a cheap metadata check each interval, and a content
+//! comparison once the file has stopped changing, so a burst of writes
+//! from an external editor is reported once. Folders are polled the same
+//! way, comparing their listing instead of content. Dropping a watcher
+//! wakes its thread, which exits straight away.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::file_ops::read_file;
+
+/// How often the watched file is checked
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// State shared between a watcher and its polling thread
+struct Shared {
+    path: PathBuf,
+    /// Hash of the content last seen or written by the app
+    known: Mutex<Option<u64>>,
+}
+
+/// Watches one file until dropped
+pub struct FileWatcher {
+    shared: Arc<Shared>,
+    /// Dropped with the watcher, which disconnects the polling thread
+    _stop: Sender<()>,
+}
+
+impl FileWatcher {
+    /// Start watching `path`, calling `on_change` when its content changes
+    pub fn watch(path: &Path, on_change: impl Fn(&Path) + Send + 'static) -> Self {
+        Self::watch_with_interval(path, POLL_INTERVAL, on_change)
+    }
+
+    /// Start watching `path`, checking it every `interval`
+    pub fn watch_with_interval(
+        path: &Path,
+        interval: Duration,
+        on_change: impl Fn(&Path) + Send + 'static,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            path: path.to_path_buf(),
+            known: Mutex::new(content_hash(path)),
+        });
+
+        // Taken alongside the content hash so writes racing the thread's start are seen
+        let initial_stamp = stamp(path);
+        let thread_shared = Arc::clone(&shared);
+        let (stop, stopped) = mpsc::channel();
+        thread::spawn(move || poll(&thread_shared, &stopped, initial_stamp, interval, on_change));
+
+        Self {
+            shared,
+            _stop: stop,
+        }
+    }
+
+    /// The watched path
+    pub fn path(&self) -> &Path {
+        &self.shared.path
+    }
+
+    /// Record content the app is about to write to `path` so the write
+    /// isn't reported as an external change
+    pub fn expect_content(&self, path: &Path, content: &str) {
+        if path == self.shared.path {
+            *self.lock_known() = Some(hash_str(content));
+        }
+    }
+
+    fn lock_known(&self) -> std::sync::MutexGuard<'_, Option<u64>> {
+        self.shared.known.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Modification time and size, which change on (almost) every write
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn hash_str(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn content_hash(path: &Path) -> Option<u64> {
+    read_file(path).ok().map(|content| hash_str(&content))
+}
+
+/// Wait for the next check; false once the watcher has been dropped
+fn next_tick(stopped: &Receiver<()>, interval: Duration) -> bool {
+    matches!(
+        stopped.recv_timeout(interval),
+        Err(RecvTimeoutError::Timeout)
+    )
+}
+
+/// Polling loop run on the watcher's thread
+fn poll(
+    shared: &Shared,
+    stopped: &Receiver<()>,
+    mut last_stamp: Option<(SystemTime, u64)>,
+    interval: Duration,
+    on_change: impl Fn(&Path),
+) {
+    let mut settling = false;
+
+    while next_tick(stopped, interval) {
+        // Wait for an interval without writes before looking at the content
+        let current = stamp(&shared.path);
+        if current != last_stamp {
+            last_stamp = current;
+            settling = true;
+            continue;
+        }
+        if !settling {
+            continue;
+        }
+        settling = false;
+
+        // A deleted or unreadable file has nothing to reload
+        let Some(hash) = content_hash(&shared.path) else {
+            continue;
+        };
+        let mut known = shared.known.lock().unwrap_or_else(|e| e.into_inner());
+        if *known != Some(hash) {
+            *known = Some(hash);
+            drop(known);
+            on_change(&shared.path);
+        }
+    }
+}
+
+/// Name, modification time and size of each entry in a folder, sorted
+type Listing = Vec<(std::ffi::OsString, Option<SystemTime>, u64)>;
+
+/// Watches the entries of one folder until dropped
+pub struct DirWatcher {
+    path: PathBuf,
+    /// Dropped with the watcher, which disconnects the polling thread
+    _stop: Sender<()>,
+}
+
+impl DirWatcher {
+    /// Start watching `dir`, calling `on_change` when entries are added,
+    /// removed or modified
+    pub fn watch(dir: &Path, on_change: impl Fn(&Path) + Send + 'static) -> Self {
+        Self::watch_with_interval(dir, POLL_INTERVAL, on_change)
+    }
+
+    /// Start watching `dir`, checking it every `interval`
+    pub fn watch_with_interval(
+        dir: &Path,
+        interval: Duration,
+        on_change: impl Fn(&Path) + Send + 'static,
+    ) -> Self {
+        let initial = listing(dir);
+        let thread_dir = dir.to_path_buf();
+        let (stop, stopped) = mpsc::channel();
+        thread::spawn(move || poll_dir(&thread_dir, &stopped, initial, interval, on_change));
+
+        Self {
+            path: dir.to_path_buf(),
+            _stop: stop,
+        }
+    }
+
+    /// The watched folder
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn listing(dir: &Path) -> Option<Listing> {
+    let mut entries: Listing = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| {
+            let metadata = entry.metadata().ok();
+            let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+            (entry.file_name(), modified, metadata.map_or(0, |m| m.len()))
+        })
+        .collect();
+    entries.sort();
+    Some(entries)
+}
+
+/// Polling loop run on a folder watcher's thread
+fn poll_dir(
+    dir: &Path,
+    stopped: &Receiver<()>,
+    mut reported: Option<Listing>,
+    interval: Duration,
+    on_change: impl Fn(&Path),
+) {
+    let mut last = reported.clone();
+
+    while next_tick(stopped, interval) {
+        // Report once the listing has held still for an interval
+        let current = listing(dir);
+        if current != last {
+            last = current;
+            continue;
+        }
+        if current != reported {
+            reported = current;
+            on_change(dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use tempfile::TempDir;
+
+    const TEST_INTERVAL: Duration = Duration::from_millis(20);
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn watch(path: &Path) -> (FileWatcher, mpsc::Receiver<PathBuf>) {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = FileWatcher::watch_with_interval(path, TEST_INTERVAL, move |changed| {
+            let _ = sender.send(changed.to_path_buf());
+        });
+        (watcher, receiver)
+    }
+
+    #[test]
+    fn test_external_change_triggers_callback() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        std::fs::write(&path, "original").unwrap();
+
+        let (_watcher, receiver) = watch(&path);
+        std::fs::write(&path, "edited in another editor").unwrap();
+
+        assert_eq!(receiver.recv_timeout(TIMEOUT).unwrap(), path);
+    }
+
+    #[test]
+    fn test_burst_of_writes_reported_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        std::fs::write(&path, "original").unwrap();
+
+        let (_watcher, receiver) = watch(&path);
+        for i in 0..5 {
+            std::fs::write(&path, "x".repeat(i + 10)).unwrap();
+        }
+
+        receiver.recv_timeout(TIMEOUT).unwrap();
+        assert!(receiver.recv_timeout(TEST_INTERVAL * 10).is_err());
+    }
+
+    #[test]
+    fn test_expected_content_is_not_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        std::fs::write(&path, "original").unwrap();
+
+        let (watcher, receiver) = watch(&path);
+        watcher.expect_content(&path, "saved by the app");
+        std::fs::write(&path, "saved by the app").unwrap();
+
+        assert!(receiver.recv_timeout(TEST_INTERVAL * 10).is_err());
+    }
+
+    #[test]
+    fn test_dropped_watcher_stops_reporting() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        std::fs::write(&path, "original").unwrap();
+
+        let (watcher, receiver) = watch(&path);
+        drop(watcher);
+        std::fs::write(&path, "edited after closing").unwrap();
+
+        assert!(receiver.recv_timeout(TEST_INTERVAL * 10).is_err());
+    }
+
+    #[test]
+    fn test_dropped_watchers_exit_without_waiting_for_a_tick() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("resume.tex");
+        std::fs::write(&path, "original").unwrap();
+        let hour = Duration::from_secs(3600);
+
+        // The callbacks own the senders, so the channels close when the threads exit
+        let (sender, receiver) = mpsc::channel::<()>();
+        let file_watcher = FileWatcher::watch_with_interval(&path, hour, move |_| {
+            let _ = sender.send(());
+        });
+        let (dir_sender, dir_receiver) = mpsc::channel::<()>();
+        let dir_watcher = DirWatcher::watch_with_interval(temp_dir.path(), hour, move |_| {
+            let _ = dir_sender.send(());
+        });
+        drop(file_watcher);
+        drop(dir_watcher);
+
+        assert_eq!(
+            receiver.recv_timeout(TIMEOUT),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        );
+        assert_eq!(
+            dir_receiver.recv_timeout(TIMEOUT),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        );
+    }
+
+    fn watch_dir(dir: &Path) -> (DirWatcher, mpsc::Receiver<PathBuf>) {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = DirWatcher::watch_with_interval(dir, TEST_INTERVAL, move |changed| {
+            let _ = sender.send(changed.to_path_buf());
+        });
+        (watcher, receiver)
+    }
+
+    #[test]
+    fn test_new_file_in_dir_triggers_callback() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("classic.tex"), "classic").unwrap();
+
+        let (_watcher, receiver) = watch_dir(temp_dir.path());
+        std::fs::write(temp_dir.path().join("modern.tex"), "modern").unwrap();
+
+        assert_eq!(receiver.recv_timeout(TIMEOUT).unwrap(), temp_dir.path());
+    }
+
+    #[test]
+    fn test_burst_of_dir_changes_reported_once() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let (_watcher, receiver) = watch_dir(temp_dir.path());
+        for i in 0..5 {
+            std::fs::write(temp_dir.path().join(format!("t{}.tex", i)), "x").unwrap();
+        }
+
+        receiver.recv_timeout(TIMEOUT).unwrap();
+        assert!(receiver.recv_timeout(TEST_INTERVAL * 10).is_err());
+    }
+}
@@ -2,9 +2,15 @@
 //!
 //! This module handles the app's workspace directory structure:
 //! - `%LOCALAPPDATA%/ResumeIDE/` on Windows
-//! - Contains: settings.json, logs/, templates/, projects/
+//! - Contains: settings.json, logs/, templates/, projects/, cache/
 
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::settings::{corrupt_backup_path, load_settings_from, Settings};
+
+/// Directories under the workspace root that must exist
+const WORKSPACE_DIRS: [&str; 3] = ["projects", "templates", "logs"];
 
 /// Get the root workspace directory for the application
 /// Returns: `%LOCALAPPDATA%/ResumeIDE/` on Windows
@@ -30,8 +36,33 @@ pub fn get_logs_dir() -> Option<PathBuf> {
     get_workspace_root().map(|p| p.join("logs"))
 }
 
+/// Get the backups directory
+/// Returns: `<workspace_root>/logs/backups/`
+pub fn get_backups_dir() -> Option<PathBuf> {
+    get_logs_dir().map(|p| p.join("backups"))
+}
+
+/// Get the cache directory, for files that can be regenerated
+/// Returns: `<workspace_root>/cache/`
+pub fn get_cache_dir() -> Option<PathBuf> {
+    get_workspace_root().map(|p| p.join("cache"))
+}
+
+/// Get the settings file path
+/// Returns: `<workspace_root>/settings.json`
+pub fn get_settings_path() -> Option<PathBuf> {
+    get_workspace_root().map(|p| p.join("settings.json"))
+}
+
+/// Get the session file path
+/// Returns: `<workspace_root>/session.json`
+pub fn get_session_path() -> Option<PathBuf> {
+    get_workspace_root().map(|p| p.join("session.json"))
+}
+
 /// Initialize the workspace directory structure
-/// Creates all required directories if they don't exist
+/// Creates all required directories if they don't exist and seeds the
+/// bundled templates
 pub fn init_workspace() -> Result<PathBuf, std::io::Error> {
     let root = get_workspace_root().ok_or_else(|| {
         std::io::Error::new(
@@ -41,25 +72,98 @@ pub fn init_workspace() -> Result<PathBuf, std::io::Error> {
     })?;
 
     // Create all required directories
-    let dirs_to_create = [
-        root.clone(),
-        root.join("projects"),
-        root.join("templates"),
-        root.join("logs"),
-    ];
-
-    for dir in &dirs_to_create {
+    let dirs_to_create =
+        std::iter::once(root.clone()).chain(WORKSPACE_DIRS.iter().map(|dir| root.join(dir)));
+
+    for dir in dirs_to_create {
         if !dir.exists() {
             std::fs::create_dir_all(dir)?;
         }
     }
 
+    // Give new users something to start from
+    crate::templates::seed_templates_in(&root.join("templates"))?;
+
     Ok(root)
 }
 
+/// What `repair_workspace` fixed
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RepairReport {
+    pub root: String,
+    /// One line per fix, e.g. "Created projects/"; empty if nothing was wrong
+    pub repaired: Vec<String>,
+}
+
+/// Check the workspace structure and fix what's broken
+pub fn repair_workspace() -> Result<RepairReport, String> {
+    let root = get_workspace_root().ok_or("Could not determine local app data directory")?;
+    repair_workspace_in(&root)
+}
+
+/// Recreate missing workspace directories and templates under `root` and
+/// reset an unreadable `settings.json` to defaults, keeping a backup
+///
+/// A file sitting where a directory should be is moved aside first.
+pub fn repair_workspace_in(root: &Path) -> Result<RepairReport, String> {
+    let mut repaired = Vec::new();
+    fs::create_dir_all(root).map_err(|e| format!("Failed to create workspace: {}", e))?;
+
+    for name in WORKSPACE_DIRS {
+        let dir = root.join(name);
+        if dir.exists() && !dir.is_dir() {
+            let backup = corrupt_backup_path(&dir);
+            fs::rename(&dir, &backup)
+                .map_err(|e| format!("Failed to move {} aside: {}", dir.display(), e))?;
+            repaired.push(format!(
+                "Moved a file named {} to {}",
+                name,
+                backup.file_name().unwrap_or_default().to_string_lossy()
+            ));
+        }
+        if !dir.exists() {
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            repaired.push(format!("Created {}/", name));
+        }
+    }
+
+    if crate::templates::seed_templates_in(&root.join("templates"))
+        .map_err(|e| format!("Failed to restore templates: {}", e))?
+    {
+        repaired.push("Restored the bundled templates".to_string());
+    }
+
+    let settings_path = root.join("settings.json");
+    let existed = settings_path.exists();
+    let valid = fs::read_to_string(&settings_path)
+        .is_ok_and(|content| serde_json::from_str::<Settings>(&content).is_ok());
+    if !valid {
+        // Backs up an unparseable file and writes defaults
+        load_settings_from(&settings_path)?;
+        repaired.push(if existed {
+            format!(
+                "Reset settings.json to defaults; the old file was kept as {}",
+                corrupt_backup_path(&settings_path)
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            )
+        } else {
+            "Created settings.json".to_string()
+        });
+    }
+
+    Ok(RepairReport {
+        root: root.to_string_lossy().to_string(),
+        repaired,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
 
     #[test]
     fn test_get_workspace_root_returns_some() {
@@ -89,6 +193,14 @@ mod tests {
         assert!(templates.ends_with("templates"));
     }
 
+    #[test]
+    fn test_settings_path_is_under_workspace() {
+        let settings = get_settings_path().unwrap();
+        let root = get_workspace_root().unwrap();
+        assert!(settings.starts_with(&root));
+        assert!(settings.ends_with("settings.json"));
+    }
+
     #[test]
     fn test_logs_dir_is_under_workspace() {
         let logs = get_logs_dir().unwrap();
@@ -96,4 +208,70 @@ mod tests {
         assert!(logs.starts_with(&root));
         assert!(logs.ends_with("logs"));
     }
+
+    #[test]
+    fn test_cache_dir_is_under_workspace() {
+        let cache = get_cache_dir().unwrap();
+        assert!(cache.starts_with(get_workspace_root().unwrap()));
+        assert!(cache.ends_with("cache"));
+    }
+
+    #[test]
+    fn test_backups_dir_is_under_logs() {
+        let backups = get_backups_dir().unwrap();
+        assert!(backups.starts_with(get_logs_dir().unwrap()));
+        assert!(backups.ends_with("backups"));
+    }
+
+    #[test]
+    fn test_repair_recreates_missing_projects_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        repair_workspace_in(root).unwrap();
+        fs::remove_dir_all(root.join("projects")).unwrap();
+
+        let report = repair_workspace_in(root).unwrap();
+        assert_eq!(report.repaired, ["Created projects/"]);
+        assert!(root.join("projects").is_dir());
+
+        // A healthy workspace needs nothing
+        assert!(repair_workspace_in(root).unwrap().repaired.is_empty());
+    }
+
+    #[test]
+    fn test_repair_moves_file_blocking_a_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("logs"), "oops").unwrap();
+
+        let report = repair_workspace_in(root).unwrap();
+        assert!(report.repaired.contains(&"Created logs/".to_string()));
+        assert!(root.join("logs").is_dir());
+        assert_eq!(
+            fs::read_to_string(root.join("logs.corrupt")).unwrap(),
+            "oops"
+        );
+    }
+
+    #[test]
+    fn test_repair_resets_corrupt_settings_with_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        repair_workspace_in(root).unwrap();
+        fs::write(root.join("settings.json"), "{ not json").unwrap();
+
+        let report = repair_workspace_in(root).unwrap();
+        assert_eq!(
+            report.repaired,
+            ["Reset settings.json to defaults; the old file was kept as settings.json.corrupt"]
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("settings.json.corrupt")).unwrap(),
+            "{ not json"
+        );
+        assert_eq!(
+            load_settings_from(&root.join("settings.json")).unwrap(),
+            Settings::default()
+        );
+    }
 }
@@ -16,6 +16,7 @@ import { StartupDialog } from './components/StartupDialog';
 import {
   openFile,
   readFileByPath,
+  restoreSession,
   saveFile,
   saveFileAs,
   initWorkspace,
@@ -60,10 +61,19 @@ function App() {
         const debug = await debugPdflatex();
         setDebugInfo(debug);
 
-        // If all requirements are satisfied, skip landing page and show startup dialog
+        // If all requirements are satisfied, skip landing page and reopen the
+        // last file, or show the startup dialog if there is none
         if (status.all_satisfied) {
           setShowLanding(false);
-          setShowStartupDialog(true);
+          const restored = await restoreSession();
+          if (restored) {
+            setContent(restored.content);
+            setOriginalContent(restored.content);
+            setFilePath(restored.path);
+            setFileName(restored.name);
+          } else {
+            setShowStartupDialog(true);
+          }
         }
       } catch (error) {
         console.error('Failed to initialize:', error);
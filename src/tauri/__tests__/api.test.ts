@@ -6,6 +6,7 @@ import {
   saveFile,
   saveFileAs,
   getCurrentFile,
+  restoreSession,
   initWorkspace,
   compileLatex,
   checkRequirements,
@@ -34,7 +35,7 @@ describe('Tauri API', () => {
       expect(open).toHaveBeenCalledWith({
         multiple: false,
         filters: [
-          { name: 'LaTeX Files', extensions: ['tex'] },
+          { name: 'LaTeX Files', extensions: ['tex', 'ltx', 'latex'] },
           { name: 'All Files', extensions: ['*'] },
         ],
       });
@@ -107,6 +108,15 @@ describe('Tauri API', () => {
     });
   });
 
+  describe('restoreSession', () => {
+    it('should call session_restore command', async () => {
+      const result = await restoreSession();
+
+      expect(invoke).toHaveBeenCalledWith('session_restore');
+      expect(result).toBeNull();
+    });
+  });
+
   describe('compileLatex', () => {
     it('should call build_compile command', async () => {
       const result = await compileLatex();
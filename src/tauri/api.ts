@@ -7,17 +7,25 @@ import { open, save } from '@tauri-apps/plugin-dialog';
 export interface FileInfo {
   path: string;
   name: string;
+  /** File name without its extension */
+  stem: string;
+  /** Extension without the dot; empty if there is none */
+  extension: string;
   content: string;
+  /** Last modification on disk, in seconds since the Unix epoch */
+  modified: number | null;
+  /** Size on disk in bytes */
+  size: number;
 }
 
 /**
- * Open a file dialog and load the selected .tex file
+ * Open a file dialog and load the selected LaTeX file
  */
 export async function openFile(): Promise<FileInfo | null> {
   const selected = await open({
     multiple: false,
     filters: [
-      { name: 'LaTeX Files', extensions: ['tex'] },
+      { name: 'LaTeX Files', extensions: ['tex', 'ltx', 'latex'] },
       { name: 'All Files', extensions: ['*'] },
     ],
   });
@@ -33,9 +41,11 @@ export async function openFile(): Promise<FileInfo | null> {
 
 /**
  * Save the current file content
+ *
+ * Resolves to the content as saved, which may have been reformatted on save
  */
-export async function saveFile(content: string): Promise<void> {
-  return invoke('file_save', { content });
+export async function saveFile(content: string): Promise<string> {
+  return invoke<string>('file_save', { content });
 }
 
 /**
@@ -71,6 +81,13 @@ export async function getCurrentFile(): Promise<string | null> {
   return invoke<string | null>('file_get_current');
 }
 
+/**
+ * Reopen the file that was active when the app last closed, if it still exists
+ */
+export async function restoreSession(): Promise<FileInfo | null> {
+  return invoke<FileInfo | null>('session_restore');
+}
+
 /**
  * Initialize the workspace
  */
@@ -78,15 +95,29 @@ export async function initWorkspace(): Promise<string> {
   return invoke<string>('workspace_init');
 }
 
+/**
+ * Why a build failed
+ */
+export type BuildErrorKind =
+  | 'tool_missing'
+  | 'timeout'
+  | 'compile_error'
+  | 'io_error'
+  | 'cancelled';
+
 /**
  * Result of a LaTeX compilation
  */
 export interface BuildResult {
   success: boolean;
   pdf_path: string | null;
+  build_pdf_path: string | null;
   log: string;
+  stdout: string;
+  stderr: string;
   duration_ms: number;
   error_message: string | null;
+  error_kind: BuildErrorKind | null;
 }
 
 /**
@@ -103,6 +134,10 @@ export interface RequirementsStatus {
   pdflatex_available: boolean;
   pdflatex_path: string | null;
   all_satisfied: boolean;
+  /** How to install TeX on this platform, when pdflatex is missing */
+  remediation?: string | null;
+  /** Where to download TeX for this platform, when pdflatex is missing */
+  download_url?: string | null;
 }
 
 /**
@@ -23,6 +23,7 @@ const mockResponses: Record<string, unknown> = {
     content: 'saved content',
   },
   file_get_current: '/mock/path/resume.tex',
+  session_restore: null,
   build_compile: {
     success: true,
     pdf_path: '/mock/path/output.pdf',